@@ -0,0 +1,77 @@
+use crate::{illuminant_type, measurement_geometry, standard_observer};
+
+/// Defines an enum stored as a `u32` in tags from the constants of its values, along with its conversions.
+/// Values without a constant are kept as `Other`.
+macro_rules! encoded_enum {
+    ($(#[$meta:meta])* $name:ident { $($(#[$variant_meta:meta])* $variant:ident = $value:path,)* }) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// Value without a constant, as found in the tag
+            Other(u32),
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name::Unknown
+            }
+        }
+
+        impl From<$name> for u32 {
+            fn from(value: $name) -> Self {
+                match value {
+                    $($name::$variant => $value,)*
+                    $name::Other(value) => value,
+                }
+            }
+        }
+
+        impl From<u32> for $name {
+            fn from(value: u32) -> Self {
+                match value {
+                    $($value => $name::$variant,)*
+                    _ => $name::Other(value),
+                }
+            }
+        }
+    };
+}
+
+encoded_enum! {
+    /// Standard observer of measurements, with the values of `standard_observer`
+    StandardObserver {
+        Unknown = standard_observer::UNKNOWN,
+        /// CIE 1931 2° observer
+        Cie1931 = standard_observer::CIE_1931,
+        /// CIE 1964 10° observer
+        Cie1964 = standard_observer::CIE_1964,
+    }
+}
+
+encoded_enum! {
+    /// Measurement geometry, with the values of `measurement_geometry`
+    MeasurementGeometry {
+        Unknown = measurement_geometry::UNKNOWN,
+        /// 0°:45° or 45°:0°
+        ZeroFortyFive = measurement_geometry::ZERO_FORTY_FIVE,
+        /// 0°:d or d:0°
+        ZeroDiffuse = measurement_geometry::ZERO_DIFFUSE,
+    }
+}
+
+encoded_enum! {
+    /// Standard illuminants of measurements and viewing conditions, with the values of `illuminant_type`
+    IlluminantType {
+        Unknown = illuminant_type::UNKNOWN,
+        D50 = illuminant_type::D50,
+        D65 = illuminant_type::D65,
+        D93 = illuminant_type::D93,
+        F2 = illuminant_type::F2,
+        D55 = illuminant_type::D55,
+        A = illuminant_type::A,
+        /// Equi-power illuminant
+        E = illuminant_type::E,
+        F8 = illuminant_type::F8,
+    }
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+use std::io::Result;
+use std::sync::{Arc, OnceLock};
+
+use crate::plugin::registry::Registry;
+use crate::plugin::{CustomTag, IoHandler, PluginBase};
+use crate::tag_types::{self, TagData, TagDescriptor};
+use crate::Signature;
+
+/// Plugins used by the profiles and transforms created with it. Contexts are cheap to clone, and plugins
+/// registered on a clone leave the original untouched, so each part of a program can have its own set.
+#[derive(Clone)]
+pub struct Context {
+    plugins: Arc<Registry>,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Context {
+    /// Context without plugins
+    pub fn new() -> Self {
+        // Contexts without plugins all share the same registry
+        static EMPTY: OnceLock<Arc<Registry>> = OnceLock::new();
+        Self {
+            plugins: EMPTY.get_or_init(Arc::default).clone(),
+        }
+    }
+
+    /// Context with a chain of plugins registered
+    pub fn with_plugins(plugin: &PluginBase) -> Result<Self> {
+        let mut result = Self::new();
+        result.register_plugins(plugin)?;
+        Ok(result)
+    }
+
+    /// Registers a chain of plugins. Plugins need a valid magic number, a type matching what they provide and
+    /// a library version no newer than `LCMS_VERSION`. Nothing is registered if any of them is invalid.
+    pub fn register_plugins(&mut self, plugin: &PluginBase) -> Result<()> {
+        for p in plugin.iter() {
+            Registry::check(p)?;
+        }
+        Arc::make_mut(&mut self.plugins).register(plugin);
+        Ok(())
+    }
+
+    /// Forgets all the plugins registered
+    pub fn unregister_plugins(&mut self) {
+        *self = Self::new();
+    }
+
+    pub(crate) fn plugins(&self) -> &Registry {
+        &self.plugins
+    }
+
+    /// What a tag may hold, tags registered by plugins first
+    pub fn tag_descriptor(&self, sig: Signature) -> Option<TagDescriptor> {
+        self.plugins
+            .tag_descriptor(sig)
+            .or_else(|| tag_types::tag_descriptor(sig))
+    }
+
+    /// Whether there is a handler for the given tag type, built in or registered
+    pub fn is_type_supported(&self, type_sig: Signature) -> bool {
+        self.plugins.tag_type_handler(type_sig).is_some() || tag_types::is_type_supported(type_sig)
+    }
+
+    /// Reads the payload of a tag of the given type, with the handlers registered by plugins first
+    pub fn read_tag_type(
+        &self,
+        type_sig: Signature,
+        io: &mut dyn IoHandler,
+        size_of_tag: u32,
+    ) -> Result<TagData> {
+        match self.plugins.tag_type_handler(type_sig) {
+            Some(handler) => Ok(TagData::Custom(CustomTag::read(handler, io, size_of_tag)?)),
+            None => tag_types::read_tag_type_with_context(self, type_sig, io, size_of_tag),
+        }
+    }
+}
+
+/// Contexts are equal if they share the same plugins
+impl PartialEq for Context {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.plugins, &other.plugins)
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context").finish_non_exhaustive()
+    }
+}
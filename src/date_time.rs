@@ -0,0 +1,39 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::DateTimeNumber;
+
+impl DateTimeNumber {
+    /// Current UTC date and time
+    pub fn now() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self::from_unix_time(secs)
+    }
+
+    /// Converts seconds since the unix epoch to a UTC date and time
+    pub fn from_unix_time(secs: u64) -> Self {
+        let days = (secs / 86400) as i64;
+        let rem = secs % 86400;
+
+        // Days to civil date, from Howard Hinnant's date algorithms
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self {
+            year: year as u16,
+            month: month as u16,
+            day: day as u16,
+            hours: (rem / 3600) as u16,
+            minutes: ((rem % 3600) / 60) as u16,
+            seconds: (rem % 60) as u16,
+        }
+    }
+}
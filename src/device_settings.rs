@@ -0,0 +1,31 @@
+use crate::Signature;
+
+/// A single setting and its possible values. Values are kept as the raw bytes of the tag, `value_size` bytes
+/// each.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct DeviceSetting {
+    /// Such as `rsln` for resolution, `mdia` for media type or `hfto` for halftoning
+    pub id: Signature,
+    pub value_size: u32,
+    pub data: Vec<u8>,
+}
+
+impl DeviceSetting {
+    /// Values of the setting, incomplete trailing bytes left out
+    pub fn values(&self) -> impl Iterator<Item = &[u8]> {
+        self.data.chunks_exact(self.value_size.max(1) as usize)
+    }
+}
+
+/// Settings for a single platform, as groups of settings that go together
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct DevicePlatform {
+    pub platform: Signature,
+    pub combinations: Vec<Vec<DeviceSetting>>,
+}
+
+/// Device settings the profile was built with, as stored by the `DEVICE_SETTINGS` tag
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct DeviceSettings {
+    pub platforms: Vec<DevicePlatform>,
+}
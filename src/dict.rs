@@ -0,0 +1,61 @@
+use crate::Mlu;
+
+/// A single entry of a dictionary. Display names and values are localized versions of the name and value meant
+/// to be shown to users.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DictEntry {
+    pub name: String,
+    pub value: Option<String>,
+    pub display_name: Option<Mlu>,
+    pub display_value: Option<Mlu>,
+}
+
+/// Ordered list of name/value pairs, as stored by the `dict` tag type in the metadata tag
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Dict {
+    entries: Vec<DictEntry>,
+}
+
+impl Dict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds an entry at the end of the dictionary. Names aren't required to be unique.
+    pub fn add(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+        display_name: Option<Mlu>,
+        display_value: Option<Mlu>,
+    ) {
+        self.entries.push(DictEntry {
+            name: name.to_string(),
+            value: value.map(|v| v.to_string()),
+            display_name,
+            display_value,
+        });
+    }
+
+    /// First entry with the given name
+    pub fn get(&self, name: &str) -> Option<&DictEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Value of the first entry with the given name
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(|e| e.value.as_deref())
+    }
+
+    pub fn entries(&self) -> &[DictEntry] {
+        &self.entries
+    }
+}
@@ -0,0 +1,78 @@
+use crate::Context;
+
+/// Rendering intent of a transform. Codes other than the four ICC intents are provided by plugins.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Intent {
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+    /// Intent registered by a plugin, with a code other than the ones above
+    Custom(u32),
+}
+
+/// ICC intents and their descriptions
+const BUILT_IN: [(Intent, &str); 4] = [
+    (Intent::Perceptual, "Perceptual"),
+    (Intent::RelativeColorimetric, "Relative colorimetric"),
+    (Intent::Saturation, "Saturation"),
+    (Intent::AbsoluteColorimetric, "Absolute colorimetric"),
+];
+
+impl Intent {
+    /// Intents transforms of `context` can use, with their descriptions, ordered by code. Plugins may give
+    /// their own descriptions to the ICC intents.
+    pub fn supported(context: &Context) -> Vec<(Intent, &'static str)> {
+        let mut result: Vec<(Intent, &'static str)> = Vec::new();
+        let registered = context
+            .plugins()
+            .intents()
+            .map(|i| (Intent::from(i.intent), i.description));
+
+        for (intent, description) in registered.chain(BUILT_IN.iter().copied()) {
+            if result
+                .iter()
+                .all(|&(known, _)| u32::from(known) != u32::from(intent))
+            {
+                result.push((intent, description));
+            }
+        }
+        result.sort_by_key(|&(intent, _)| u32::from(intent));
+        result
+    }
+
+    /// Description of the intent, if `context` supports it
+    pub fn description(self, context: &Context) -> Option<&'static str> {
+        match context.plugins().intent(self.into()) {
+            Some(registered) => Some(registered.description),
+            None => BUILT_IN
+                .iter()
+                .find(|&&(intent, _)| u32::from(intent) == u32::from(self))
+                .map(|&(_, description)| description),
+        }
+    }
+}
+
+impl From<u32> for Intent {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => Intent::Perceptual,
+            1 => Intent::RelativeColorimetric,
+            2 => Intent::Saturation,
+            3 => Intent::AbsoluteColorimetric,
+            other => Intent::Custom(other),
+        }
+    }
+}
+
+impl From<Intent> for u32 {
+    fn from(value: Intent) -> Self {
+        match value {
+            Intent::Perceptual => 0,
+            Intent::RelativeColorimetric => 1,
+            Intent::Saturation => 2,
+            Intent::AbsoluteColorimetric => 3,
+            Intent::Custom(code) => code,
+        }
+    }
+}
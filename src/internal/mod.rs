@@ -1 +1,28 @@
 pub const MATRIX_DET_TOLERANCE: f64 = 0.0001;
+pub const MAX_TABLE_TAG: u32 = 100;
+
+/// Rounds and clamps to the 16 bit range
+pub fn quick_saturate_word(d: f64) -> u16 {
+    let d = d + 0.5;
+    if d <= 0.0 {
+        0
+    } else if d >= 65535.0 {
+        0xffff
+    } else {
+        d.floor() as u16
+    }
+}
+
+/// Value of the i-th of `max_samples` points evenly spread over the 16 bit range
+pub fn quantize_val(i: f64, max_samples: u32) -> u16 {
+    let x = (i * 65535.0) / (max_samples - 1) as f64;
+    quick_saturate_word(x)
+}
+
+pub fn from_8_to_16(rgb: u8) -> u16 {
+    ((rgb as u16) << 8) | rgb as u16
+}
+
+pub fn from_16_to_8(rgb: u16) -> u8 {
+    (((rgb as u32 * 65281 + 8388608) >> 24) & 0xff) as u8
+}
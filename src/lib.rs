@@ -9,17 +9,18 @@ pub type U16F16 = u32;
 mod signature;
 pub use signature::Signature;
 
-pub const USE_BIG_ENDIAN: bool = if cfg!(BIG_ENDIAN = "true") {
-    true
-} else {
-    false
-};
+pub const USE_BIG_ENDIAN: bool = cfg!(BIG_ENDIAN = "true");
+
+/// Maximum number of channels in ICC profiles
+pub const MAX_CHANNELS: usize = 16;
 
 /// D50 XYZ normalized to Y=1.0
 pub mod d50 {
     pub const X: f64 = 0.9642;
     pub const Y: f64 = 1.0;
     pub const Z: f64 = 0.8249;
+
+    pub const XYZ: crate::CIEXYZ = crate::CIEXYZ { X, Y, Z };
 }
 
 /// V4 perceptual black
@@ -39,14 +40,23 @@ pub mod device_attributes {
     pub const MATTE: u32 = 2;
 }
 
-/// Common structures in ICC tags
+// Common structures in ICC tags
+
+/// Contents of the `DATA` tag type
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct ICCData {
-    pub length: u32,
+    /// `ICCData::ASCII` or `ICCData::BINARY`
     pub flag: u32,
-    pub data: [u8],
+    pub data: Vec<u8>,
+}
+
+impl ICCData {
+    pub const ASCII: u32 = 0;
+    pub const BINARY: u32 = 1;
 }
 
 /// ICC date time
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 pub struct DateTimeNumber {
     pub year: u16,
     pub month: u16,
@@ -57,6 +67,7 @@ pub struct DateTimeNumber {
 }
 
 /// ICC XYZ
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 pub struct EncodedXYZNumber {
     pub x: S15F16,
     pub y: S15F16,
@@ -159,6 +170,7 @@ pub enum ColorSpace {
 }
 
 #[allow(non_snake_case)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct CIEXYZ {
     pub X: f64,
     pub Y: f64,
@@ -166,6 +178,7 @@ pub struct CIEXYZ {
 }
 
 #[allow(non_snake_case)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct CIExyY {
     pub x: f64,
     pub y: f64,
@@ -173,6 +186,7 @@ pub struct CIExyY {
 }
 
 #[allow(non_snake_case)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct CIELab {
     pub L: f64,
     pub a: f64,
@@ -193,30 +207,75 @@ pub struct CIEJCh {
     pub h: f64,
 }
 
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct CIEXYZTriple {
     pub red: CIEXYZ,
     pub green: CIEXYZ,
     pub blue: CIEXYZ,
 }
 
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct CIExyYTripple {
     pub red: CIExyY,
     pub green: CIExyY,
     pub blue: CIExyY,
 }
 
+/// Chromaticities of the primaries of a device, as stored by the `CHROMATICITY` tag
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Chromaticity {
+    /// One of `colorant_type`, the standard the primaries come from
+    pub colorant_type: u16,
+    /// Chromaticities of the red, green and blue primaries, with a luminance of 1.0
+    pub primaries: CIExyYTripple,
+}
+
+/// Phosphor or colorant types of the `CHROMATICITY` tag
+pub mod colorant_type {
+    pub const UNKNOWN: u16 = 0;
+    pub const ITU_R_BT709: u16 = 1;
+    pub const SMPTE_RP145: u16 = 2;
+    pub const EBU_TECH_3213_E: u16 = 3;
+    pub const P22: u16 = 4;
+}
+
+mod conditions;
+pub use conditions::{IlluminantType, MeasurementGeometry, StandardObserver};
+
+/// Conditions colors were measured under, as stored by the `MEASUREMENT` tag
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct ICCMeasurementConditions {
-    pub observer: u32,
+    pub observer: StandardObserver,
+    /// Absolute XYZ of the measurement backing
     pub backing: CIEXYZ,
-    pub geometry: u32,
+    pub geometry: MeasurementGeometry,
+    /// Measurement flare, 0 to 1
     pub flare: f64,
-    pub illuminant_type: u32,
+    pub illuminant_type: IlluminantType,
 }
 
+/// Viewing conditions, as stored by the `VIEWING_CONDITIONS` tag
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
 pub struct ICCViewingConditions {
+    /// Absolute XYZ of the illuminant, in cd/m²
     pub illuminant_xyz: CIEXYZ,
+    /// Absolute XYZ of the surround, in cd/m²
     pub surround_xyz: CIEXYZ,
-    pub illuminant_type: u32,
+    pub illuminant_type: IlluminantType,
+}
+
+/// Standard observers of the `MEASUREMENT` tag
+pub mod standard_observer {
+    pub const UNKNOWN: u32 = 0;
+    pub const CIE_1931: u32 = 1;
+    pub const CIE_1964: u32 = 2;
+}
+
+/// Measurement geometries of the `MEASUREMENT` tag
+pub mod measurement_geometry {
+    pub const UNKNOWN: u32 = 0;
+    pub const ZERO_FORTY_FIVE: u32 = 1;
+    pub const ZERO_DIFFUSE: u32 = 2;
 }
 
 pub mod illuminant_type {
@@ -231,6 +290,52 @@ pub mod illuminant_type {
     pub const F8: u32 = 8;
 }
 
+/// Screening flags of the `SCREENING` tag
+pub mod screening_flags {
+    pub const PRINTER_DEFAULT_SCREENS: u32 = 0x0001;
+    pub const FREQUENCY_UNITS_LPI: u32 = 0x0000;
+    pub const FREQUENCY_UNITS_LPCM: u32 = 0x0002;
+}
+
+/// Spot shapes of the `SCREENING` tag
+pub mod spot_shape {
+    pub const UNKNOWN: u32 = 0;
+    pub const PRINTER_DEFAULT: u32 = 1;
+    pub const ROUND: u32 = 2;
+    pub const DIAMOND: u32 = 3;
+    pub const ELLIPSE: u32 = 4;
+    pub const LINE: u32 = 5;
+    pub const SQUARE: u32 = 6;
+    pub const CROSS: u32 = 7;
+}
+
+/// Halftone screen of a single channel
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct ScreeningChannel {
+    /// Lines per inch or per centimeter, depending on the screening flags
+    pub frequency: f64,
+    /// Angle in degrees
+    pub screen_angle: f64,
+    /// One of `spot_shape`
+    pub spot_shape: u32,
+}
+
+/// Halftone screening, as stored by the `SCREENING` tag
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Screening {
+    /// Combination of `screening_flags`
+    pub flag: u32,
+    pub channels: Vec<ScreeningChannel>,
+}
+
+/// Under color removal and black generation, as stored by the `UCR_BG` tag
+#[derive(Clone, PartialEq, Debug)]
+pub struct UcrBg {
+    pub ucr: ToneCurve,
+    pub bg: ToneCurve,
+    pub desc: Mlu,
+}
+
 #[allow(non_snake_case)]
 pub struct ViewingConditions {
     pub white_point: CIEXYZ,
@@ -241,17 +346,64 @@ pub struct ViewingConditions {
 }
 
 /// Tone curves
-/// 
-/// This describes a curve segment. Users can increase the nuber of available types by using a proper plug-in.
-/// Parametric segments allow 10 parameters at most
+///
+/// This describes a curve segment. Sampled segments have a type of zero and use `sampled_points`, spread evenly
+/// over the segment. Parametric segments allow 10 parameters at most.
+#[derive(Clone, PartialEq, Debug)]
 pub struct CurveSegment {
     pub x0: f32,
     pub x1: f32,
     pub r#type: i32,
     pub params: [f64; 10],
     pub n_grid_points: u32,
-    pub sampled_points: [f32]
+    pub sampled_points: Vec<f32>,
 }
 
 pub mod plugin;
 mod internal;
+
+mod context;
+pub use context::Context;
+mod pcs;
+pub use pcs::{lab_to_xyz, xyz_to_lab};
+mod date_time;
+
+mod dict;
+pub use dict::{Dict, DictEntry};
+
+mod mlu;
+pub use mlu::{Mlu, NO_COUNTRY, NO_LANGUAGE};
+
+mod named_color;
+pub use named_color::{NamedColor, NamedColorList};
+
+mod device_settings;
+pub use device_settings::{DevicePlatform, DeviceSetting, DeviceSettings};
+
+mod response_curve;
+pub use response_curve::{Response16, ResponseCurve, ResponseCurveSet};
+
+mod profile;
+pub use profile::{InfoType, Profile, ProfileSequenceDesc};
+
+mod tone_curve;
+pub use tone_curve::{ToneCurve, MINUS_INF, PLUS_INF};
+
+/// Pipelines of processing stages
+pub mod pipeline;
+pub use pipeline::{Pipeline, Stage, StageLoc};
+
+/// ICC tag type readers and writers
+pub mod tag_types;
+
+mod intent;
+pub use intent::Intent;
+
+mod transform;
+pub use transform::Transform;
+
+/// Chromatic adaptation and white point utilities
+pub mod white_point;
+
+#[cfg(test)]
+mod tests;
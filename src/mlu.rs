@@ -0,0 +1,150 @@
+/// Code used for entries that are not tied to a language or country, as in v2 text tags
+pub const NO_LANGUAGE: &str = "\0\0";
+/// Code used for entries that are not tied to a language or country, as in v2 text tags
+pub const NO_COUNTRY: &str = "\0\0";
+
+fn str_to_code(value: &str) -> [u8; 2] {
+    let bytes = value.as_bytes();
+    let mut result = [0u8; 2];
+    for (dst, src) in result.iter_mut().zip(bytes) {
+        *dst = *src;
+    }
+    result
+}
+
+fn code_to_string(value: [u8; 2]) -> String {
+    String::from_utf8_lossy(&value).into_owned()
+}
+
+/// Builds an MLU holding a single string that is not tied to any language
+impl From<&str> for Mlu {
+    fn from(text: &str) -> Self {
+        let mut result = Self::new();
+        result.set(NO_LANGUAGE, NO_COUNTRY, text);
+        result
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct MluEntry {
+    language: [u8; 2],
+    country: [u8; 2],
+    text: Vec<u16>,
+}
+
+/// Multi-localized unicode strings. Each translation is identified by an ISO 639-1 language code and an
+/// ISO 3166-1 country code, and is kept as UTF-16 as stored in ICC profiles.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Mlu {
+    entries: Vec<MluEntry>,
+}
+
+impl Mlu {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sets the string for a language and country, replacing any existing one
+    pub fn set(&mut self, language: &str, country: &str, text: &str) {
+        self.set_utf16(
+            language,
+            country,
+            &text.encode_utf16().collect::<Vec<u16>>(),
+        );
+    }
+
+    /// Sets the UTF-16 string for a language and country, replacing any existing one
+    pub fn set_utf16(&mut self, language: &str, country: &str, text: &[u16]) {
+        let (language, country) = (str_to_code(language), str_to_code(country));
+
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.language == language && e.country == country)
+        {
+            Some(entry) => entry.text = text.to_vec(),
+            None => self.entries.push(MluEntry {
+                language,
+                country,
+                text: text.to_vec(),
+            }),
+        }
+    }
+
+    /// Finds the best match for a language and country. Falls back to the first entry of the same language
+    /// and then to the first entry of all.
+    fn find(&self, language: &str, country: &str) -> Option<&MluEntry> {
+        let (language, country) = (str_to_code(language), str_to_code(country));
+        let mut best = None;
+
+        for entry in self.entries.iter() {
+            if entry.language == language {
+                if entry.country == country {
+                    return Some(entry);
+                }
+                best = best.or(Some(entry));
+            }
+        }
+
+        best.or_else(|| self.entries.first())
+    }
+
+    /// Gets the translation for a language and country, with fallback
+    pub fn get(&self, language: &str, country: &str) -> Option<String> {
+        self.get_utf16(language, country).map(|text| {
+            String::from_utf16_lossy(text)
+                .trim_end_matches('\0')
+                .to_string()
+        })
+    }
+
+    /// Gets the UTF-16 translation for a language and country, with fallback
+    pub fn get_utf16(&self, language: &str, country: &str) -> Option<&[u16]> {
+        self.find(language, country).map(|e| e.text.as_slice())
+    }
+
+    /// Gets the translation as ASCII, replacing anything outside of it with '?'
+    pub fn get_ascii(&self, language: &str, country: &str) -> Option<String> {
+        self.get(language, country).map(|text| {
+            text.chars()
+                .map(|c| if c.is_ascii() { c } else { '?' })
+                .collect()
+        })
+    }
+
+    /// Language and country codes that `get` would use for the given request
+    pub fn translation_codes(&self, language: &str, country: &str) -> Option<(String, String)> {
+        self.find(language, country)
+            .map(|e| (code_to_string(e.language), code_to_string(e.country)))
+    }
+
+    /// Language and country codes of all the entries
+    pub fn translations(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.entries
+            .iter()
+            .map(|e| (code_to_string(e.language), code_to_string(e.country)))
+    }
+
+    /// Raw entries as (language, country, UTF-16 text)
+    pub(crate) fn entries(&self) -> impl Iterator<Item = ([u8; 2], [u8; 2], &[u16])> {
+        self.entries
+            .iter()
+            .map(|e| (e.language, e.country, e.text.as_slice()))
+    }
+
+    pub(crate) fn push_entry(&mut self, language: [u8; 2], country: [u8; 2], text: Vec<u16>) {
+        self.entries.push(MluEntry {
+            language,
+            country,
+            text,
+        })
+    }
+}
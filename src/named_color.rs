@@ -0,0 +1,87 @@
+use crate::MAX_CHANNELS;
+
+/// A single color of a named color list
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NamedColor {
+    pub name: String,
+    /// PCS coordinates, in the 16 bit encoding of the profile connection space
+    pub pcs: [u16; 3],
+    /// Device coordinates, one per colorant of the list
+    pub device_colorant: Vec<u16>,
+}
+
+/// List of named colors, such as a spot color library. Full names of colors are made of the prefix, the name of
+/// each color and the suffix.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct NamedColorList {
+    colorant_count: usize,
+    prefix: String,
+    suffix: String,
+    colors: Vec<NamedColor>,
+}
+
+impl NamedColorList {
+    /// Empty list whose colors have `colorant_count` device coordinates. Returns `None` if there are more than
+    /// `MAX_CHANNELS` of them.
+    pub fn new(colorant_count: usize, prefix: &str, suffix: &str) -> Option<Self> {
+        if colorant_count > MAX_CHANNELS {
+            return None;
+        }
+
+        Some(Self {
+            colorant_count,
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+            colors: Vec::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    pub fn colorant_count(&self) -> usize {
+        self.colorant_count
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn suffix(&self) -> &str {
+        &self.suffix
+    }
+
+    /// Adds a color at the end of the list. Missing device coordinates are set to 0, and extra ones ignored.
+    pub fn append(&mut self, name: &str, pcs: [u16; 3], device_colorant: &[u16]) {
+        let mut colorant = vec![0u16; self.colorant_count];
+        for (dst, src) in colorant.iter_mut().zip(device_colorant) {
+            *dst = *src;
+        }
+
+        self.colors.push(NamedColor {
+            name: name.to_string(),
+            pcs,
+            device_colorant: colorant,
+        });
+    }
+
+    pub fn get(&self, index: usize) -> Option<&NamedColor> {
+        self.colors.get(index)
+    }
+
+    pub fn colors(&self) -> &[NamedColor] {
+        &self.colors
+    }
+
+    /// Index of the color with the given name, ignoring case
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.colors
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(name))
+    }
+}
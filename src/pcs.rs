@@ -0,0 +1,94 @@
+use crate::plugin::{f64_to_s15f16, s15f16_to_f64};
+use crate::{d50, CIELab, CIExyY, EncodedXYZNumber, CIEXYZ};
+
+impl From<EncodedXYZNumber> for CIEXYZ {
+    fn from(value: EncodedXYZNumber) -> Self {
+        Self {
+            X: s15f16_to_f64(value.x),
+            Y: s15f16_to_f64(value.y),
+            Z: s15f16_to_f64(value.z),
+        }
+    }
+}
+
+impl From<CIEXYZ> for EncodedXYZNumber {
+    fn from(value: CIEXYZ) -> Self {
+        Self {
+            x: f64_to_s15f16(value.X),
+            y: f64_to_s15f16(value.Y),
+            z: f64_to_s15f16(value.Z),
+        }
+    }
+}
+
+impl From<CIExyY> for CIEXYZ {
+    fn from(value: CIExyY) -> Self {
+        Self {
+            X: (value.x / value.y) * value.Y,
+            Y: value.Y,
+            Z: ((1.0 - value.x - value.y) / value.y) * value.Y,
+        }
+    }
+}
+
+impl From<CIEXYZ> for CIExyY {
+    fn from(value: CIEXYZ) -> Self {
+        let i_sum = 1.0 / (value.X + value.Y + value.Z);
+
+        Self {
+            x: value.X * i_sum,
+            y: value.Y * i_sum,
+            Y: value.Y,
+        }
+    }
+}
+
+fn f(t: f64) -> f64 {
+    const LIMIT: f64 = (24.0 / 116.0) * (24.0 / 116.0) * (24.0 / 116.0);
+
+    if t <= LIMIT {
+        (841.0 / 108.0) * t + (16.0 / 116.0)
+    } else {
+        t.powf(1.0 / 3.0)
+    }
+}
+
+fn f_1(t: f64) -> f64 {
+    const LIMIT: f64 = 24.0 / 116.0;
+
+    if t <= LIMIT {
+        (108.0 / 841.0) * (t - (16.0 / 116.0))
+    } else {
+        t * t * t
+    }
+}
+
+/// Converts XYZ to Lab relative to the given white point, D50 if not given
+pub fn xyz_to_lab(white_point: Option<CIEXYZ>, xyz: CIEXYZ) -> CIELab {
+    let white_point = white_point.unwrap_or(d50::XYZ);
+
+    let fx = f(xyz.X / white_point.X);
+    let fy = f(xyz.Y / white_point.Y);
+    let fz = f(xyz.Z / white_point.Z);
+
+    CIELab {
+        L: 116.0 * fy - 16.0,
+        a: 500.0 * (fx - fy),
+        b: 200.0 * (fy - fz),
+    }
+}
+
+/// Converts Lab relative to the given white point, D50 if not given, to XYZ
+pub fn lab_to_xyz(white_point: Option<CIEXYZ>, lab: CIELab) -> CIEXYZ {
+    let white_point = white_point.unwrap_or(d50::XYZ);
+
+    let y = (lab.L + 16.0) / 116.0;
+    let x = y + 0.002 * lab.a;
+    let z = y - 0.005 * lab.b;
+
+    CIEXYZ {
+        X: f_1(x) * white_point.X,
+        Y: f_1(y) * white_point.Y,
+        Z: f_1(z) * white_point.Z,
+    }
+}
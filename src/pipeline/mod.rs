@@ -0,0 +1,234 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::internal::quick_saturate_word;
+use crate::plugin::MAX_STAGE_CHANNELS;
+use crate::{Context, Signature, MAX_CHANNELS};
+
+mod optimize;
+mod stage;
+pub use stage::{Stage, StageData};
+
+#[cfg(test)]
+mod tests;
+
+/// Where to insert or remove a stage
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StageLoc {
+    AtBegin,
+    AtEnd,
+}
+
+type Eval16Fn = dyn Fn(&[u16], &mut [u16]) + Send + Sync;
+
+/// 16 bit evaluator set by an optimization, standing for the stages. Pipelines sharing one are equal.
+#[derive(Clone)]
+struct OptimizedEval(Arc<Eval16Fn>);
+
+impl PartialEq for OptimizedEval {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for OptimizedEval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OptimizedEval").finish_non_exhaustive()
+    }
+}
+
+/// A chain of stages converting `input_channels` values into `output_channels` values
+#[derive(Clone, PartialEq, Debug)]
+pub struct Pipeline {
+    input_channels: usize,
+    output_channels: usize,
+    stages: Vec<Stage>,
+    save_as_8_bits: bool,
+    optimized_16: Option<OptimizedEval>,
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline. Returns `None` if a channel count is out of range.
+    pub fn new(input_channels: usize, output_channels: usize) -> Option<Self> {
+        if input_channels > MAX_CHANNELS || output_channels > MAX_CHANNELS {
+            return None;
+        }
+        Some(Self {
+            input_channels,
+            output_channels,
+            stages: Vec::new(),
+            save_as_8_bits: false,
+            optimized_16: None,
+        })
+    }
+
+    pub fn input_channels(&self) -> usize {
+        self.input_channels
+    }
+
+    pub fn output_channels(&self) -> usize {
+        self.output_channels
+    }
+
+    /// Makes the CLUTs of the pipeline use the interpolation routines registered in `context`, if any
+    pub fn use_interpolators(&mut self, context: &Context) {
+        for stage in &mut self.stages {
+            stage.use_interpolators(context);
+        }
+    }
+
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    pub fn stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Whether the stages have exactly the given types, in order
+    pub fn has_stage_types(&self, types: &[Signature]) -> bool {
+        self.stages.len() == types.len()
+            && self
+                .stages
+                .iter()
+                .zip(types)
+                .all(|(stage, t)| stage.stage_type() == *t)
+    }
+
+    /// Whether CLUTs should be stored with 8 bit precision
+    pub fn save_as_8_bits(&self) -> bool {
+        self.save_as_8_bits
+    }
+
+    pub fn set_save_as_8_bits(&mut self, value: bool) {
+        self.save_as_8_bits = value;
+    }
+
+    pub fn first_stage(&self) -> Option<&Stage> {
+        self.stages.first()
+    }
+
+    pub fn last_stage(&self) -> Option<&Stage> {
+        self.stages.last()
+    }
+
+    /// Makes `eval_16` call `eval` instead of going through the stages, until they change. Meant for optimization
+    /// passes, `eval` having to give the same results as the stages, give or take the precision of the formats
+    /// optimized for.
+    pub fn set_optimized_eval_16<F>(&mut self, eval: F)
+    where
+        F: Fn(&[u16], &mut [u16]) + Send + Sync + 'static,
+    {
+        self.optimized_16 = Some(OptimizedEval(Arc::new(eval)));
+    }
+
+    /// Whether `eval_16` uses an evaluator set by an optimization
+    pub fn has_optimized_eval_16(&self) -> bool {
+        self.optimized_16.is_some()
+    }
+
+    /// Updates the channel counts after the stages changed, checking they chain together
+    fn bless(&mut self) -> bool {
+        if let (Some(first), Some(last)) = (self.stages.first(), self.stages.last()) {
+            if self
+                .stages
+                .windows(2)
+                .any(|w| w[0].output_channels() != w[1].input_channels())
+            {
+                return false;
+            }
+            self.input_channels = first.input_channels();
+            self.output_channels = last.output_channels();
+        }
+        self.optimized_16 = None;
+        true
+    }
+
+    /// Adds a stage at either end. Returns false, leaving the pipeline untouched, if the channels don't match.
+    pub fn insert_stage(&mut self, loc: StageLoc, stage: Stage) -> bool {
+        match loc {
+            StageLoc::AtBegin => self.stages.insert(0, stage),
+            StageLoc::AtEnd => self.stages.push(stage),
+        }
+        if self.bless() {
+            return true;
+        }
+
+        match loc {
+            StageLoc::AtBegin => self.stages.remove(0),
+            StageLoc::AtEnd => self.stages.pop().unwrap(),
+        };
+        false
+    }
+
+    /// Takes a stage from either end
+    pub fn remove_stage(&mut self, loc: StageLoc) -> Option<Stage> {
+        if self.stages.is_empty() {
+            return None;
+        }
+        let result = match loc {
+            StageLoc::AtBegin => self.stages.remove(0),
+            StageLoc::AtEnd => self.stages.pop().unwrap(),
+        };
+        self.bless();
+        Some(result)
+    }
+
+    /// Appends the stages of `other`. Returns false, leaving the pipeline untouched, if the channels don't match.
+    pub fn cat(&mut self, other: &Pipeline) -> bool {
+        // An empty pipeline takes the shape of the other one
+        if self.stages.is_empty() && other.stages.is_empty() {
+            self.input_channels = other.input_channels;
+            self.output_channels = other.output_channels;
+            return true;
+        }
+
+        let len = self.stages.len();
+        self.stages.extend(other.stages.iter().cloned());
+        if self.bless() {
+            return true;
+        }
+
+        self.stages.truncate(len);
+        false
+    }
+
+    /// Evaluates in floating point. `input` needs `input_channels` values and `output` room for `output_channels`.
+    pub fn eval_float(&self, input: &[f32], output: &mut [f32]) {
+        let mut storage = [[0f32; MAX_STAGE_CHANNELS]; 2];
+        let mut phase = 0;
+
+        storage[phase][..self.input_channels].copy_from_slice(&input[..self.input_channels]);
+
+        for stage in &self.stages {
+            let (a, b) = storage.split_at_mut(1);
+            let (from, to) = if phase == 0 {
+                (&a[0], &mut b[0])
+            } else {
+                (&b[0], &mut a[0])
+            };
+            stage.eval(from, to);
+            phase ^= 1;
+        }
+
+        output[..self.output_channels].copy_from_slice(&storage[phase][..self.output_channels]);
+    }
+
+    /// Evaluates on 16 bit values, working in floating point internally unless an optimization set a faster way
+    pub fn eval_16(&self, input: &[u16], output: &mut [u16]) {
+        if let Some(OptimizedEval(eval)) = &self.optimized_16 {
+            return eval(input, output);
+        }
+
+        let mut in_float = [0f32; MAX_STAGE_CHANNELS];
+        let mut out_float = [0f32; MAX_STAGE_CHANNELS];
+
+        for (i, v) in input.iter().enumerate().take(self.input_channels) {
+            in_float[i] = *v as f32 / 65535.0;
+        }
+        self.eval_float(&in_float, &mut out_float);
+        for (i, out) in output.iter_mut().enumerate().take(self.output_channels) {
+            *out = quick_saturate_word(out_float[i] as f64 * 65535.0);
+        }
+    }
+}
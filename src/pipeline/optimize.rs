@@ -0,0 +1,399 @@
+use crate::internal::quick_saturate_word;
+use crate::plugin::{InterpParams, OptimizationFn, MAX_INPUT_DIMENSIONS};
+use crate::signatures::stage;
+use crate::{Context, PixelType, ToneCurve};
+
+use super::{Pipeline, Stage, StageData};
+
+/// Samples of curves joined together
+const PRELINEARIZATION_POINTS: usize = 4096;
+
+/// Samples of the output curves of the matrix-shaper path, over [0, 1]
+const SHAPER_OUTPUT_POINTS: usize = 16385;
+
+/// Stage types undoing each other when next to each other
+const INVERSE_PAIRS: [(crate::Signature, crate::Signature); 6] = [
+    (stage::LAB_TO_XYZ_ELEM_TYPE, stage::XYZ_TO_LAB_ELEM_TYPE),
+    (stage::XYZ_TO_LAB_ELEM_TYPE, stage::LAB_TO_XYZ_ELEM_TYPE),
+    (stage::LAB_V2_TO_V4, stage::LAB_V4_TO_V2),
+    (stage::LAB_V4_TO_V2, stage::LAB_V2_TO_V4),
+    (stage::LAB_TO_FLOAT_PCS, stage::FLOAT_PCS_TO_LAB),
+    (stage::XYZ_TO_FLOAT_PCS, stage::FLOAT_PCS_TO_XYZ),
+];
+
+/// Passes tried after the ones registered by plugins
+const BUILT_IN: [OptimizationFn; 3] = [join_curves, matrix_shaper, prelinearized_clut];
+
+impl Pipeline {
+    /// Optimizes the pipeline for pixels of the given formats. Identities and stages undoing each other are
+    /// removed and consecutive matrices merged, then the optimization passes registered in `context` are tried,
+    /// followed by the built-in ones, until one succeeds. Integer formats get a faster `eval_16`, which only
+    /// keeps the precision of the formats. Returns whether the pipeline changed.
+    pub fn optimize(
+        &mut self,
+        context: &Context,
+        intent: u32,
+        input_format: PixelType,
+        output_format: PixelType,
+    ) -> bool {
+        // Named colors are looked up by index
+        if self
+            .stages
+            .iter()
+            .any(|s| s.stage_type() == stage::NAMED_COLOR_ELEM_TYPE)
+        {
+            return false;
+        }
+
+        let pre_optimized = self.pre_optimize();
+        if self.stages.is_empty() {
+            return pre_optimized;
+        }
+
+        let mut passes = context
+            .plugins()
+            .optimizations()
+            .chain(BUILT_IN.iter().copied());
+        passes.any(|pass| pass(self, intent, input_format, output_format)) || pre_optimized
+    }
+
+    /// Removes identities and inverse pairs, and merges consecutive matrices. Returns whether anything changed.
+    fn pre_optimize(&mut self) -> bool {
+        let mut changed = false;
+
+        loop {
+            let count = self.stages.len();
+            self.stages
+                .retain(|s| s.implements() != stage::IDENTITY_ELEM_TYPE);
+
+            let pair = self
+                .stages
+                .windows(2)
+                .position(|w| INVERSE_PAIRS.contains(&(w[0].implements(), w[1].implements())));
+            if let Some(i) = pair {
+                self.stages.drain(i..i + 2);
+            }
+
+            let matrices = self.stages.windows(2).position(|w| {
+                matches!(
+                    (w[0].data(), w[1].data()),
+                    (StageData::Matrix { .. }, StageData::Matrix { .. })
+                )
+            });
+            if let Some(i) = matrices {
+                let merged = multiply_matrices(&self.stages[i], &self.stages[i + 1]);
+                self.stages.splice(i..i + 2, merged);
+            }
+
+            if self.stages.len() == count {
+                break;
+            }
+            changed = true;
+        }
+
+        if changed {
+            self.bless();
+        }
+        changed
+    }
+}
+
+/// Matrix doing `first` then `second`, or nothing if that is the identity
+fn multiply_matrices(first: &Stage, second: &Stage) -> Option<Stage> {
+    let (m1, o1) = matrix_of(first);
+    let (m2, o2) = matrix_of(second);
+    let cols = first.input_channels();
+    let inner = first.output_channels();
+    let rows = second.output_channels();
+
+    let mut matrix = vec![0.0; rows * cols];
+    let mut offset = vec![0.0; rows];
+    for r in 0..rows {
+        for c in 0..cols {
+            matrix[r * cols + c] = (0..inner)
+                .map(|k| m2[r * inner + k] * m1[k * cols + c])
+                .sum();
+        }
+        offset[r] = (0..inner).map(|k| m2[r * inner + k] * o1[k]).sum::<f64>() + o2[r];
+    }
+
+    let is_close = |a: f64, b: f64| (a - b).abs() < 1.0 / 65535.0;
+    let is_identity = rows == cols
+        && offset.iter().all(|&o| is_close(o, 0.0))
+        && matrix
+            .iter()
+            .enumerate()
+            .all(|(i, &m)| is_close(m, if i % (cols + 1) == 0 { 1.0 } else { 0.0 }));
+    if is_identity {
+        return None;
+    }
+
+    let has_offset = offset.iter().any(|&o| o != 0.0);
+    Stage::new_matrix(
+        rows,
+        cols,
+        &matrix,
+        Some(&offset[..]).filter(|_| has_offset),
+    )
+}
+
+/// Coefficients and offset of a matrix stage, the offset being zeros if missing
+fn matrix_of(stage: &Stage) -> (&[f64], Vec<f64>) {
+    match stage.data() {
+        StageData::Matrix { matrix, offset } => (
+            matrix,
+            offset
+                .clone()
+                .unwrap_or_else(|| vec![0.0; stage.output_channels()]),
+        ),
+        _ => unreachable!("Not a matrix stage"),
+    }
+}
+
+fn curves_of(stage: &Stage) -> Option<&[ToneCurve]> {
+    match stage.data() {
+        StageData::Curves(curves) => Some(curves),
+        _ => None,
+    }
+}
+
+fn is_integer(format: PixelType) -> bool {
+    !format.float()
+}
+
+/// Table of the 16 bit values of a curve, for every input value
+fn table_of(curve: &ToneCurve) -> Vec<u16> {
+    (0..=0xffff)
+        .map(|v| quick_saturate_word(curve.eval_f32(v as f32 / 65535.0) as f64 * 65535.0))
+        .collect()
+}
+
+/// Pipelines made only of curves become a single set of curves, looked up in tables by `eval_16`
+fn join_curves(
+    pipeline: &mut Pipeline,
+    _intent: u32,
+    input_format: PixelType,
+    output_format: PixelType,
+) -> bool {
+    if !is_integer(input_format) || !is_integer(output_format) {
+        return false;
+    }
+    let all_curves: Option<Vec<&[ToneCurve]>> = pipeline.stages.iter().map(curves_of).collect();
+    let all_curves = match all_curves {
+        Some(curves) => curves,
+        None => return false,
+    };
+
+    let n_channels = pipeline.input_channels;
+    let mut joined = Vec::with_capacity(n_channels);
+    for c in 0..n_channels {
+        let samples: Vec<u16> = (0..PRELINEARIZATION_POINTS)
+            .map(|i| {
+                let x = i as f32 / (PRELINEARIZATION_POINTS - 1) as f32;
+                let y = all_curves.iter().fold(x, |v, curves| curves[c].eval_f32(v));
+                quick_saturate_word(y as f64 * 65535.0)
+            })
+            .collect();
+        joined.push(ToneCurve::from_table16(&samples).unwrap());
+    }
+
+    if joined.iter().all(|curve| curve.is_linear()) {
+        pipeline.stages.clear();
+        pipeline.bless();
+        return true;
+    }
+
+    let tables: Vec<Vec<u16>> = joined.iter().map(table_of).collect();
+    pipeline.stages = vec![Stage::new_tone_curves(joined).unwrap()];
+    pipeline.bless();
+    pipeline.set_optimized_eval_16(move |input, output| {
+        for (c, table) in tables.iter().enumerate() {
+            output[c] = table[input[c] as usize];
+        }
+    });
+    true
+}
+
+/// RGB to RGB pipelines made of curves, a 3x3 matrix and curves get an `eval_16` going through tables and a
+/// single precision matrix. Input tables have 256 entries for 8 bit input formats.
+fn matrix_shaper(
+    pipeline: &mut Pipeline,
+    _intent: u32,
+    input_format: PixelType,
+    output_format: PixelType,
+) -> bool {
+    if !is_integer(input_format)
+        || !is_integer(output_format)
+        || input_format.channels() != 3
+        || output_format.channels() != 3
+    {
+        return false;
+    }
+
+    let mut stages = pipeline.stages.iter().peekable();
+    let input_curves = stages
+        .next_if(|s| curves_of(s).is_some())
+        .and_then(curves_of);
+    let matrix = match stages.next() {
+        Some(s) if matches!(s.data(), StageData::Matrix { .. }) => s,
+        _ => return false,
+    };
+    let output_curves = stages
+        .next_if(|s| curves_of(s).is_some())
+        .and_then(curves_of);
+    if stages.next().is_some() || matrix.input_channels() != 3 || matrix.output_channels() != 3 {
+        return false;
+    }
+
+    let eight_bits = input_format.bytes() == 1;
+    let input_points = if eight_bits { 256 } else { 65536 };
+    let shaper1: Vec<Vec<f32>> = (0..3)
+        .map(|c| {
+            (0..input_points)
+                .map(|i| {
+                    let x = i as f32 / (input_points - 1) as f32;
+                    input_curves.map_or(x, |curves| curves[c].eval_f32(x))
+                })
+                .collect()
+        })
+        .collect();
+    let shaper2: Vec<Vec<u16>> = (0..3)
+        .map(|c| {
+            (0..SHAPER_OUTPUT_POINTS)
+                .map(|i| {
+                    let x = i as f32 / (SHAPER_OUTPUT_POINTS - 1) as f32;
+                    let y = output_curves.map_or(x, |curves| curves[c].eval_f32(x));
+                    quick_saturate_word(y as f64 * 65535.0)
+                })
+                .collect()
+        })
+        .collect();
+
+    let (m, o) = matrix_of(matrix);
+    let mut coefficients = [0f32; 9];
+    for (to, from) in coefficients.iter_mut().zip(m) {
+        *to = *from as f32;
+    }
+    let offset = [o[0] as f32, o[1] as f32, o[2] as f32];
+
+    pipeline.set_optimized_eval_16(move |input, output| {
+        let mut linear = [0f32; 3];
+        for (c, v) in linear.iter_mut().enumerate() {
+            let index = if eight_bits { input[c] >> 8 } else { input[c] };
+            *v = shaper1[c][index as usize];
+        }
+
+        for (r, out) in output.iter_mut().enumerate().take(3) {
+            let row = &coefficients[r * 3..r * 3 + 3];
+            let y = row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2] + offset[r];
+
+            // Linear interpolation on the output table
+            let pos = y.clamp(0.0, 1.0) * (SHAPER_OUTPUT_POINTS - 1) as f32;
+            let cell = (pos as usize).min(SHAPER_OUTPUT_POINTS - 2);
+            let rest = pos - cell as f32;
+            let (a, b) = (shaper2[r][cell] as f32, shaper2[r][cell + 1] as f32);
+            *out = (a + (b - a) * rest + 0.5) as u16;
+        }
+    });
+    true
+}
+
+/// Grid points of CLUTs resampling a pipeline with the given number of inputs
+fn reasonable_grid_points(n_inputs: usize) -> u32 {
+    match n_inputs {
+        n if n > 4 => 7,
+        4 => 17,
+        _ => 33,
+    }
+}
+
+/// Pipelines holding a CLUT become curves, a 16 bit CLUT sampling whatever is between them, and curves.
+/// `eval_16` then goes through tables and the CLUT without converting to floating point.
+fn prelinearized_clut(
+    pipeline: &mut Pipeline,
+    _intent: u32,
+    input_format: PixelType,
+    output_format: PixelType,
+) -> bool {
+    let n_inputs = pipeline.input_channels;
+    let n_outputs = pipeline.output_channels;
+    if !is_integer(input_format)
+        || !is_integer(output_format)
+        || n_inputs == 0
+        || n_inputs > MAX_INPUT_DIMENSIONS
+        || !pipeline
+            .stages
+            .iter()
+            .any(|s| s.stage_type() == stage::C_LUT_ELEM_TYPE)
+    {
+        return false;
+    }
+
+    let mut middle = pipeline.stages.clone();
+    let pre = if curves_of(&middle[0]).is_some() {
+        Some(middle.remove(0))
+    } else {
+        None
+    };
+    let post = match middle.last() {
+        Some(s) if curves_of(s).is_some() => middle.pop(),
+        _ => None,
+    };
+
+    let clut = match &middle[..] {
+        [clut] if matches!(clut.data(), StageData::CLut16(_)) => clut.clone(),
+        _ => {
+            let mut sampled = Pipeline::new(middle[0].input_channels(), n_outputs).unwrap();
+            sampled.stages = middle;
+            if !sampled.bless() {
+                return false;
+            }
+
+            let mut clut = match Stage::new_clut16(
+                reasonable_grid_points(sampled.input_channels),
+                sampled.input_channels,
+                sampled.output_channels,
+                None,
+            ) {
+                Some(clut) => clut,
+                None => return false,
+            };
+            clut.sample_clut16(|input, output| sampled.eval_16(input, output));
+            clut
+        }
+    };
+    let params: InterpParams<u16> = match clut.data() {
+        StageData::CLut16(params) => params.clone(),
+        _ => unreachable!("Not a 16 bit CLUT"),
+    };
+
+    let pre_tables: Option<Vec<Vec<u16>>> = pre
+        .as_ref()
+        .and_then(curves_of)
+        .map(|curves| curves.iter().map(table_of).collect());
+    let post_tables: Option<Vec<Vec<u16>>> = post
+        .as_ref()
+        .and_then(curves_of)
+        .map(|curves| curves.iter().map(table_of).collect());
+
+    pipeline.stages = pre.into_iter().chain(Some(clut)).chain(post).collect();
+    pipeline.bless();
+    pipeline.set_optimized_eval_16(move |input, output| {
+        let mut linear = [0u16; MAX_INPUT_DIMENSIONS];
+        for (c, v) in linear.iter_mut().enumerate().take(n_inputs) {
+            *v = match &pre_tables {
+                Some(tables) => tables[c][input[c] as usize],
+                None => input[c],
+            };
+        }
+
+        params.eval(&linear[..n_inputs], output);
+
+        if let Some(tables) = &post_tables {
+            for (c, out) in output.iter_mut().enumerate().take(n_outputs) {
+                *out = tables[c][*out as usize];
+            }
+        }
+    });
+    true
+}
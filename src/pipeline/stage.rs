@@ -0,0 +1,439 @@
+use crate::internal::{quantize_val, quick_saturate_word};
+use crate::plugin::{InterpParams, MAX_INPUT_DIMENSIONS, MAX_STAGE_CHANNELS};
+use crate::signatures::stage;
+use crate::{
+    lab_to_xyz, xyz_to_lab, CIELab, Context, NamedColorList, Signature, ToneCurve, CIEXYZ,
+};
+
+/// What a stage holds, depending on its type
+#[derive(Clone, PartialEq, Debug)]
+pub enum StageData {
+    /// One curve per channel
+    Curves(Vec<ToneCurve>),
+    /// Row major `output_channels` x `input_channels` matrix, with an optional offset per output
+    Matrix {
+        matrix: Vec<f64>,
+        offset: Option<Vec<f64>>,
+    },
+    /// Color lookup table with 16 bit samples
+    CLut16(InterpParams<u16>),
+    /// Color lookup table with float samples
+    CLutFloat(InterpParams<f32>),
+    /// Maps color indices to the PCS or device values of a named color list
+    NamedColor { list: NamedColorList, use_pcs: bool },
+    /// Converts Lab to XYZ, both in the 0..1 range used by pipelines
+    LabToXyz,
+    /// Converts XYZ to Lab, both in the 0..1 range used by pipelines
+    XyzToLab,
+    /// Passes values through
+    Identity,
+}
+
+/// Largest XYZ value the 16 bit encoding can hold, 1 + 32767/32768
+const MAX_ENCODEABLE_XYZ: f64 = 1.0 + 32767.0 / 32768.0;
+
+/// A single step of a pipeline. Stages work in floating point, on values in the 0..1 range.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Stage {
+    r#type: Signature,
+    implements: Signature,
+    input_channels: usize,
+    output_channels: usize,
+    data: StageData,
+}
+
+impl Stage {
+    fn new(
+        r#type: Signature,
+        input_channels: usize,
+        output_channels: usize,
+        data: StageData,
+    ) -> Self {
+        Self {
+            r#type,
+            implements: r#type,
+            input_channels,
+            output_channels,
+            data,
+        }
+    }
+
+    /// Stage that passes `n_channels` values through
+    pub fn new_identity(n_channels: usize) -> Option<Self> {
+        if n_channels == 0 || n_channels > MAX_STAGE_CHANNELS {
+            return None;
+        }
+        Some(Self::new(
+            stage::IDENTITY_ELEM_TYPE,
+            n_channels,
+            n_channels,
+            StageData::Identity,
+        ))
+    }
+
+    /// Stage applying one curve to each channel
+    pub fn new_tone_curves(curves: Vec<ToneCurve>) -> Option<Self> {
+        let n = curves.len();
+        if n == 0 || n > MAX_STAGE_CHANNELS {
+            return None;
+        }
+        Some(Self::new(
+            stage::CURVE_SET_ELEM_TYPE,
+            n,
+            n,
+            StageData::Curves(curves),
+        ))
+    }
+
+    /// Stage applying a linear curve to each of `n_channels` channels
+    pub fn new_identity_curves(n_channels: usize) -> Option<Self> {
+        let mut result = Self::new_tone_curves(vec![ToneCurve::linear(); n_channels])?;
+        result.implements = stage::IDENTITY_ELEM_TYPE;
+        Some(result)
+    }
+
+    /// Stage multiplying by a `rows` x `cols` matrix, given in row major order, and adding `offset` if given
+    pub fn new_matrix(
+        rows: usize,
+        cols: usize,
+        matrix: &[f64],
+        offset: Option<&[f64]>,
+    ) -> Option<Self> {
+        if rows == 0 || cols == 0 || rows > MAX_STAGE_CHANNELS || cols > MAX_STAGE_CHANNELS {
+            return None;
+        }
+        if matrix.len() != rows * cols || offset.is_some_and(|o| o.len() != rows) {
+            return None;
+        }
+        Some(Self::new(
+            stage::MATRIX_ELEM_TYPE,
+            cols,
+            rows,
+            StageData::Matrix {
+                matrix: matrix.to_vec(),
+                offset: offset.map(|o| o.to_vec()),
+            },
+        ))
+    }
+
+    fn clut_size(grid_points: &[u32], output_channels: usize) -> Option<usize> {
+        if grid_points.is_empty()
+            || grid_points.len() > MAX_INPUT_DIMENSIONS
+            || output_channels == 0
+            || output_channels > MAX_STAGE_CHANNELS
+        {
+            return None;
+        }
+
+        grid_points
+            .iter()
+            .try_fold(output_channels, |size, &points| {
+                if points < 2 {
+                    None
+                } else {
+                    size.checked_mul(points as usize)
+                }
+            })
+    }
+
+    /// 16 bit CLUT with the given number of grid points per input. The table is zero filled if not given.
+    pub fn new_clut16_granular(
+        grid_points: &[u32],
+        output_channels: usize,
+        table: Option<&[u16]>,
+    ) -> Option<Self> {
+        let size = Self::clut_size(grid_points, output_channels)?;
+        let table = match table {
+            Some(table) if table.len() == size => table.to_vec(),
+            Some(_) => return None,
+            None => vec![0; size],
+        };
+        let params = InterpParams::new(grid_points, output_channels, table, 0)?;
+
+        Some(Self::new(
+            stage::C_LUT_ELEM_TYPE,
+            grid_points.len(),
+            output_channels,
+            StageData::CLut16(params),
+        ))
+    }
+
+    /// 16 bit CLUT with the same number of grid points on every input
+    pub fn new_clut16(
+        grid_points: u32,
+        input_channels: usize,
+        output_channels: usize,
+        table: Option<&[u16]>,
+    ) -> Option<Self> {
+        if input_channels > MAX_INPUT_DIMENSIONS {
+            return None;
+        }
+        Self::new_clut16_granular(&vec![grid_points; input_channels], output_channels, table)
+    }
+
+    /// Float CLUT with the given number of grid points per input. The table is zero filled if not given.
+    pub fn new_clut_float_granular(
+        grid_points: &[u32],
+        output_channels: usize,
+        table: Option<&[f32]>,
+    ) -> Option<Self> {
+        let size = Self::clut_size(grid_points, output_channels)?;
+        let table = match table {
+            Some(table) if table.len() == size => table.to_vec(),
+            Some(_) => return None,
+            None => vec![0.0; size],
+        };
+        let params = InterpParams::new(
+            grid_points,
+            output_channels,
+            table,
+            crate::plugin::lerp_flags::FLOAT,
+        )?;
+
+        Some(Self::new(
+            stage::C_LUT_ELEM_TYPE,
+            grid_points.len(),
+            output_channels,
+            StageData::CLutFloat(params),
+        ))
+    }
+
+    /// Float CLUT with the same number of grid points on every input
+    pub fn new_clut_float(
+        grid_points: u32,
+        input_channels: usize,
+        output_channels: usize,
+        table: Option<&[f32]>,
+    ) -> Option<Self> {
+        if input_channels > MAX_INPUT_DIMENSIONS {
+            return None;
+        }
+        Self::new_clut_float_granular(&vec![grid_points; input_channels], output_channels, table)
+    }
+
+    /// 3x3 diagonal matrix stage with an optional offset, implementing a known conversion
+    fn new_scaling(implements: Signature, scale: [f64; 3], offset: Option<&[f64]>) -> Self {
+        let matrix = [
+            scale[0], 0.0, 0.0, //
+            0.0, scale[1], 0.0, //
+            0.0, 0.0, scale[2],
+        ];
+        let mut result = Self::new_matrix(3, 3, &matrix, offset).unwrap();
+        result.implements = implements;
+        result
+    }
+
+    /// Converts Lab from the v2 16 bit encoding, where L = 100 is 0xFF00, to the v4 one
+    pub fn new_lab_v2_to_v4() -> Self {
+        const V2_TO_V4: f64 = 65535.0 / 65280.0;
+        Self::new_scaling(stage::LAB_V2_TO_V4, [V2_TO_V4; 3], None)
+    }
+
+    /// Converts Lab from the v4 16 bit encoding to the v2 one
+    pub fn new_lab_v4_to_v2() -> Self {
+        const V4_TO_V2: f64 = 65280.0 / 65535.0;
+        Self::new_scaling(stage::LAB_V4_TO_V2, [V4_TO_V2; 3], None)
+    }
+
+    /// Converts Lab from the 0..1 range used by pipelines to actual Lab values
+    pub fn new_normalize_to_lab_float() -> Self {
+        Self::new_scaling(
+            stage::LAB_TO_FLOAT_PCS,
+            [100.0, 255.0, 255.0],
+            Some(&[0.0, -128.0, -128.0]),
+        )
+    }
+
+    /// Converts actual Lab values to the 0..1 range used by pipelines
+    pub fn new_normalize_from_lab_float() -> Self {
+        Self::new_scaling(
+            stage::FLOAT_PCS_TO_LAB,
+            [1.0 / 100.0, 1.0 / 255.0, 1.0 / 255.0],
+            Some(&[0.0, 128.0 / 255.0, 128.0 / 255.0]),
+        )
+    }
+
+    /// Converts XYZ from the 0..1 range used by pipelines, where 1.0 is encoded as 0x8000, to actual XYZ values
+    pub fn new_normalize_to_xyz_float() -> Self {
+        Self::new_scaling(stage::XYZ_TO_FLOAT_PCS, [65535.0 / 32768.0; 3], None)
+    }
+
+    /// Converts actual XYZ values to the 0..1 range used by pipelines
+    pub fn new_normalize_from_xyz_float() -> Self {
+        Self::new_scaling(stage::FLOAT_PCS_TO_XYZ, [32768.0 / 65535.0; 3], None)
+    }
+
+    /// Maps a color index, taken as a 16 bit value, to the PCS values of the color if `use_pcs` is set, or to
+    /// its device values otherwise
+    pub fn new_named_color(list: NamedColorList, use_pcs: bool) -> Option<Self> {
+        let output_channels = if use_pcs { 3 } else { list.colorant_count() };
+        if output_channels == 0 {
+            return None;
+        }
+
+        Some(Self::new(
+            stage::NAMED_COLOR_ELEM_TYPE,
+            1,
+            output_channels,
+            StageData::NamedColor { list, use_pcs },
+        ))
+    }
+
+    /// Makes CLUTs use the interpolation routines registered in `context`, if any
+    pub fn use_interpolators(&mut self, context: &Context) {
+        match &mut self.data {
+            StageData::CLut16(params) => {
+                params.use_interpolators(context);
+            }
+            StageData::CLutFloat(params) => {
+                params.use_interpolators(context);
+            }
+            _ => {}
+        }
+    }
+
+    /// Fills the table of a 16 bit CLUT, calling `sampler` with the input values of each node and the output
+    /// values to set. Returns false if the stage is not a 16 bit CLUT.
+    pub fn sample_clut16<F>(&mut self, mut sampler: F) -> bool
+    where
+        F: FnMut(&[u16], &mut [u16]),
+    {
+        let params = match &mut self.data {
+            StageData::CLut16(params) => params,
+            _ => return false,
+        };
+
+        let n_inputs = params.n_inputs;
+        let n_outputs = params.n_outputs;
+        let mut input = [0u16; MAX_INPUT_DIMENSIONS];
+
+        for (index, node) in params.table.chunks_exact_mut(n_outputs).enumerate() {
+            // The last input moves fastest
+            let mut rest = index as u32;
+            for t in (0..n_inputs).rev() {
+                let colorant = rest % params.n_samples[t];
+                rest /= params.n_samples[t];
+
+                input[t] = quantize_val(colorant as f64, params.n_samples[t]);
+            }
+
+            sampler(&input[..n_inputs], node);
+        }
+        true
+    }
+
+    /// Converts Lab in the v4 encoding to XYZ, relative to D50
+    pub fn new_lab_to_xyz() -> Self {
+        Self::new(stage::LAB_TO_XYZ_ELEM_TYPE, 3, 3, StageData::LabToXyz)
+    }
+
+    /// Converts XYZ to Lab in the v4 encoding, relative to D50
+    pub fn new_xyz_to_lab() -> Self {
+        Self::new(stage::XYZ_TO_LAB_ELEM_TYPE, 3, 3, StageData::XyzToLab)
+    }
+
+    pub fn stage_type(&self) -> Signature {
+        self.r#type
+    }
+
+    /// What the stage does, which may be more specific than its type
+    pub fn implements(&self) -> Signature {
+        self.implements
+    }
+
+    pub fn input_channels(&self) -> usize {
+        self.input_channels
+    }
+
+    pub fn output_channels(&self) -> usize {
+        self.output_channels
+    }
+
+    pub fn data(&self) -> &StageData {
+        &self.data
+    }
+
+    /// Evaluates the stage. `input` needs `input_channels` values and `output` room for `output_channels`.
+    pub fn eval(&self, input: &[f32], output: &mut [f32]) {
+        match &self.data {
+            StageData::Identity => {
+                output[..self.input_channels].copy_from_slice(&input[..self.input_channels]);
+            }
+            StageData::Curves(curves) => {
+                for (i, curve) in curves.iter().enumerate() {
+                    output[i] = curve.eval_f32(input[i]);
+                }
+            }
+            StageData::Matrix { matrix, offset } => {
+                for (i, out) in output.iter_mut().enumerate().take(self.output_channels) {
+                    let row = &matrix[i * self.input_channels..(i + 1) * self.input_channels];
+                    let mut tmp = row
+                        .iter()
+                        .zip(input)
+                        .map(|(m, v)| *v as f64 * m)
+                        .sum::<f64>();
+                    if let Some(offset) = offset {
+                        tmp += offset[i];
+                    }
+                    *out = tmp as f32;
+                }
+            }
+            StageData::CLut16(params) => {
+                let mut in16 = [0u16; MAX_STAGE_CHANNELS];
+                let mut out16 = [0u16; MAX_STAGE_CHANNELS];
+
+                for (i, v) in input.iter().enumerate().take(self.input_channels) {
+                    in16[i] = quick_saturate_word(*v as f64 * 65535.0);
+                }
+                params.eval(&in16, &mut out16);
+                for (i, out) in output.iter_mut().enumerate().take(self.output_channels) {
+                    *out = out16[i] as f32 / 65535.0;
+                }
+            }
+            StageData::CLutFloat(params) => params.eval(input, output),
+            StageData::LabToXyz => {
+                let lab = CIELab {
+                    L: input[0] as f64 * 100.0,
+                    a: input[1] as f64 * 255.0 - 128.0,
+                    b: input[2] as f64 * 255.0 - 128.0,
+                };
+                let xyz = lab_to_xyz(None, lab);
+
+                output[0] = (xyz.X / MAX_ENCODEABLE_XYZ) as f32;
+                output[1] = (xyz.Y / MAX_ENCODEABLE_XYZ) as f32;
+                output[2] = (xyz.Z / MAX_ENCODEABLE_XYZ) as f32;
+            }
+            StageData::XyzToLab => {
+                let xyz = CIEXYZ {
+                    X: input[0] as f64 * MAX_ENCODEABLE_XYZ,
+                    Y: input[1] as f64 * MAX_ENCODEABLE_XYZ,
+                    Z: input[2] as f64 * MAX_ENCODEABLE_XYZ,
+                };
+                let lab = xyz_to_lab(None, xyz);
+
+                output[0] = (lab.L / 100.0) as f32;
+                output[1] = ((lab.a + 128.0) / 255.0) as f32;
+                output[2] = ((lab.b + 128.0) / 255.0) as f32;
+            }
+            StageData::NamedColor { list, use_pcs } => {
+                let index = quick_saturate_word(input[0] as f64 * 65535.0);
+                let output = &mut output[..self.output_channels];
+
+                // Unknown colors give zeros
+                match list.get(index as usize) {
+                    Some(color) => {
+                        let values: &[u16] = if *use_pcs {
+                            &color.pcs
+                        } else {
+                            &color.device_colorant
+                        };
+                        for (out, v) in output.iter_mut().zip(values) {
+                            *out = *v as f32 / 65535.0;
+                        }
+                    }
+                    None => output.iter_mut().for_each(|v| *v = 0.0),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,2 @@
+mod optimize;
+mod pipeline;
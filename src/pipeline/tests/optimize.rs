@@ -0,0 +1,191 @@
+use crate::pipeline::{Pipeline, Stage, StageLoc};
+use crate::plugin::{Plugin, PluginBase};
+use crate::signatures::stage;
+use crate::{Context, PixelType, ToneCurve};
+
+fn pipeline_of(stages: Vec<Stage>) -> Pipeline {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    for stage in stages {
+        assert!(pipeline.insert_stage(StageLoc::AtEnd, stage));
+    }
+    pipeline
+}
+
+fn gamma_curves(gamma: f64) -> Stage {
+    let curve = ToneCurve::build_gamma(gamma).unwrap();
+    Stage::new_tone_curves(vec![curve.clone(), curve.clone(), curve]).unwrap()
+}
+
+fn optimize(pipeline: &mut Pipeline, format: PixelType) -> bool {
+    pipeline.optimize(&Context::new(), 0, format, format)
+}
+
+/// Largest difference between the optimized pipeline and the original on 8 bit values
+fn max_difference_8(original: &Pipeline, optimized: &Pipeline) -> i32 {
+    let mut result = 0;
+    for r in (0..=255u16).step_by(15) {
+        for g in (0..=255u16).step_by(15) {
+            for b in (0..=255u16).step_by(15) {
+                let input = [r * 257, g * 257, b * 257];
+                let (mut expected, mut actual) = ([0u16; 3], [0u16; 3]);
+                original.eval_16(&input, &mut expected);
+                optimized.eval_16(&input, &mut actual);
+
+                for (e, a) in expected.iter().zip(&actual) {
+                    result = result.max((*e as i32 - *a as i32).abs());
+                }
+            }
+        }
+    }
+    result
+}
+
+#[test]
+fn test_identities_and_inverse_pairs_are_removed() {
+    let mut pipeline = pipeline_of(vec![
+        Stage::new_identity_curves(3).unwrap(),
+        Stage::new_lab_to_xyz(),
+        Stage::new_xyz_to_lab(),
+        Stage::new_lab_v2_to_v4(),
+        Stage::new_lab_v4_to_v2(),
+        Stage::new_identity(3).unwrap(),
+    ]);
+
+    assert!(optimize(&mut pipeline, PixelType::RGB_16));
+    assert_eq!(0, pipeline.stage_count());
+    assert_eq!(3, pipeline.input_channels());
+
+    let mut out = [0u16; 3];
+    pipeline.eval_16(&[1, 0x8000, 0xffff], &mut out);
+    assert_eq!([1, 0x8000, 0xffff], out);
+}
+
+#[test]
+fn test_consecutive_matrices_are_merged() {
+    let scale = [2.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0];
+    let halve = [0.5, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.5];
+    let mut pipeline = pipeline_of(vec![
+        Stage::new_matrix(3, 3, &scale, None).unwrap(),
+        Stage::new_matrix(3, 3, &halve, None).unwrap(),
+    ]);
+    assert!(optimize(&mut pipeline, PixelType::RGB_FLT));
+    assert_eq!(0, pipeline.stage_count());
+
+    let swap = [0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+    let original = pipeline_of(vec![
+        Stage::new_matrix(3, 3, &halve, Some(&[0.1, 0.2, 0.3])).unwrap(),
+        Stage::new_matrix(3, 3, &swap, Some(&[0.0, 0.0, 0.1])).unwrap(),
+    ]);
+    let mut pipeline = original.clone();
+    assert!(optimize(&mut pipeline, PixelType::RGB_FLT));
+    assert!(pipeline.has_stage_types(&[stage::MATRIX_ELEM_TYPE]));
+    assert!(!pipeline.has_optimized_eval_16());
+
+    let (mut expected, mut actual) = ([0f32; 3], [0f32; 3]);
+    original.eval_float(&[0.2, 0.4, 0.6], &mut expected);
+    pipeline.eval_float(&[0.2, 0.4, 0.6], &mut actual);
+    for (e, a) in expected.iter().zip(&actual) {
+        assert!((e - a).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_curves_are_joined() {
+    let mut pipeline = pipeline_of(vec![gamma_curves(2.2), gamma_curves(1.0 / 2.2)]);
+    assert!(optimize(&mut pipeline, PixelType::RGB_8));
+    assert_eq!(0, pipeline.stage_count());
+
+    let original = pipeline_of(vec![gamma_curves(2.0), gamma_curves(1.5)]);
+    let mut pipeline = original.clone();
+    assert!(optimize(&mut pipeline, PixelType::RGB_16));
+    assert!(pipeline.has_stage_types(&[stage::CURVE_SET_ELEM_TYPE]));
+    assert!(pipeline.has_optimized_eval_16());
+    assert!(max_difference_8(&original, &pipeline) <= 0x20);
+
+    // Float formats keep the curves as they are
+    let mut pipeline = original.clone();
+    assert!(!optimize(&mut pipeline, PixelType::RGB_FLT));
+    assert_eq!(original, pipeline);
+}
+
+#[test]
+fn test_matrix_shapers_get_a_fast_path() {
+    let to_xyz = [
+        0.4361, 0.3851, 0.1431, 0.2225, 0.7169, 0.0606, 0.0139, 0.0971, 0.7141,
+    ];
+    let original = pipeline_of(vec![
+        gamma_curves(2.2),
+        Stage::new_matrix(3, 3, &to_xyz, None).unwrap(),
+        Stage::new_identity_curves(3).unwrap(),
+        Stage::new_matrix(3, 3, &to_xyz, None).unwrap(),
+        gamma_curves(1.0 / 2.2),
+    ]);
+
+    for &format in &[PixelType::RGB_8, PixelType::RGB_16] {
+        let mut pipeline = original.clone();
+        assert!(optimize(&mut pipeline, format));
+        assert!(pipeline.has_optimized_eval_16());
+        assert_eq!(3, pipeline.stage_count());
+        assert!(max_difference_8(&original, &pipeline) <= 0x40);
+    }
+
+    // Changing the stages drops the fast path
+    let mut pipeline = original.clone();
+    optimize(&mut pipeline, PixelType::RGB_8);
+    pipeline.insert_stage(StageLoc::AtEnd, gamma_curves(1.0));
+    assert!(!pipeline.has_optimized_eval_16());
+}
+
+#[test]
+fn test_cluts_are_prelinearized() {
+    // Sorts the inputs into a 2 point grid
+    let table: Vec<u16> = (0..8u16)
+        .flat_map(|i| {
+            let bits = [(i >> 2) & 1, (i >> 1) & 1, i & 1];
+            [bits[0] * 0xffff, bits[1] * 0xffff, bits[2] * 0xffff]
+        })
+        .collect();
+    let original = pipeline_of(vec![
+        gamma_curves(2.2),
+        Stage::new_matrix(3, 3, &[0.9, 0.1, 0.0, 0.0, 1.0, 0.0, 0.0, 0.1, 0.9], None).unwrap(),
+        Stage::new_clut16(2, 3, 3, Some(&table)).unwrap(),
+        gamma_curves(1.0 / 2.2),
+    ]);
+
+    let mut pipeline = original.clone();
+    assert!(optimize(&mut pipeline, PixelType::RGB_16));
+    assert!(pipeline.has_stage_types(&[
+        stage::CURVE_SET_ELEM_TYPE,
+        stage::C_LUT_ELEM_TYPE,
+        stage::CURVE_SET_ELEM_TYPE
+    ]));
+    assert!(pipeline.has_optimized_eval_16());
+    assert!(max_difference_8(&original, &pipeline) <= 0x100);
+}
+
+fn black_out(
+    pipeline: &mut Pipeline,
+    _intent: u32,
+    _input_format: PixelType,
+    _output_format: PixelType,
+) -> bool {
+    pipeline.set_optimized_eval_16(|_, output| output.iter_mut().for_each(|v| *v = 0));
+    true
+}
+
+#[test]
+fn test_plugin_passes_come_first() {
+    let context = Context::with_plugins(&PluginBase::new(Plugin::Optimization(black_out))).unwrap();
+    let mut pipeline = pipeline_of(vec![gamma_curves(2.0)]);
+    assert!(pipeline.optimize(&context, 0, PixelType::RGB_16, PixelType::RGB_16));
+
+    // The built-in passes didn't run
+    assert_eq!(
+        pipeline_of(vec![gamma_curves(2.0)]).stages(),
+        pipeline.stages()
+    );
+
+    let mut out = [1u16; 3];
+    pipeline.eval_16(&[0x8000; 3], &mut out);
+    assert_eq!([0; 3], out);
+}
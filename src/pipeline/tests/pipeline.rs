@@ -0,0 +1,91 @@
+use crate::pipeline::{Pipeline, Stage, StageData, StageLoc};
+use crate::signatures::stage;
+use crate::ToneCurve;
+
+#[test]
+fn test_empty_pipeline_passes_values_through() {
+    let pipeline = Pipeline::new(3, 3).unwrap();
+    let mut out = [0u16; 3];
+
+    pipeline.eval_16(&[1, 0x8000, 0xffff], &mut out);
+
+    assert_eq!([1, 0x8000, 0xffff], out);
+}
+
+#[test]
+fn test_insert_stage_checks_channels() {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+
+    assert!(pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap()));
+    assert!(!pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(4).unwrap()));
+    assert_eq!(1, pipeline.stage_count());
+
+    let matrix = Stage::new_matrix(1, 3, &[1.0, 1.0, 1.0], None).unwrap();
+    assert!(pipeline.insert_stage(StageLoc::AtEnd, matrix));
+    assert_eq!(3, pipeline.input_channels());
+    assert_eq!(1, pipeline.output_channels());
+
+    let removed = pipeline.remove_stage(StageLoc::AtEnd).unwrap();
+    assert_eq!(stage::MATRIX_ELEM_TYPE, removed.stage_type());
+    assert_eq!(3, pipeline.output_channels());
+}
+
+#[test]
+fn test_matrix_with_offset_and_curves() {
+    let mut pipeline = Pipeline::new(2, 2).unwrap();
+    let curve = ToneCurve::from_table16(&[0, 0x8000, 0xffff]).unwrap();
+
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_matrix(2, 2, &[0.0, 1.0, 0.5, 0.0], Some(&[0.0, 0.25])).unwrap(),
+    );
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_tone_curves(vec![curve.clone(), curve]).unwrap(),
+    );
+
+    let mut out = [0f32; 2];
+    pipeline.eval_float(&[0.5, 0.25], &mut out);
+
+    assert!((out[0] - 0.25).abs() < 1e-4);
+    assert!((out[1] - 0.5).abs() < 1e-4);
+}
+
+#[test]
+fn test_clut_stage_in_pipeline() {
+    // Swaps both inputs
+    let table = [0, 0, 0xffff, 0, 0, 0xffff, 0xffff, 0xffff];
+    let clut = Stage::new_clut16(2, 2, 2, Some(&table)).unwrap();
+    assert!(matches!(clut.data(), StageData::CLut16(_)));
+
+    let mut pipeline = Pipeline::new(2, 2).unwrap();
+    pipeline.insert_stage(StageLoc::AtEnd, clut);
+
+    let mut out = [0u16; 2];
+    pipeline.eval_16(&[0x1000, 0xc000], &mut out);
+
+    assert_eq!([0xc000, 0x1000], out);
+}
+
+#[test]
+fn test_clut_rejects_bad_grid() {
+    assert!(Stage::new_clut16(1, 3, 3, None).is_none());
+    assert!(Stage::new_clut16(2, 3, 3, Some(&[0; 10])).is_none());
+    assert!(Stage::new_clut_float_granular(&[2, 3], 1, None).is_some());
+}
+
+#[test]
+fn test_cat_appends_stages() {
+    let mut a = Pipeline::new(3, 3).unwrap();
+    a.insert_stage(StageLoc::AtEnd, Stage::new_lab_v2_to_v4());
+    let mut b = Pipeline::new(3, 3).unwrap();
+    b.insert_stage(StageLoc::AtEnd, Stage::new_lab_v4_to_v2());
+
+    assert!(a.cat(&b));
+    assert_eq!(2, a.stage_count());
+    assert_eq!(stage::LAB_V4_TO_V2, a.last_stage().unwrap().implements());
+
+    let mut out = [0u16; 3];
+    a.eval_16(&[0xff00, 0x8080, 0x1234], &mut out);
+    assert_eq!([0xff00, 0x8080, 0x1234], out);
+}
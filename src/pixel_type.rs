@@ -23,7 +23,9 @@ bitfield! {
     ///                B: bytes per sample
     ///                Y: Swap first - changes ABGR to BGRA and KCMY to CMYK
     /// ```
+    #[derive(Copy, Clone, PartialEq, Eq)]
     pub struct PixelType(u32);
+    impl Debug;
     pub u8, bps, set_bps: 2, 0;
     pub u8, channels, set_channels: 6, 3;
     pub u8, extra, set_extra: 9, 7;
@@ -311,6 +313,14 @@ impl PixelType {
     PixelTypeDef!(pub const BGRA_HALF_FLT; float, color_space ColorSpace::Rgb, extra 1, channels 3, bps 2, do_swap, swap_first);
     PixelTypeDef!(pub const ABGR_HALF_FLT; float, color_space ColorSpace::Rgb, channels 3, bps 2, do_swap);
 
+    /// Bytes per sample, doubles being stored with a zero `bps`
+    pub fn bytes(self) -> usize {
+        match self.bps() {
+            0 => 8,
+            n => n as usize,
+        }
+    }
+
     pub fn set_color_space(&mut self, cs: ColorSpace) {
         self._set_color_space(cs as u8);
     }
@@ -349,3 +359,14 @@ impl PixelType {
         }
     }
 }
+
+impl From<u32> for PixelType {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+impl From<PixelType> for u32 {
+    fn from(value: PixelType) -> Self {
+        value.0
+    }
+}
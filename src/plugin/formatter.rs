@@ -0,0 +1,313 @@
+use std::fmt::Debug;
+
+use crate::internal::quick_saturate_word;
+use crate::{ColorSpace, Context, PixelType, MAX_CHANNELS};
+
+/// Reads the channels of a pixel from `input` into `values`, returning the number of bytes to move to the next
+/// pixel. `stride` is the distance between planes of planar formats.
+pub type UnrollFn<T> =
+    fn(format: PixelType, values: &mut [T], input: &[u8], stride: usize) -> usize;
+
+/// Writes the channels of a pixel from `values` to `output`, returning the number of bytes to move to the next
+/// pixel. `stride` is the distance between planes of planar formats.
+pub type PackFn<T> = fn(format: PixelType, values: &[T], output: &mut [u8], stride: usize) -> usize;
+
+/// Provides pixel formatters, registered with a `Plugin::Formatters`. Formatters are picked by matching on the
+/// bits of the `PixelType`, and take precedence over the built-in ones. Returning `None` leaves the choice to
+/// plugins registered earlier and then to the built-in formatters.
+///
+/// 16 bit formatters are used when neither side of a transform is floating point, float ones otherwise.
+pub trait FormatterFactory: Send + Sync {
+    fn unroll_16(&self, _format: PixelType) -> Option<UnrollFn<u16>> {
+        None
+    }
+
+    fn pack_16(&self, _format: PixelType) -> Option<PackFn<u16>> {
+        None
+    }
+
+    fn unroll_float(&self, _format: PixelType) -> Option<UnrollFn<f32>> {
+        None
+    }
+
+    fn pack_float(&self, _format: PixelType) -> Option<PackFn<f32>> {
+        None
+    }
+}
+
+/// Values pixels are unrolled to and packed from. 16 bit values span the whole range, float ones go from 0 to 1.
+pub trait FormatSample: Copy + Default + Debug {
+    fn from_u8(v: u8) -> Self;
+    fn from_u16(v: u16) -> Self;
+    fn from_f64(v: f64) -> Self;
+    fn to_u8(self) -> u8;
+    fn to_u16(self) -> u16;
+    fn to_f64(self) -> f64;
+    /// Value for formats where the minimum is white
+    fn reverse(self) -> Self;
+
+    /// Unroll routine provided by a plugin for the format
+    fn plugin_unroll(factory: &dyn FormatterFactory, format: PixelType) -> Option<UnrollFn<Self>>;
+
+    /// Pack routine provided by a plugin for the format
+    fn plugin_pack(factory: &dyn FormatterFactory, format: PixelType) -> Option<PackFn<Self>>;
+}
+
+impl FormatSample for u16 {
+    fn from_u8(v: u8) -> Self {
+        ((v as u16) << 8) | v as u16
+    }
+    fn from_u16(v: u16) -> Self {
+        v
+    }
+    fn from_f64(v: f64) -> Self {
+        quick_saturate_word(v * 65535.0)
+    }
+    fn to_u8(self) -> u8 {
+        ((self as u32 * 65281 + 8388608) >> 24) as u8
+    }
+    fn to_u16(self) -> u16 {
+        self
+    }
+    fn to_f64(self) -> f64 {
+        self as f64 / 65535.0
+    }
+    fn reverse(self) -> Self {
+        0xffff - self
+    }
+
+    fn plugin_unroll(factory: &dyn FormatterFactory, format: PixelType) -> Option<UnrollFn<u16>> {
+        factory.unroll_16(format)
+    }
+
+    fn plugin_pack(factory: &dyn FormatterFactory, format: PixelType) -> Option<PackFn<u16>> {
+        factory.pack_16(format)
+    }
+}
+
+impl FormatSample for f32 {
+    fn from_u8(v: u8) -> Self {
+        v as f32 / 255.0
+    }
+    fn from_u16(v: u16) -> Self {
+        v as f32 / 65535.0
+    }
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+    fn to_u8(self) -> u8 {
+        (self as f64 * 255.0 + 0.5).clamp(0.0, 255.0) as u8
+    }
+    fn to_u16(self) -> u16 {
+        quick_saturate_word(self as f64 * 65535.0)
+    }
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+    fn reverse(self) -> Self {
+        1.0 - self
+    }
+
+    fn plugin_unroll(factory: &dyn FormatterFactory, format: PixelType) -> Option<UnrollFn<f32>> {
+        factory.unroll_float(format)
+    }
+
+    fn plugin_pack(factory: &dyn FormatterFactory, format: PixelType) -> Option<PackFn<f32>> {
+        factory.pack_float(format)
+    }
+}
+
+/// Unroll routine for the format, from the plugins of `context` or built in
+pub fn find_unroll<T: FormatSample>(context: &Context, format: PixelType) -> Option<UnrollFn<T>> {
+    context.plugins().unroll(format).or_else(|| {
+        if is_builtin_format(format) {
+            Some(unroll_any::<T>)
+        } else {
+            None
+        }
+    })
+}
+
+/// Pack routine for the format, from the plugins of `context` or built in
+pub fn find_pack<T: FormatSample>(context: &Context, format: PixelType) -> Option<PackFn<T>> {
+    context.plugins().pack(format).or_else(|| {
+        if is_builtin_format(format) {
+            Some(pack_any::<T>)
+        } else {
+            None
+        }
+    })
+}
+
+/// Whether the built-in formatters handle the format: 8 and 16 bit integers, floats and doubles, chunky or
+/// planar. Premultiplied alpha and optimized formats are left to plugins, and so are floating point Lab, XYZ and
+/// ink based spaces, as the built-in formatters only know the 0..1 range.
+pub fn is_builtin_format(format: PixelType) -> bool {
+    let n_chan = format.channels() as usize;
+    let sizes_ok = matches!(
+        (format.float(), format.bps()),
+        (false, 1) | (false, 2) | (true, 4) | (true, 0)
+    );
+    let range_ok = !format.float() || !needs_float_scaling(format.color_space());
+
+    sizes_ok
+        && range_ok
+        && n_chan > 0
+        && n_chan <= MAX_CHANNELS
+        && !format.premul()
+        && !format.optimized()
+}
+
+/// Spaces whose float values aren't in the 0..1 range, like 0..100 for inks
+fn needs_float_scaling(space: ColorSpace) -> bool {
+    use ColorSpace::*;
+
+    matches!(
+        space,
+        Lab | LabV2
+            | Xyz
+            | Cmy
+            | Cmyk
+            | Mch5
+            | Mch6
+            | Mch7
+            | Mch8
+            | Mch9
+            | Mch10
+            | Mch11
+            | Mch12
+            | Mch13
+            | Mch14
+            | Mch15
+    )
+}
+
+/* ---------------------------------------------------- Built-in ---------------------------------------------------- */
+
+/// Position of a sample in the buffer
+fn sample_offset(format: PixelType, position: usize, stride: usize) -> usize {
+    if format.planar() {
+        position * stride
+    } else {
+        position * format.bytes()
+    }
+}
+
+/// Bytes to the next pixel
+fn pixel_advance(format: PixelType) -> usize {
+    if format.planar() {
+        format.bytes()
+    } else {
+        (format.channels() + format.extra()) as usize * format.bytes()
+    }
+}
+
+fn read_sample<T: FormatSample>(format: PixelType, input: &[u8]) -> T {
+    let value = match (format.float(), format.bps()) {
+        (false, 1) => T::from_u8(input[0]),
+        (false, _) => {
+            let word = u16::from_ne_bytes([input[0], input[1]]);
+            T::from_u16(if format.endian16() {
+                word.swap_bytes()
+            } else {
+                word
+            })
+        }
+        (true, 4) => {
+            let mut buf = [0u8; 4];
+            buf.copy_from_slice(&input[..4]);
+            T::from_f64(f32::from_ne_bytes(buf) as f64)
+        }
+        (true, _) => {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&input[..8]);
+            T::from_f64(f64::from_ne_bytes(buf))
+        }
+    };
+
+    if format.flavor() {
+        value.reverse()
+    } else {
+        value
+    }
+}
+
+fn write_sample<T: FormatSample>(format: PixelType, value: T, output: &mut [u8]) {
+    let value = if format.flavor() {
+        value.reverse()
+    } else {
+        value
+    };
+
+    match (format.float(), format.bps()) {
+        (false, 1) => output[0] = value.to_u8(),
+        (false, _) => {
+            let word = value.to_u16();
+            let word = if format.endian16() {
+                word.swap_bytes()
+            } else {
+                word
+            };
+            output[..2].copy_from_slice(&word.to_ne_bytes());
+        }
+        (true, 4) => output[..4].copy_from_slice(&(value.to_f64() as f32).to_ne_bytes()),
+        (true, _) => output[..8].copy_from_slice(&value.to_f64().to_ne_bytes()),
+    }
+}
+
+/// Reads any format accepted by `is_builtin_format`. Extra channels are skipped.
+pub fn unroll_any<T: FormatSample>(
+    format: PixelType,
+    values: &mut [T],
+    input: &[u8],
+    stride: usize,
+) -> usize {
+    let n_chan = format.channels() as usize;
+    let extra = format.extra() as usize;
+    let bytes = format.bytes();
+    let extra_first = format.do_swap() ^ format.swap_first();
+    let start = if extra_first { extra } else { 0 };
+
+    for i in 0..n_chan {
+        let index = if format.do_swap() { n_chan - i - 1 } else { i };
+        let offset = sample_offset(format, start + i, stride);
+        values[index] = read_sample(format, &input[offset..offset + bytes]);
+    }
+
+    if extra == 0 && format.swap_first() {
+        values[..n_chan].rotate_left(1);
+    }
+
+    pixel_advance(format)
+}
+
+/// Writes any format accepted by `is_builtin_format`. Extra channels are left untouched.
+pub fn pack_any<T: FormatSample>(
+    format: PixelType,
+    values: &[T],
+    output: &mut [u8],
+    stride: usize,
+) -> usize {
+    let n_chan = format.channels() as usize;
+    let extra = format.extra() as usize;
+    let bytes = format.bytes();
+    let extra_first = format.do_swap() ^ format.swap_first();
+    let start = if extra_first { extra } else { 0 };
+
+    let mut samples = [T::default(); MAX_CHANNELS];
+    for (i, sample) in samples[..n_chan].iter_mut().enumerate() {
+        let index = if format.do_swap() { n_chan - i - 1 } else { i };
+        *sample = values[index];
+    }
+
+    if extra == 0 && format.swap_first() {
+        samples[..n_chan].rotate_right(1);
+    }
+
+    for (i, sample) in samples[..n_chan].iter().enumerate() {
+        let offset = sample_offset(format, start + i, stride);
+        write_sample(format, *sample, &mut output[offset..offset + bytes]);
+    }
+
+    pixel_advance(format)
+}
@@ -0,0 +1,780 @@
+use std::fmt::{Debug, Formatter};
+
+use super::{MAX_INPUT_DIMENSIONS, MAX_STAGE_CHANNELS};
+use crate::Context;
+
+/// Flags used to pick an interpolation routine
+pub mod lerp_flags {
+    pub const BITS_16: u32 = 0x0000;
+    pub const FLOAT: u32 = 0x0001;
+    pub const TRILINEAR: u32 = 0x0100;
+}
+
+/// Interpolation routine. `input` holds one value per input dimension and `output` gets one per output channel.
+pub type InterpFn<T> = fn(input: &[T], output: &mut [T], p: &InterpParams<T>);
+
+/// Provides interpolation routines, registered with a `Plugin::Interpolation`. Routines are picked by number of
+/// inputs and outputs and by `lerp_flags`, overriding the built-in ones. Returning `None` leaves the choice to
+/// plugins registered earlier and then to the built-in routines.
+pub trait InterpolatorFactory: Send + Sync {
+    /// Routine for tables with 16 bit samples
+    fn interpolator_16(
+        &self,
+        _n_inputs: usize,
+        _n_outputs: usize,
+        _flags: u32,
+    ) -> Option<InterpFn<u16>> {
+        None
+    }
+
+    /// Routine for tables with float samples
+    fn interpolator_float(
+        &self,
+        _n_inputs: usize,
+        _n_outputs: usize,
+        _flags: u32,
+    ) -> Option<InterpFn<f32>> {
+        None
+    }
+}
+
+/// Sample types that can be interpolated
+pub trait InterpSample: Copy + Default + Debug + PartialEq {
+    /// Built-in routine for the given configuration
+    fn default_interpolator(
+        n_inputs: usize,
+        n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<Self>>;
+
+    /// Routine provided by a plugin for the given configuration
+    fn plugin_interpolator(
+        factory: &dyn InterpolatorFactory,
+        n_inputs: usize,
+        n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<Self>>;
+}
+
+/// A table sampled on a regular grid, along with the routine that interpolates it
+#[derive(Clone)]
+pub struct InterpParams<T> {
+    pub flags: u32,
+    pub n_inputs: usize,
+    pub n_outputs: usize,
+    /// Number of grid points of each input
+    pub n_samples: Vec<u32>,
+    /// Number of grid points of each input minus one
+    pub domain: Vec<u32>,
+    /// Distance in the table between two neighbour grid points of each input
+    pub opta: Vec<u32>,
+    pub table: Vec<T>,
+    interpolation: InterpFn<T>,
+}
+
+impl<T: InterpSample> InterpParams<T> {
+    /// Creates the interpolation for a table with the given number of grid points per input. Returns `None` if
+    /// the table size doesn't match or there is no routine for the configuration.
+    pub fn new(n_samples: &[u32], n_outputs: usize, table: Vec<T>, flags: u32) -> Option<Self> {
+        Self::with_interpolator(n_samples, n_outputs, table, flags, None)
+    }
+
+    /// Same as `new`, but uses the routines registered in `context` over the built-in ones
+    pub fn new_with_context(
+        context: &Context,
+        n_samples: &[u32],
+        n_outputs: usize,
+        table: Vec<T>,
+        flags: u32,
+    ) -> Option<Self> {
+        let interpolation = context
+            .plugins()
+            .interpolator(n_samples.len(), n_outputs, flags);
+        Self::with_interpolator(n_samples, n_outputs, table, flags, interpolation)
+    }
+
+    /// Same as `new`, but uses `interpolation` instead of the built-in routine if given
+    pub fn with_interpolator(
+        n_samples: &[u32],
+        n_outputs: usize,
+        table: Vec<T>,
+        flags: u32,
+        interpolation: Option<InterpFn<T>>,
+    ) -> Option<Self> {
+        let n_inputs = n_samples.len();
+        if n_inputs == 0 || n_inputs > MAX_INPUT_DIMENSIONS || n_outputs > MAX_STAGE_CHANNELS {
+            return None;
+        }
+
+        let mut opta = vec![0u32; n_inputs];
+        opta[n_inputs - 1] = n_outputs as u32;
+        for i in (0..n_inputs - 1).rev() {
+            opta[i] = opta[i + 1].checked_mul(n_samples[i + 1])?;
+        }
+        let size = (opta[0] as usize).checked_mul(n_samples[0] as usize)?;
+        if table.len() != size {
+            return None;
+        }
+
+        let interpolation = match interpolation {
+            Some(f) => f,
+            None => T::default_interpolator(n_inputs, n_outputs, flags)?,
+        };
+
+        Some(Self {
+            flags,
+            n_inputs,
+            n_outputs,
+            n_samples: n_samples.to_vec(),
+            domain: n_samples.iter().map(|n| n.saturating_sub(1)).collect(),
+            opta,
+            table,
+            interpolation,
+        })
+    }
+
+    /// Switches to the routine registered in `context` for this configuration, if any. Returns whether it did.
+    pub fn use_interpolators(&mut self, context: &Context) -> bool {
+        match context
+            .plugins()
+            .interpolator(self.n_inputs, self.n_outputs, self.flags)
+        {
+            Some(f) => {
+                self.interpolation = f;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn eval(&self, input: &[T], output: &mut [T]) {
+        (self.interpolation)(input, output, self)
+    }
+}
+
+impl<T: PartialEq> PartialEq for InterpParams<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.flags == other.flags
+            && self.n_outputs == other.n_outputs
+            && self.n_samples == other.n_samples
+            && self.table == other.table
+    }
+}
+
+impl<T: Debug> Debug for InterpParams<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InterpParams")
+            .field("flags", &self.flags)
+            .field("n_samples", &self.n_samples)
+            .field("n_outputs", &self.n_outputs)
+            .field("table", &self.table)
+            .finish()
+    }
+}
+
+impl InterpSample for u16 {
+    fn default_interpolator(
+        n_inputs: usize,
+        n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<u16>> {
+        let trilinear = flags & lerp_flags::TRILINEAR != 0;
+        match n_inputs {
+            1 if n_outputs == 1 => Some(lin_lerp_1d),
+            1 => Some(eval_1_input),
+            2 => Some(bilinear_interp_16),
+            3 if trilinear => Some(trilinear_interp_16),
+            3 => Some(tetrahedral_interp_16),
+            4..=MAX_INPUT_DIMENSIONS => Some(eval_n_inputs_16),
+            _ => None,
+        }
+    }
+
+    fn plugin_interpolator(
+        factory: &dyn InterpolatorFactory,
+        n_inputs: usize,
+        n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<u16>> {
+        factory.interpolator_16(n_inputs, n_outputs, flags)
+    }
+}
+
+impl InterpSample for f32 {
+    fn default_interpolator(
+        n_inputs: usize,
+        n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<f32>> {
+        let trilinear = flags & lerp_flags::TRILINEAR != 0;
+        match n_inputs {
+            1 if n_outputs == 1 => Some(lin_lerp_1d_float),
+            1 => Some(eval_1_input_float),
+            2 => Some(bilinear_interp_float),
+            3 if trilinear => Some(trilinear_interp_float),
+            3 => Some(tetrahedral_interp_float),
+            4..=MAX_INPUT_DIMENSIONS => Some(eval_n_inputs_float),
+            _ => None,
+        }
+    }
+
+    fn plugin_interpolator(
+        factory: &dyn InterpolatorFactory,
+        n_inputs: usize,
+        n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<f32>> {
+        factory.interpolator_float(n_inputs, n_outputs, flags)
+    }
+}
+
+/* ---------------------------------------------------- Helpers ----------------------------------------------------- */
+
+/// Converts a value scaled by the domain to 15.16 fixed point
+#[inline]
+pub fn to_fixed_domain(a: i32) -> i32 {
+    a + ((a + 0x7fff) / 0xffff)
+}
+
+#[inline]
+fn fixed_to_int(x: i32) -> i32 {
+    x >> 16
+}
+
+#[inline]
+fn fixed_rest_to_int(x: i32) -> i32 {
+    x & 0xFFFF
+}
+
+#[inline]
+fn round_fixed_to_int(x: i64) -> i32 {
+    ((x + 0x8000) >> 16) as i32
+}
+
+#[inline]
+fn linear_interp(a: i32, l: i32, h: i32) -> u16 {
+    let dif = (h - l) as i64 * a as i64 + 0x8000;
+    ((dif >> 16) + l as i64) as u16
+}
+
+#[inline]
+fn lerp_16(a: i32, l: i32, h: i32) -> i32 {
+    l + round_fixed_to_int((h - l) as i64 * a as i64)
+}
+
+/// Clamps to [0, 1], sending NaN to zero
+#[inline]
+fn fclamp(v: f32) -> f32 {
+    if v < 1.0e-9 || v.is_nan() {
+        0.0
+    } else if v > 1.0 {
+        1.0
+    } else {
+        v
+    }
+}
+
+/// Splits a 16 bit input on the grid of `domain` points into the cell index and the position inside the cell
+#[inline]
+fn split_16(input: u16, domain: u32) -> (u32, i32) {
+    let f = to_fixed_domain(input as i32 * domain as i32);
+    (fixed_to_int(f) as u32, fixed_rest_to_int(f))
+}
+
+/// Same as `split_16` for float inputs
+#[inline]
+fn split_float(input: f32, domain: u32) -> (u32, f32) {
+    let p = fclamp(input) * domain as f32;
+    let k = p.floor();
+    (k as u32, p - k)
+}
+
+/* --------------------------------------------------- 1 input ------------------------------------------------------ */
+
+/// Linear interpolation of a single 16 bit curve
+pub fn lin_lerp_1d(input: &[u16], output: &mut [u16], p: &InterpParams<u16>) {
+    output[0] = lerp_table_16(&p.table, input[0]);
+}
+
+/// Interpolates a 16 bit table as a curve, with as many points as the table holds
+pub fn lerp_table_16(table: &[u16], value: u16) -> u16 {
+    let domain = table.len().saturating_sub(1) as u32;
+
+    // If last value or just one point
+    if value == 0xffff || domain == 0 {
+        return table[domain as usize];
+    }
+
+    let (cell0, rest) = split_16(value, domain);
+    let y0 = table[cell0 as usize] as i32;
+    let y1 = table[cell0 as usize + 1] as i32;
+
+    linear_interp(rest, y0, y1)
+}
+
+/// Interpolates a float table as a curve over [0, 1]
+pub fn lerp_table_float(table: &[f32], value: f32) -> f32 {
+    let value = fclamp(value);
+    let domain = table.len().saturating_sub(1);
+
+    // If last value or just one point
+    if value == 1.0 || domain == 0 {
+        return table[domain];
+    }
+
+    let val2 = domain as f32 * value;
+    let cell0 = val2.floor();
+    let cell1 = val2.ceil();
+    let rest = val2 - cell0;
+
+    let y0 = table[cell0 as usize];
+    let y1 = table[cell1 as usize];
+
+    y0 + (y1 - y0) * rest
+}
+
+pub fn lin_lerp_1d_float(input: &[f32], output: &mut [f32], p: &InterpParams<f32>) {
+    output[0] = lerp_table_float(&p.table, input[0]);
+}
+
+/// 1 input, many outputs
+pub fn eval_1_input(input: &[u16], output: &mut [u16], p: &InterpParams<u16>) {
+    let opta = p.opta[0] as usize;
+
+    if input[0] == 0xffff || p.domain[0] == 0 {
+        let y0 = p.domain[0] as usize * opta;
+        output[..p.n_outputs].copy_from_slice(&p.table[y0..y0 + p.n_outputs]);
+        return;
+    }
+
+    let (k0, rk) = split_16(input[0], p.domain[0]);
+    let k0 = k0 as usize * opta;
+    let k1 = k0 + opta;
+
+    for (i, out) in output.iter_mut().enumerate().take(p.n_outputs) {
+        *out = linear_interp(rk, p.table[k0 + i] as i32, p.table[k1 + i] as i32);
+    }
+}
+
+pub fn eval_1_input_float(input: &[f32], output: &mut [f32], p: &InterpParams<f32>) {
+    let value = fclamp(input[0]);
+    let opta = p.opta[0] as usize;
+
+    if value == 1.0 || p.domain[0] == 0 {
+        let y0 = p.domain[0] as usize * opta;
+        output[..p.n_outputs].copy_from_slice(&p.table[y0..y0 + p.n_outputs]);
+        return;
+    }
+
+    let (k0, rest) = split_float(value, p.domain[0]);
+    let k0 = k0 as usize * opta;
+    let k1 = k0 + opta;
+
+    for (i, out) in output.iter_mut().enumerate().take(p.n_outputs) {
+        let y0 = p.table[k0 + i];
+        let y1 = p.table[k1 + i];
+        *out = y0 + (y1 - y0) * rest;
+    }
+}
+
+/* --------------------------------------------------- 2 inputs ----------------------------------------------------- */
+
+pub fn bilinear_interp_16(input: &[u16], output: &mut [u16], p: &InterpParams<u16>) {
+    let (x0, rx) = split_16(input[0], p.domain[0]);
+    let (y0, ry) = split_16(input[1], p.domain[1]);
+
+    let x0 = (p.opta[0] * x0) as usize;
+    let x1 = x0
+        + if input[0] == 0xffff {
+            0
+        } else {
+            p.opta[0] as usize
+        };
+    let y0 = (p.opta[1] * y0) as usize;
+    let y1 = y0
+        + if input[1] == 0xffff {
+            0
+        } else {
+            p.opta[1] as usize
+        };
+
+    let t = &p.table;
+    for (i, out) in output.iter_mut().enumerate().take(p.n_outputs) {
+        let dens = |x: usize, y: usize| t[x + y + i] as i32;
+
+        let dx0 = lerp_16(rx, dens(x0, y0), dens(x1, y0));
+        let dx1 = lerp_16(rx, dens(x0, y1), dens(x1, y1));
+        *out = lerp_16(ry, dx0, dx1) as u16;
+    }
+}
+
+pub fn bilinear_interp_float(input: &[f32], output: &mut [f32], p: &InterpParams<f32>) {
+    let (px, py) = (fclamp(input[0]), fclamp(input[1]));
+    let (x0, rx) = split_float(px, p.domain[0]);
+    let (y0, ry) = split_float(py, p.domain[1]);
+
+    let x0 = (p.opta[0] * x0) as usize;
+    let x1 = x0 + if px >= 1.0 { 0 } else { p.opta[0] as usize };
+    let y0 = (p.opta[1] * y0) as usize;
+    let y1 = y0 + if py >= 1.0 { 0 } else { p.opta[1] as usize };
+
+    let t = &p.table;
+    for (i, out) in output.iter_mut().enumerate().take(p.n_outputs) {
+        let dens = |x: usize, y: usize| t[x + y + i];
+        let lerp = |a: f32, l: f32, h: f32| l + (h - l) * a;
+
+        let dx0 = lerp(rx, dens(x0, y0), dens(x1, y0));
+        let dx1 = lerp(rx, dens(x0, y1), dens(x1, y1));
+        *out = lerp(ry, dx0, dx1);
+    }
+}
+
+/* --------------------------------------------------- 3 inputs ----------------------------------------------------- */
+
+fn trilinear_16(input: &[u16], output: &mut [u16], table: &[u16], domain: &[u32], opta: &[u32]) {
+    let (x0, rx) = split_16(input[0], domain[0]);
+    let (y0, ry) = split_16(input[1], domain[1]);
+    let (z0, rz) = split_16(input[2], domain[2]);
+
+    let x0 = (opta[0] * x0) as usize;
+    let x1 = x0
+        + if input[0] == 0xffff {
+            0
+        } else {
+            opta[0] as usize
+        };
+    let y0 = (opta[1] * y0) as usize;
+    let y1 = y0
+        + if input[1] == 0xffff {
+            0
+        } else {
+            opta[1] as usize
+        };
+    let z0 = (opta[2] * z0) as usize;
+    let z1 = z0
+        + if input[2] == 0xffff {
+            0
+        } else {
+            opta[2] as usize
+        };
+
+    for (i, out) in output.iter_mut().enumerate() {
+        let dens = |x: usize, y: usize, z: usize| table[x + y + z + i] as i32;
+
+        let dx00 = lerp_16(rx, dens(x0, y0, z0), dens(x1, y0, z0));
+        let dx01 = lerp_16(rx, dens(x0, y0, z1), dens(x1, y0, z1));
+        let dx10 = lerp_16(rx, dens(x0, y1, z0), dens(x1, y1, z0));
+        let dx11 = lerp_16(rx, dens(x0, y1, z1), dens(x1, y1, z1));
+
+        let dxy0 = lerp_16(ry, dx00, dx10);
+        let dxy1 = lerp_16(ry, dx01, dx11);
+
+        *out = lerp_16(rz, dxy0, dxy1) as u16;
+    }
+}
+
+pub fn trilinear_interp_16(input: &[u16], output: &mut [u16], p: &InterpParams<u16>) {
+    trilinear_16(
+        input,
+        &mut output[..p.n_outputs],
+        &p.table,
+        &p.domain,
+        &p.opta,
+    )
+}
+
+fn trilinear_float(input: &[f32], output: &mut [f32], table: &[f32], domain: &[u32], opta: &[u32]) {
+    let (px, py, pz) = (fclamp(input[0]), fclamp(input[1]), fclamp(input[2]));
+    let (x0, rx) = split_float(px, domain[0]);
+    let (y0, ry) = split_float(py, domain[1]);
+    let (z0, rz) = split_float(pz, domain[2]);
+
+    let x0 = (opta[0] * x0) as usize;
+    let x1 = x0 + if px >= 1.0 { 0 } else { opta[0] as usize };
+    let y0 = (opta[1] * y0) as usize;
+    let y1 = y0 + if py >= 1.0 { 0 } else { opta[1] as usize };
+    let z0 = (opta[2] * z0) as usize;
+    let z1 = z0 + if pz >= 1.0 { 0 } else { opta[2] as usize };
+
+    for (i, out) in output.iter_mut().enumerate() {
+        let dens = |x: usize, y: usize, z: usize| table[x + y + z + i];
+        let lerp = |a: f32, l: f32, h: f32| l + (h - l) * a;
+
+        let dx00 = lerp(rx, dens(x0, y0, z0), dens(x1, y0, z0));
+        let dx01 = lerp(rx, dens(x0, y0, z1), dens(x1, y0, z1));
+        let dx10 = lerp(rx, dens(x0, y1, z0), dens(x1, y1, z0));
+        let dx11 = lerp(rx, dens(x0, y1, z1), dens(x1, y1, z1));
+
+        let dxy0 = lerp(ry, dx00, dx10);
+        let dxy1 = lerp(ry, dx01, dx11);
+
+        *out = lerp(rz, dxy0, dxy1);
+    }
+}
+
+pub fn trilinear_interp_float(input: &[f32], output: &mut [f32], p: &InterpParams<f32>) {
+    trilinear_float(
+        input,
+        &mut output[..p.n_outputs],
+        &p.table,
+        &p.domain,
+        &p.opta,
+    )
+}
+
+/// Tetrahedral interpolation, picking the tetrahedron of the cube containing the point
+fn tetrahedral_16(input: &[u16], output: &mut [u16], table: &[u16], domain: &[u32], opta: &[u32]) {
+    let (x0, rx) = split_16(input[0], domain[0]);
+    let (y0, ry) = split_16(input[1], domain[1]);
+    let (z0, rz) = split_16(input[2], domain[2]);
+
+    let x0 = (opta[0] * x0) as usize;
+    let x1 = x0
+        + if input[0] == 0xffff {
+            0
+        } else {
+            opta[0] as usize
+        };
+    let y0 = (opta[1] * y0) as usize;
+    let y1 = y0
+        + if input[1] == 0xffff {
+            0
+        } else {
+            opta[1] as usize
+        };
+    let z0 = (opta[2] * z0) as usize;
+    let z1 = z0
+        + if input[2] == 0xffff {
+            0
+        } else {
+            opta[2] as usize
+        };
+
+    for (i, out) in output.iter_mut().enumerate() {
+        let dens = |x: usize, y: usize, z: usize| table[x + y + z + i] as i32;
+
+        let c0 = dens(x0, y0, z0);
+        let (c1, c2, c3) = if rx >= ry && ry >= rz {
+            (
+                dens(x1, y0, z0) - c0,
+                dens(x1, y1, z0) - dens(x1, y0, z0),
+                dens(x1, y1, z1) - dens(x1, y1, z0),
+            )
+        } else if rx >= rz && rz >= ry {
+            (
+                dens(x1, y0, z0) - c0,
+                dens(x1, y1, z1) - dens(x1, y0, z1),
+                dens(x1, y0, z1) - dens(x1, y0, z0),
+            )
+        } else if rz >= rx && rx >= ry {
+            (
+                dens(x1, y0, z1) - dens(x0, y0, z1),
+                dens(x1, y1, z1) - dens(x1, y0, z1),
+                dens(x0, y0, z1) - c0,
+            )
+        } else if ry >= rx && rx >= rz {
+            (
+                dens(x1, y1, z0) - dens(x0, y1, z0),
+                dens(x0, y1, z0) - c0,
+                dens(x1, y1, z1) - dens(x1, y1, z0),
+            )
+        } else if ry >= rz && rz >= rx {
+            (
+                dens(x1, y1, z1) - dens(x0, y1, z1),
+                dens(x0, y1, z0) - c0,
+                dens(x0, y1, z1) - dens(x0, y1, z0),
+            )
+        } else {
+            (
+                dens(x1, y1, z1) - dens(x0, y1, z1),
+                dens(x0, y1, z1) - dens(x0, y0, z1),
+                dens(x0, y0, z1) - c0,
+            )
+        };
+
+        let rest = c1 as i64 * rx as i64 + c2 as i64 * ry as i64 + c3 as i64 * rz as i64 + 0x8001;
+        *out = (c0 as i64 + ((rest + (rest >> 16)) >> 16)) as u16;
+    }
+}
+
+pub fn tetrahedral_interp_16(input: &[u16], output: &mut [u16], p: &InterpParams<u16>) {
+    tetrahedral_16(
+        input,
+        &mut output[..p.n_outputs],
+        &p.table,
+        &p.domain,
+        &p.opta,
+    )
+}
+
+fn tetrahedral_float(
+    input: &[f32],
+    output: &mut [f32],
+    table: &[f32],
+    domain: &[u32],
+    opta: &[u32],
+) {
+    let (px, py, pz) = (fclamp(input[0]), fclamp(input[1]), fclamp(input[2]));
+    let (x0, rx) = split_float(px, domain[0]);
+    let (y0, ry) = split_float(py, domain[1]);
+    let (z0, rz) = split_float(pz, domain[2]);
+
+    let x0 = (opta[0] * x0) as usize;
+    let x1 = x0 + if px >= 1.0 { 0 } else { opta[0] as usize };
+    let y0 = (opta[1] * y0) as usize;
+    let y1 = y0 + if py >= 1.0 { 0 } else { opta[1] as usize };
+    let z0 = (opta[2] * z0) as usize;
+    let z1 = z0 + if pz >= 1.0 { 0 } else { opta[2] as usize };
+
+    for (i, out) in output.iter_mut().enumerate() {
+        let dens = |x: usize, y: usize, z: usize| table[x + y + z + i];
+
+        let c0 = dens(x0, y0, z0);
+        let (c1, c2, c3) = if rx >= ry && ry >= rz {
+            (
+                dens(x1, y0, z0) - c0,
+                dens(x1, y1, z0) - dens(x1, y0, z0),
+                dens(x1, y1, z1) - dens(x1, y1, z0),
+            )
+        } else if rx >= rz && rz >= ry {
+            (
+                dens(x1, y0, z0) - c0,
+                dens(x1, y1, z1) - dens(x1, y0, z1),
+                dens(x1, y0, z1) - dens(x1, y0, z0),
+            )
+        } else if rz >= rx && rx >= ry {
+            (
+                dens(x1, y0, z1) - dens(x0, y0, z1),
+                dens(x1, y1, z1) - dens(x1, y0, z1),
+                dens(x0, y0, z1) - c0,
+            )
+        } else if ry >= rx && rx >= rz {
+            (
+                dens(x1, y1, z0) - dens(x0, y1, z0),
+                dens(x0, y1, z0) - c0,
+                dens(x1, y1, z1) - dens(x1, y1, z0),
+            )
+        } else if ry >= rz && rz >= rx {
+            (
+                dens(x1, y1, z1) - dens(x0, y1, z1),
+                dens(x0, y1, z0) - c0,
+                dens(x0, y1, z1) - dens(x0, y1, z0),
+            )
+        } else if rz >= ry && ry >= rx {
+            (
+                dens(x1, y1, z1) - dens(x0, y1, z1),
+                dens(x0, y1, z1) - dens(x0, y0, z1),
+                dens(x0, y0, z1) - c0,
+            )
+        } else {
+            (0.0, 0.0, 0.0)
+        };
+
+        *out = c0 + c1 * rx + c2 * ry + c3 * rz;
+    }
+}
+
+pub fn tetrahedral_interp_float(input: &[f32], output: &mut [f32], p: &InterpParams<f32>) {
+    tetrahedral_float(
+        input,
+        &mut output[..p.n_outputs],
+        &p.table,
+        &p.domain,
+        &p.opta,
+    )
+}
+
+/* ------------------------------------------------- 4 to 15 inputs ------------------------------------------------- */
+
+/// Interpolates the two hyperplanes around the first input, then blends them. Ends up in tetrahedral.
+fn eval_n_16(input: &[u16], output: &mut [u16], table: &[u16], domain: &[u32], opta: &[u32]) {
+    if input.len() == 3 {
+        return tetrahedral_16(input, output, table, domain, opta);
+    }
+
+    let (k0, rk) = split_16(input[0], domain[0]);
+    let k0 = (opta[0] * k0) as usize;
+    let k1 = k0
+        + if input[0] == 0xffff {
+            0
+        } else {
+            opta[0] as usize
+        };
+
+    let n = output.len();
+    let mut tmp1 = [0u16; MAX_STAGE_CHANNELS];
+    let mut tmp2 = [0u16; MAX_STAGE_CHANNELS];
+
+    eval_n_16(
+        &input[1..],
+        &mut tmp1[..n],
+        &table[k0..],
+        &domain[1..],
+        &opta[1..],
+    );
+    eval_n_16(
+        &input[1..],
+        &mut tmp2[..n],
+        &table[k1..],
+        &domain[1..],
+        &opta[1..],
+    );
+
+    for (i, out) in output.iter_mut().enumerate() {
+        *out = linear_interp(rk, tmp1[i] as i32, tmp2[i] as i32);
+    }
+}
+
+pub fn eval_n_inputs_16(input: &[u16], output: &mut [u16], p: &InterpParams<u16>) {
+    eval_n_16(
+        &input[..p.n_inputs],
+        &mut output[..p.n_outputs],
+        &p.table,
+        &p.domain,
+        &p.opta,
+    )
+}
+
+fn eval_n_float(input: &[f32], output: &mut [f32], table: &[f32], domain: &[u32], opta: &[u32]) {
+    if input.len() == 3 {
+        return tetrahedral_float(input, output, table, domain, opta);
+    }
+
+    let pk = fclamp(input[0]);
+    let (k0, rest) = split_float(pk, domain[0]);
+    let k0 = (opta[0] * k0) as usize;
+    let k1 = k0 + if pk >= 1.0 { 0 } else { opta[0] as usize };
+
+    let n = output.len();
+    let mut tmp1 = [0f32; MAX_STAGE_CHANNELS];
+    let mut tmp2 = [0f32; MAX_STAGE_CHANNELS];
+
+    eval_n_float(
+        &input[1..],
+        &mut tmp1[..n],
+        &table[k0..],
+        &domain[1..],
+        &opta[1..],
+    );
+    eval_n_float(
+        &input[1..],
+        &mut tmp2[..n],
+        &table[k1..],
+        &domain[1..],
+        &opta[1..],
+    );
+
+    for (i, out) in output.iter_mut().enumerate() {
+        *out = tmp1[i] + (tmp2[i] - tmp1[i]) * rest;
+    }
+}
+
+pub fn eval_n_inputs_float(input: &[f32], output: &mut [f32], p: &InterpParams<f32>) {
+    eval_n_float(
+        &input[..p.n_inputs],
+        &mut output[..p.n_outputs],
+        &p.table,
+        &p.domain,
+        &p.opta,
+    )
+}
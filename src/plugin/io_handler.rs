@@ -0,0 +1,398 @@
+use std::fs::File;
+use std::io::{Error, ErrorKind, Result};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Source or sink of profile data. Positions are absolute and 32 bits wide, as in the ICC format.
+///
+/// Reads through the `plugin::read_*` helpers fail with `UnexpectedEof` if fewer bytes than requested are
+/// available.
+pub trait IoHandler: Read + Write {
+    /// Moves to an absolute position
+    fn seek(&mut self, offset: u32) -> Result<()>;
+    /// Current position
+    fn tell(&self) -> u32;
+    /// Number of bytes written so far, counting up to the furthest position reached
+    fn used_space(&self) -> u32;
+    /// Total size of the underlying data when it was opened
+    fn reported_size(&self) -> u32;
+}
+
+fn out_of_bounds_error(offset: u32) -> Error {
+    Error::new(
+        ErrorKind::UnexpectedEof,
+        format!(
+            "Seek to {} is out of bounds; probably corrupted profile",
+            offset
+        ),
+    )
+}
+
+fn read_only_error() -> Error {
+    Error::new(ErrorKind::PermissionDenied, "Handler is read only")
+}
+
+fn too_big_error() -> Error {
+    Error::new(ErrorKind::InvalidData, "Data does not fit in 32 bits")
+}
+
+enum MemoryBlock<'a> {
+    Read(&'a [u8]),
+    Write(&'a mut [u8]),
+}
+
+/// Reads from or writes to a fixed size block of memory
+pub struct MemoryIo<'a> {
+    block: MemoryBlock<'a>,
+    pointer: usize,
+    used: usize,
+}
+
+impl<'a> MemoryIo<'a> {
+    /// Read only handler over existing data
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            used: data.len(),
+            block: MemoryBlock::Read(data),
+            pointer: 0,
+        }
+    }
+    /// Handler writing into a buffer. Writing past its end fails.
+    pub fn new_writable(buffer: &'a mut [u8]) -> Self {
+        Self {
+            block: MemoryBlock::Write(buffer),
+            pointer: 0,
+            used: 0,
+        }
+    }
+    fn len(&self) -> usize {
+        match &self.block {
+            MemoryBlock::Read(data) => data.len(),
+            MemoryBlock::Write(data) => data.len(),
+        }
+    }
+    /// Written part of the buffer
+    pub fn data(&self) -> &[u8] {
+        match &self.block {
+            MemoryBlock::Read(data) => data,
+            MemoryBlock::Write(data) => &data[..self.used],
+        }
+    }
+}
+
+impl Read for MemoryIo<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let data: &[u8] = match &self.block {
+            MemoryBlock::Read(data) => data,
+            MemoryBlock::Write(data) => &data[..self.used],
+        };
+        if self.pointer >= data.len() {
+            return Ok(0);
+        }
+        let available = data.len() - self.pointer;
+        let len = buf.len().min(available);
+
+        buf[..len].copy_from_slice(&data[self.pointer..self.pointer + len]);
+        self.pointer += len;
+        Ok(len)
+    }
+}
+
+impl Write for MemoryIo<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let data = match &mut self.block {
+            MemoryBlock::Read(_) => return Err(read_only_error()),
+            MemoryBlock::Write(data) => data,
+        };
+        let available = data.len().saturating_sub(self.pointer);
+        let len = buf.len().min(available);
+
+        data[self.pointer..self.pointer + len].copy_from_slice(&buf[..len]);
+        self.pointer += len;
+        self.used = self.used.max(self.pointer);
+        Ok(len)
+    }
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl IoHandler for MemoryIo<'_> {
+    fn seek(&mut self, offset: u32) -> Result<()> {
+        if offset as usize > self.len() {
+            return Err(out_of_bounds_error(offset));
+        }
+        self.pointer = offset as usize;
+        Ok(())
+    }
+    fn tell(&self) -> u32 {
+        self.pointer as u32
+    }
+    fn used_space(&self) -> u32 {
+        self.used as u32
+    }
+    fn reported_size(&self) -> u32 {
+        self.len() as u32
+    }
+}
+
+/// Reads from and writes to a growable vector
+#[derive(Default)]
+pub struct VecIo {
+    data: Vec<u8>,
+    pointer: usize,
+    reported: usize,
+}
+
+impl VecIo {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl From<Vec<u8>> for VecIo {
+    fn from(data: Vec<u8>) -> Self {
+        Self {
+            reported: data.len(),
+            data,
+            pointer: 0,
+        }
+    }
+}
+
+impl Read for VecIo {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.pointer >= self.data.len() {
+            return Ok(0);
+        }
+        let available = self.data.len() - self.pointer;
+        let len = buf.len().min(available);
+
+        buf[..len].copy_from_slice(&self.data[self.pointer..self.pointer + len]);
+        self.pointer += len;
+        Ok(len)
+    }
+}
+
+impl Write for VecIo {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let end = self.pointer + buf.len();
+        if end > u32::MAX as usize {
+            return Err(too_big_error());
+        }
+        if end > self.data.len() {
+            self.data.resize(end, 0);
+        }
+        self.data[self.pointer..end].copy_from_slice(buf);
+        self.pointer = end;
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl IoHandler for VecIo {
+    fn seek(&mut self, offset: u32) -> Result<()> {
+        // Seeking past the end is allowed, the gap gets filled with zeros on the next write
+        self.pointer = offset as usize;
+        Ok(())
+    }
+    fn tell(&self) -> u32 {
+        self.pointer as u32
+    }
+    fn used_space(&self) -> u32 {
+        self.data.len() as u32
+    }
+    fn reported_size(&self) -> u32 {
+        self.reported as u32
+    }
+}
+
+/// Reads from or writes to a file
+pub struct FileIo {
+    file: File,
+    pointer: u32,
+    used: u32,
+    reported: u32,
+}
+
+impl FileIo {
+    /// Opens an existing file for reading
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file(File::open(path)?)
+    }
+    /// Creates or truncates a file for writing
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file(File::create(path)?)
+    }
+    pub fn from_file(file: File) -> Result<Self> {
+        let len = file.metadata()?.len();
+        if len > u32::MAX as u64 {
+            return Err(too_big_error());
+        }
+
+        Ok(Self {
+            file,
+            pointer: 0,
+            used: 0,
+            reported: len as u32,
+        })
+    }
+}
+
+impl Read for FileIo {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.file.read(buf)?;
+        self.pointer += len as u32;
+        Ok(len)
+    }
+}
+
+impl Write for FileIo {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if self.pointer as usize + buf.len() > u32::MAX as usize {
+            return Err(too_big_error());
+        }
+        let len = self.file.write(buf)?;
+        self.pointer += len as u32;
+        self.used = self.used.max(self.pointer);
+        Ok(len)
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.file.flush()
+    }
+}
+
+impl IoHandler for FileIo {
+    fn seek(&mut self, offset: u32) -> Result<()> {
+        self.file.seek(SeekFrom::Start(offset as u64))?;
+        self.pointer = offset;
+        Ok(())
+    }
+    fn tell(&self) -> u32 {
+        self.pointer
+    }
+    fn used_space(&self) -> u32 {
+        self.used
+    }
+    fn reported_size(&self) -> u32 {
+        self.reported
+    }
+}
+
+/// Discards everything written to it and only keeps count. Used to compute sizes before writing.
+#[derive(Default)]
+pub struct NullIo {
+    pointer: u32,
+    used: u32,
+}
+
+impl NullIo {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Read for NullIo {
+    fn read(&mut self, _buf: &mut [u8]) -> Result<usize> {
+        Ok(0)
+    }
+}
+
+impl Write for NullIo {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.pointer = self
+            .pointer
+            .checked_add(buf.len() as u32)
+            .ok_or_else(too_big_error)?;
+        self.used = self.used.max(self.pointer);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl IoHandler for NullIo {
+    fn seek(&mut self, offset: u32) -> Result<()> {
+        self.pointer = offset;
+        Ok(())
+    }
+    fn tell(&self) -> u32 {
+        self.pointer
+    }
+    fn used_space(&self) -> u32 {
+        self.used
+    }
+    fn reported_size(&self) -> u32 {
+        0
+    }
+}
+
+/// Restricts another handler to the bytes of a single tag. Positions are relative to the start of the tag,
+/// which is what offsets stored inside tags are relative to.
+pub struct TagIo<'a> {
+    inner: &'a mut dyn IoHandler,
+    base: u32,
+    size: u32,
+}
+
+impl<'a> TagIo<'a> {
+    /// Bounds `inner` to `size` bytes starting at `base` and moves to the start of the tag
+    pub fn new(inner: &'a mut dyn IoHandler, base: u32, size: u32) -> Result<Self> {
+        base.checked_add(size).ok_or_else(too_big_error)?;
+        inner.seek(base)?;
+        Ok(Self { inner, base, size })
+    }
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+    fn remaining(&self) -> usize {
+        (self.base + self.size).saturating_sub(self.inner.tell()) as usize
+    }
+}
+
+impl Read for TagIo<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = buf.len().min(self.remaining());
+        self.inner.read(&mut buf[..len])
+    }
+}
+
+impl Write for TagIo<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let len = buf.len().min(self.remaining());
+        self.inner.write(&buf[..len])
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl IoHandler for TagIo<'_> {
+    fn seek(&mut self, offset: u32) -> Result<()> {
+        if offset > self.size {
+            return Err(out_of_bounds_error(offset));
+        }
+        self.inner.seek(self.base + offset)
+    }
+    fn tell(&self) -> u32 {
+        self.inner.tell() - self.base
+    }
+    fn used_space(&self) -> u32 {
+        self.inner
+            .used_space()
+            .saturating_sub(self.base)
+            .min(self.size)
+    }
+    fn reported_size(&self) -> u32 {
+        self.size
+    }
+}
@@ -38,6 +38,7 @@ impl Mat3 {
         [self.vx.as_array(), self.vy.as_array(), self.vz.as_array()]
     }
 
+    #[allow(clippy::needless_return)]
     pub fn is_identity(self) -> bool {
         let value = self.as_array();
         let identity = Self::IDENTITY.as_array();
@@ -68,6 +69,7 @@ impl Mat3 {
     }
 
     /// Inverse of a matrix
+    #[allow(clippy::needless_return)]
     pub fn inverse(self) -> Option<Self> {
         let a = self;
         let c0 = a.vy.y * a.vz.z - a.vy.z * a.vz.y;
@@ -101,6 +103,7 @@ impl Mat3 {
     }
 
     /// Solve a system in the form Ax = b
+    #[allow(clippy::unnecessary_unwrap)]
     pub fn solve(self, x: Vec3) -> Option<Vec3> {
         let a_1 = self.inverse();
         if a_1.is_none() {
@@ -0,0 +1,378 @@
+use std::ops::{Index, IndexMut, Mul};
+
+use super::{Mat3, Vec3};
+
+/// Relative size under which a pivot is considered zero
+const PIVOT_TOLERANCE: f64 = 1e-12;
+
+/// Dense row-major matrix of arbitrary size
+#[derive(Clone, PartialEq, Debug)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    values: Vec<f64>,
+}
+
+impl Matrix {
+    /// Initializes a new zero filled matrix
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            values: vec![0.0; rows * cols],
+        }
+    }
+
+    /// Square identity matrix
+    pub fn identity(size: usize) -> Self {
+        let mut result = Self::new(size, size);
+        for i in 0..size {
+            result[(i, i)] = 1.0;
+        }
+        result
+    }
+
+    /// Builds a matrix from row-major values. Returns `None` if the length does not match.
+    pub fn from_rows(rows: usize, cols: usize, values: &[f64]) -> Option<Self> {
+        if values.len() != rows * cols {
+            return None;
+        }
+        Some(Self {
+            rows,
+            cols,
+            values: values.to_vec(),
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    /// A single row as a slice
+    pub fn row(&self, row: usize) -> &[f64] {
+        &self.values[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = Self::new(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                result[(j, i)] = self[(i, j)];
+            }
+        }
+        result
+    }
+
+    /// Multiply two matrices. Returns `None` if the sizes don't match.
+    pub fn per(&self, b: &Self) -> Option<Self> {
+        if self.cols != b.rows {
+            return None;
+        }
+
+        let mut result = Self::new(self.rows, b.cols);
+        for i in 0..self.rows {
+            for j in 0..b.cols {
+                result[(i, j)] = (0..self.cols).map(|k| self[(i, k)] * b[(k, j)]).sum();
+            }
+        }
+        Some(result)
+    }
+
+    /// Evaluate a vector across a matrix
+    pub fn eval(&self, v: &[f64]) -> Option<Vec<f64>> {
+        if v.len() != self.cols {
+            return None;
+        }
+        Some(
+            (0..self.rows)
+                .map(|i| self.row(i).iter().zip(v).map(|(a, b)| a * b).sum())
+                .collect(),
+        )
+    }
+
+    /// Maximum absolute column sum
+    pub fn norm_1(&self) -> f64 {
+        (0..self.cols)
+            .map(|j| (0..self.rows).map(|i| self[(i, j)].abs()).sum::<f64>())
+            .fold(0.0, f64::max)
+    }
+
+    /// LU decomposition with partial pivoting. Returns `None` if the matrix is not square or is singular.
+    pub fn lu(&self) -> Option<LuDecomposition> {
+        if !self.is_square() {
+            return None;
+        }
+
+        let n = self.rows;
+        let mut lu = self.clone();
+        let mut pivots: Vec<usize> = (0..n).collect();
+        let mut sign = 1.0;
+
+        let scale = self.values.iter().fold(0.0, |acc: f64, v| acc.max(v.abs()));
+        if scale == 0.0 {
+            return None;
+        }
+
+        for k in 0..n {
+            // Find the largest pivot in this column
+            let p = (k..n)
+                .max_by(|a, b| lu[(*a, k)].abs().total_cmp(&lu[(*b, k)].abs()))
+                .unwrap_or(k);
+
+            if lu[(p, k)].abs() <= PIVOT_TOLERANCE * scale {
+                return None;
+            }
+
+            if p != k {
+                for j in 0..n {
+                    lu.values.swap(p * n + j, k * n + j);
+                }
+                pivots.swap(p, k);
+                sign = -sign;
+            }
+
+            for i in (k + 1)..n {
+                let factor = lu[(i, k)] / lu[(k, k)];
+                lu[(i, k)] = factor;
+                for j in (k + 1)..n {
+                    let value = lu[(k, j)];
+                    lu[(i, j)] -= factor * value;
+                }
+            }
+        }
+
+        Some(LuDecomposition { lu, pivots, sign })
+    }
+
+    /// Inverse of a matrix
+    pub fn inverse(&self) -> Option<Self> {
+        Some(self.lu()?.inverse())
+    }
+
+    pub fn determinant(&self) -> Option<f64> {
+        if !self.is_square() {
+            return None;
+        }
+        Some(self.lu().map_or(0.0, |lu| lu.determinant()))
+    }
+
+    /// Solve a system in the form Ax = b
+    pub fn solve(&self, b: &[f64]) -> Option<Vec<f64>> {
+        if b.len() != self.rows {
+            return None;
+        }
+        Some(self.lu()?.solve(b))
+    }
+
+    /// Condition number in the 1-norm. Large values mean the matrix is close to singular, and results
+    /// obtained from it are unreliable.
+    pub fn condition_number(&self) -> Option<f64> {
+        Some(self.norm_1() * self.inverse()?.norm_1())
+    }
+
+    /// Finds the X that minimizes ||AX - B|| in the least squares sense, using a Householder QR
+    /// decomposition. A needs at least as many rows as columns and full column rank.
+    pub fn least_squares(&self, b: &Self) -> Option<Self> {
+        let (m, n) = (self.rows, self.cols);
+        if m < n || b.rows != m || n == 0 {
+            return None;
+        }
+
+        let mut qr = self.clone();
+        let mut rhs = b.clone();
+        let mut r_diag = vec![0.0; n];
+        let scale = self.values.iter().fold(0.0, |acc: f64, v| acc.max(v.abs()));
+
+        for k in 0..n {
+            let norm = (k..m).map(|i| qr[(i, k)] * qr[(i, k)]).sum::<f64>().sqrt();
+
+            if norm <= PIVOT_TOLERANCE * scale {
+                // Rank deficient
+                return None;
+            }
+
+            let alpha = if qr[(k, k)] > 0.0 { -norm } else { norm };
+            for i in k..m {
+                qr[(i, k)] /= -alpha;
+            }
+            qr[(k, k)] += 1.0;
+
+            // Apply the reflector to the remaining columns and to the right hand side
+            for j in (k + 1)..n {
+                let s = -(k..m).map(|i| qr[(i, k)] * qr[(i, j)]).sum::<f64>() / qr[(k, k)];
+                for i in k..m {
+                    let v = qr[(i, k)];
+                    qr[(i, j)] += s * v;
+                }
+            }
+            for j in 0..rhs.cols {
+                let s = -(k..m).map(|i| qr[(i, k)] * rhs[(i, j)]).sum::<f64>() / qr[(k, k)];
+                for i in k..m {
+                    rhs[(i, j)] += s * qr[(i, k)];
+                }
+            }
+            r_diag[k] = alpha;
+        }
+
+        // Back substitution against R
+        let mut x = Self::new(n, rhs.cols);
+        for j in 0..rhs.cols {
+            for k in (0..n).rev() {
+                let sum: f64 = ((k + 1)..n).map(|i| qr[(k, i)] * x[(i, j)]).sum();
+                x[(k, j)] = (rhs[(k, j)] - sum) / r_diag[k];
+            }
+        }
+        Some(x)
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+    fn index(&self, (row, col): (usize, usize)) -> &f64 {
+        &self.values[row * self.cols + col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut f64 {
+        &mut self.values[row * self.cols + col]
+    }
+}
+
+impl Mul for &Matrix {
+    type Output = Option<Matrix>;
+    fn mul(self, other: Self) -> Option<Matrix> {
+        self.per(other)
+    }
+}
+
+impl From<Mat3> for Matrix {
+    fn from(value: Mat3) -> Self {
+        Self {
+            rows: 3,
+            cols: 3,
+            values: <[f64; 9]>::from(value).to_vec(),
+        }
+    }
+}
+
+impl std::convert::TryFrom<&Matrix> for Mat3 {
+    type Error = ();
+    fn try_from(value: &Matrix) -> Result<Self, ()> {
+        if value.rows != 3 || value.cols != 3 {
+            return Err(());
+        }
+        let mut array = [0.0; 9];
+        array.copy_from_slice(&value.values);
+        Ok(Mat3::from(array))
+    }
+}
+
+/// Result of `Matrix::lu`, stored in a single matrix holding both L (unit diagonal) and U
+#[derive(Clone, PartialEq, Debug)]
+pub struct LuDecomposition {
+    lu: Matrix,
+    pivots: Vec<usize>,
+    sign: f64,
+}
+
+impl LuDecomposition {
+    pub fn size(&self) -> usize {
+        self.lu.rows
+    }
+
+    pub fn determinant(&self) -> f64 {
+        (0..self.size()).fold(self.sign, |acc, i| acc * self.lu[(i, i)])
+    }
+
+    /// Solve Ax = b for the decomposed A
+    pub fn solve(&self, b: &[f64]) -> Vec<f64> {
+        let n = self.size();
+        let mut x: Vec<f64> = self.pivots.iter().map(|p| b[*p]).collect();
+
+        for i in 0..n {
+            for k in 0..i {
+                x[i] -= self.lu[(i, k)] * x[k];
+            }
+        }
+        for i in (0..n).rev() {
+            for k in (i + 1)..n {
+                x[i] -= self.lu[(i, k)] * x[k];
+            }
+            x[i] /= self.lu[(i, i)];
+        }
+        x
+    }
+
+    pub fn inverse(&self) -> Matrix {
+        let n = self.size();
+        let mut result = Matrix::new(n, n);
+        let mut e = vec![0.0; n];
+
+        for j in 0..n {
+            e[j] = 1.0;
+            for (i, value) in self.solve(&e).into_iter().enumerate() {
+                result[(i, j)] = value;
+            }
+            e[j] = 0.0;
+        }
+        result
+    }
+}
+
+fn samples_to_matrices(samples: &[(Vec3, Vec3)], offset: bool) -> (Matrix, Matrix) {
+    let cols = if offset { 4 } else { 3 };
+    let mut a = Matrix::new(samples.len(), cols);
+    let mut b = Matrix::new(samples.len(), 3);
+
+    for (i, (src, dst)) in samples.iter().enumerate() {
+        a[(i, 0)] = src.x;
+        a[(i, 1)] = src.y;
+        a[(i, 2)] = src.z;
+        if offset {
+            a[(i, 3)] = 1.0;
+        }
+        b[(i, 0)] = dst.x;
+        b[(i, 1)] = dst.y;
+        b[(i, 2)] = dst.z;
+    }
+    (a, b)
+}
+
+/// Fits the 3x3 matrix M that best maps each `(source, dest)` pair as `dest = M * source`. Needs at least
+/// three independent samples.
+pub fn fit_mat3(samples: &[(Vec3, Vec3)]) -> Option<Mat3> {
+    let (a, b) = samples_to_matrices(samples, false);
+    let x = a.least_squares(&b)?;
+
+    // x holds the transposed matrix
+    Some(Mat3 {
+        vx: Vec3::new(x[(0, 0)], x[(1, 0)], x[(2, 0)]),
+        vy: Vec3::new(x[(0, 1)], x[(1, 1)], x[(2, 1)]),
+        vz: Vec3::new(x[(0, 2)], x[(1, 2)], x[(2, 2)]),
+    })
+}
+
+/// Fits the 3x4 affine transform that best maps each `(source, dest)` pair as `dest = M * source + offset`.
+/// Needs at least four samples not lying on the same plane.
+pub fn fit_mat3_with_offset(samples: &[(Vec3, Vec3)]) -> Option<(Mat3, Vec3)> {
+    let (a, b) = samples_to_matrices(samples, true);
+    let x = a.least_squares(&b)?;
+
+    Some((
+        Mat3 {
+            vx: Vec3::new(x[(0, 0)], x[(1, 0)], x[(2, 0)]),
+            vy: Vec3::new(x[(0, 1)], x[(1, 1)], x[(2, 1)]),
+            vz: Vec3::new(x[(0, 2)], x[(1, 2)], x[(2, 2)]),
+        },
+        Vec3::new(x[(3, 0)], x[(3, 1)], x[(3, 2)]),
+    ))
+}
@@ -1,23 +1,50 @@
 use std::io::Read;
 use std::io::Write;
-use std::io::{Error, ErrorKind, Result};
+use std::io::Result;
 use std::mem::size_of;
+use std::sync::Arc;
 
 use crate::*;
+use crate::signatures::plugin_type;
+use crate::tag_types::TagDescriptor;
 
 mod vec3;
 
 mod mat3;
 
+mod matrix;
+
 /// ICC base tag
 mod tag_base;
 
+mod io_handler;
+
+pub(crate) mod interpolation;
+
+/// Moving pixels between buffers and pipelines
+mod formatter;
+
+/// User defined tag types
+mod tag_type;
+
+/// Plugins registered in a `Context`
+pub(crate) mod registry;
+
 #[cfg(test)]
 mod tests;
 
 // Public exports
 pub use mat3::Mat3;
+pub use matrix::{fit_mat3, fit_mat3_with_offset, LuDecomposition, Matrix};
+pub use formatter::{
+    find_pack, find_unroll, is_builtin_format, pack_any, unroll_any, FormatSample, FormatterFactory,
+    PackFn, UnrollFn,
+};
+pub use crate::transform::default_icc_intents;
+pub use interpolation::{lerp_flags, InterpFn, InterpParams, InterpSample, InterpolatorFactory};
+pub use io_handler::{FileIo, IoHandler, MemoryIo, NullIo, TagIo, VecIo};
 pub use tag_base::TagBase;
+pub use tag_type::{CustomTag, TagTypeHandler};
 pub use vec3::Vec3;
 
 // const READ_ADJUST_ENDIANNESS_U32: &dyn Fn([u8; 4]) -> u32 = if CMS_USE_BIG_ENDIAN {&u32::from_be_bytes} else {&u32::from_le_bytes};
@@ -50,167 +77,175 @@ pub fn adjust_endianness_64(qword: u64) -> u64 {
     u64::from_be_bytes(qword.to_le_bytes())
 }
 
-fn eof_error() -> Error {
-    Error::new(
-        ErrorKind::UnexpectedEof,
-        "Can't read from buffer. Unexpected EOF.",
-    )
-}
-
 pub fn read_u8(reader: &mut dyn Read) -> Result<u8> {
     let mut buf = [0u8; size_of::<u8>()];
-    match reader.read(&mut buf)? {
-        len if len == size_of::<u8>() => Ok(buf[0]),
-        _ => Err(eof_error()),
-    }
+    reader.read_exact(&mut buf)?;
+
+    Ok(buf[0])
 }
 
 pub fn read_u16(reader: &mut dyn Read) -> Result<u16> {
     let mut buf = [0u8; size_of::<u16>()];
-    
-    match reader.read(&mut buf)? {
-        len if len == size_of::<u16>() => Ok(u16::from_be_bytes(buf)),
-        _ => Err(eof_error()),
-    }
+    reader.read_exact(&mut buf)?;
+
+    Ok(u16::from_be_bytes(buf))
 }
 
 pub fn read_u16_array(reader: &mut dyn Read, result: &mut [u16]) -> Result<()> {
-    for i in 0..result.len() {
-        let value = read_u16(reader)?;
-        result[i] = value;
+    for item in result.iter_mut() {
+        *item = read_u16(reader)?;
     }
     Ok(())
 }
 
 pub fn read_u32(reader: &mut dyn Read) -> Result<u32> {
     let mut buf = [0u8; size_of::<u32>()];
-    
-    match reader.read(&mut buf)? {
-        len if len == size_of::<u32>() => Ok(u32::from_be_bytes(buf)),
-        _ => Err(eof_error()),
-    }
+    reader.read_exact(&mut buf)?;
+
+    Ok(u32::from_be_bytes(buf))
 }
 
 pub fn read_f32(reader: &mut dyn Read) -> Result<f32> {
     let mut buf = [0u8; size_of::<f32>()];
-    
-    match reader.read(&mut buf)? {
-        len if len == size_of::<f32>() => Ok(f32::from_be_bytes(buf)),
-        _ => Err(eof_error()),
-    }
+    reader.read_exact(&mut buf)?;
+
+    Ok(f32::from_be_bytes(buf))
 }
 
 pub fn read_u64(reader: &mut dyn Read) -> Result<u64> {
     let mut buf = [0u8; size_of::<u64>()];
-    
-    match reader.read(&mut buf)? {
-        len if len == size_of::<u64>() => Ok(u64::from_be_bytes(buf)),
-        _ => Err(eof_error()),
-    }
+    reader.read_exact(&mut buf)?;
+
+    Ok(u64::from_be_bytes(buf))
 }
 
 pub fn read_s15f16(reader: &mut dyn Read) -> Result<S15F16> {
     let mut buf = [0u8; size_of::<S15F16>()];
-    
-    match reader.read(&mut buf)? {
-        len if len == size_of::<S15F16>() => Ok(S15F16::from_be_bytes(buf)),
-        _ => Err(eof_error()),
-    }
+    reader.read_exact(&mut buf)?;
+
+    Ok(S15F16::from_be_bytes(buf))
 }
 
 pub fn read_f64(reader: &mut dyn Read) -> Result<f64> {
     let mut buf = [0u8; size_of::<f64>()];
-    
-    match reader.read(&mut buf)? {
-        len if len == size_of::<f64>() => Ok(f64::from_be_bytes(buf)),
-        _ => Err(eof_error()),
-    }
+    reader.read_exact(&mut buf)?;
+
+    Ok(f64::from_be_bytes(buf))
+}
+
+pub fn read_encoded_xyz(reader: &mut dyn Read) -> Result<EncodedXYZNumber> {
+    Ok(EncodedXYZNumber {
+        x: read_s15f16(reader)?,
+        y: read_s15f16(reader)?,
+        z: read_s15f16(reader)?,
+    })
 }
 
+/// Reads an XYZ number stored as three s15Fixed16 values
 pub fn read_xyz(reader: &mut dyn Read) -> Result<CIEXYZ> {
-    Ok(CIEXYZ {
-        X: read_f64(reader)?,
-        Y: read_f64(reader)?,
-        Z: read_f64(reader)?,
+    Ok(CIEXYZ::from(read_encoded_xyz(reader)?))
+}
+
+pub fn read_date_time(reader: &mut dyn Read) -> Result<DateTimeNumber> {
+    Ok(DateTimeNumber {
+        year: read_u16(reader)?,
+        month: read_u16(reader)?,
+        day: read_u16(reader)?,
+        hours: read_u16(reader)?,
+        minutes: read_u16(reader)?,
+        seconds: read_u16(reader)?,
     })
 }
 
 pub fn write_u8(writer: &mut dyn Write, value: u8) -> Result<()> {
     let buf = [value];
-
-    match writer.write(&buf)? {
-        len if len == size_of::<u8>() => Ok(()),
-        _ => Err(eof_error()),
-    }
+    writer.write_all(&buf)
 }
 
 pub fn write_u16(writer: &mut dyn Write, value: u16) -> Result<()> {
     let buf = u16::to_be_bytes(value);
-    
-    match writer.write(&buf)? {
-        len if len == size_of::<u16>() => Ok(()),
-        _ => Err(eof_error()),
-    }
+    writer.write_all(&buf)
 }
 
 pub fn write_u16_array(writer: &mut dyn Write, value: &[u16]) -> Result<()> {
-    for i in 0..value.len() {
-        write_u16(writer, value[i])?;
+    for item in value {
+        write_u16(writer, *item)?;
     }
     Ok(())
 }
 
 pub fn write_u32(writer: &mut dyn Write, value: u32) -> Result<()> {
     let buf = u32::to_be_bytes(value);
-    
-    match writer.write(&buf)? {
-        len if len == size_of::<u32>() => Ok(()),
-        _ => Err(eof_error()),
-    }
+    writer.write_all(&buf)
 }
 
 pub fn write_f32(writer: &mut dyn Write, value: f32) -> Result<()> {
     let buf = f32::to_be_bytes(value);
-    
-    match writer.write(&buf)? {
-        len if len == size_of::<f32>() => Ok(()),
-        _ => Err(eof_error()),
-    }
+    writer.write_all(&buf)
 }
 
 pub fn write_u64(writer: &mut dyn Write, value: u64) -> Result<()> {
     let buf = u64::to_be_bytes(value);
-    
-    match writer.write(&buf)? {
-        len if len == size_of::<u64>() => Ok(()),
-        _ => Err(eof_error()),
-    }
+    writer.write_all(&buf)
 }
 
 pub fn write_s15f16(writer: &mut dyn Write, value: S15F16) -> Result<()> {
     let buf = S15F16::to_be_bytes(value);
-    
-    match writer.write(&buf)? {
-        len if len == size_of::<S15F16>() => Ok(()),
-        _ => Err(eof_error()),
-    }
+    writer.write_all(&buf)
 }
 
-pub fn write_xyz(writer: &mut dyn Write, value: CIEXYZ) -> Result<()> {
-    write_f64(writer, value.X)?;
-    write_f64(writer, value.Y)?;
-    write_f64(writer, value.Z)?;
+pub fn write_encoded_xyz(writer: &mut dyn Write, value: EncodedXYZNumber) -> Result<()> {
+    write_s15f16(writer, value.x)?;
+    write_s15f16(writer, value.y)?;
+    write_s15f16(writer, value.z)?;
 
     Ok(())
 }
 
+/// Writes an XYZ number as three s15Fixed16 values
+pub fn write_xyz(writer: &mut dyn Write, value: CIEXYZ) -> Result<()> {
+    write_encoded_xyz(writer, EncodedXYZNumber::from(value))
+}
+
+pub fn write_date_time(writer: &mut dyn Write, value: DateTimeNumber) -> Result<()> {
+    write_u16_array(
+        writer,
+        &[
+            value.year,
+            value.month,
+            value.day,
+            value.hours,
+            value.minutes,
+            value.seconds,
+        ],
+    )
+}
+
 pub fn write_f64(writer: &mut dyn Write, value: f64) -> Result<()> {
     let buf = f64::to_be_bytes(value);
-    
-    match writer.write(&buf)? {
-        len if len == size_of::<f64>() => Ok(()),
-        _ => Err(eof_error()),
-    }
+    writer.write_all(&buf)
+}
+
+/// Rounds up to the next 32-bit boundary
+pub fn align_32(value: u32) -> u32 {
+    (value + 3) & !3
+}
+
+/// Skips the padding up to the next 32-bit boundary
+pub fn read_alignment(io: &mut dyn IoHandler) -> Result<()> {
+    let at = io.tell();
+    let padding = (align_32(at) - at) as usize;
+    let mut buf = [0u8; 4];
+
+    io.read_exact(&mut buf[..padding])
+}
+
+/// Writes zeros up to the next 32-bit boundary
+pub fn write_alignment(io: &mut dyn IoHandler) -> Result<()> {
+    let at = io.tell();
+    let padding = (align_32(at) - at) as usize;
+
+    io.write_all(&[0u8; 4][..padding])
 }
 
 pub fn u8f8_to_f64(fixed8: U8F8) -> f64 {
@@ -224,35 +259,177 @@ pub fn f64_to_u8f8(val: f64) -> U8F8 {
     ((fixed32 >> 8) & 0xFFFF) as U8F8
 }
 pub fn s15f16_to_f64(fixed32: S15F16) -> f64 {
-    let sign = if fixed32 < 0 { -1.0 } else { 1.0 };
-    let fixed32 = S15F16::abs(fixed32);
+    fixed32 as f64 / 65536.0
+}
+pub fn f64_to_s15f16(v: f64) -> S15F16 {
+    f64::floor(v * 65536.0 + 0.5) as S15F16
+}
+pub fn u16f16_to_f64(fixed32: U16F16) -> f64 {
+    fixed32 as f64 / 65536.0
+}
+pub fn f64_to_u16f16(v: f64) -> U16F16 {
+    f64::floor(v * 65536.0 + 0.5) as U16F16
+}
+
+/// Value of `PluginBase::magic`
+pub const PLUGIN_MAGIC_NUMBER: Signature = Signature::new(b"acpp");
+
+/// What a plugin provides. The variant must agree with `PluginBase::r#type`.
+#[derive(Clone)]
+pub enum Plugin {
+    /// A tag type, for `plugin_type::TAG_TYPE`
+    TagType(Arc<dyn TagTypeHandler>),
+    /// A tag and the types it may be stored as, for `plugin_type::TAG`
+    Tag {
+        signature: Signature,
+        descriptor: TagDescriptor,
+    },
+    /// Parametric curve types, for `plugin_type::PARAMETRIC_CURVE`
+    ParametricCurves(ParametricCurves),
+    /// CLUT interpolation routines, for `plugin_type::INTERPOLATION`
+    Interpolation(Arc<dyn InterpolatorFactory>),
+    /// Pixel formatters, for `plugin_type::FORMATTERS`
+    Formatters(Arc<dyn FormatterFactory>),
+    /// A rendering intent, for `plugin_type::RENDERING_INTENT`
+    RenderingIntent(RenderingIntent),
+    /// A pipeline optimization pass, for `plugin_type::OPTIMIZATION`
+    Optimization(OptimizationFn),
+    /// Workers doing whole transforms, for `plugin_type::TRANSFORM`
+    Transform(Arc<dyn TransformFactory>),
+}
 
-    let whole = ((fixed32 >> 16) & 0xFFFF) as u16;
-    let frac_part = (fixed32 & 0xFFFF) as u16;
+impl Plugin {
+    /// Plugin type matching the variant
+    pub fn plugin_type(&self) -> Signature {
+        match self {
+            Plugin::TagType(_) => plugin_type::TAG_TYPE,
+            Plugin::Tag { .. } => plugin_type::TAG,
+            Plugin::ParametricCurves(_) => plugin_type::PARAMETRIC_CURVE,
+            Plugin::Interpolation(_) => plugin_type::INTERPOLATION,
+            Plugin::Formatters(_) => plugin_type::FORMATTERS,
+            Plugin::RenderingIntent(_) => plugin_type::RENDERING_INTENT,
+            Plugin::Optimization(_) => plugin_type::OPTIMIZATION,
+            Plugin::Transform(_) => plugin_type::TRANSFORM,
+        }
+    }
+}
 
-    let mid = (frac_part as f64 / 65536.0) as f64;
-    let floater = whole as f64 + mid as f64;
+/// Evaluates a parametric curve of the given type, or of its inverse if negative
+pub type ParametricCurveEvaluator = fn(r#type: i32, params: &[f64], r: f64) -> f64;
 
-    sign * floater
+/// Parametric curve types provided by a plugin, overriding the built-in ones
+#[derive(Copy, Clone)]
+pub struct ParametricCurves {
+    /// Positive types handled and their number of parameters, 10 at most
+    pub functions: &'static [(i32, usize)],
+    pub evaluator: ParametricCurveEvaluator,
 }
-pub fn f64_to_s15f16(v: f64) -> S15F16 {
-    f64::floor(v * 65536.0 + 0.5) as S15F16
+
+/// Links a chain of profiles into a single pipeline. Each profile comes with its intent, whether to use black
+/// point compensation and its adaptation state, from 0 for none to 1 for full adaptation.
+pub type IntentFn = fn(
+    context: &Context,
+    intents: &[u32],
+    profiles: &[&Profile],
+    bpc: &[bool],
+    adaptation_states: &[f64],
+) -> Result<Pipeline>;
+
+/// Rendering intent provided by a plugin. Transforms whose first intent is `intent` are linked by `link`.
+#[derive(Copy, Clone)]
+pub struct RenderingIntent {
+    pub intent: u32,
+    pub description: &'static str,
+    pub link: IntentFn,
 }
 
+/// Optimizes a pipeline that transforms pixels of the given formats, once identities are removed and matrices
+/// merged. Returns whether it did, in which case no other pass is tried. Passes may replace the stages and set
+/// a faster evaluator with `Pipeline::set_optimized_eval_16`.
+pub type OptimizationFn = fn(
+    pipeline: &mut Pipeline,
+    intent: u32,
+    input_format: PixelType,
+    output_format: PixelType,
+) -> bool;
+
+/// Header of a plugin. Plugins are chained through `next` so several can be registered at once.
 pub struct PluginBase<'a> {
     pub magic: Signature,
     pub expected_version: u32,
     pub r#type: Signature,
-    pub next: &'a PluginBase<'a>,
+    pub plugin: Plugin,
+    pub next: Option<&'a PluginBase<'a>>,
+}
+
+impl<'a> PluginBase<'a> {
+    /// Plugin with the magic number and type filled in, expecting the current version
+    pub fn new(plugin: Plugin) -> Self {
+        Self {
+            magic: PLUGIN_MAGIC_NUMBER,
+            expected_version: LCMS_VERSION as u32,
+            r#type: plugin.plugin_type(),
+            plugin,
+            next: None,
+        }
+    }
+
+    /// Chains `next` after this plugin
+    pub fn with_next(self, next: &'a PluginBase<'a>) -> Self {
+        Self {
+            next: Some(next),
+            ..self
+        }
+    }
+
+    /// This plugin followed by the rest of the chain
+    pub fn iter(&self) -> impl Iterator<Item = &PluginBase<'a>> {
+        std::iter::successors(Some(self), |plugin| plugin.next)
+    }
 }
 
 pub const MAX_TYPES_IN_LCMS_PLUGIN: u8 = 20;
 
+/// Maximum number of inputs a CLUT may have
+pub const MAX_INPUT_DIMENSIONS: usize = 15;
+
+/// Maximum number of channels flowing between pipeline stages
+pub const MAX_STAGE_CHANNELS: usize = 128;
+
 /* ------------------------------------------------- Full Transform ------------------------------------------------- */
 
+/// Layout of the buffers of a transform, in bytes. Planes are only used by planar formats.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
 pub struct Stride {
     pub bytes_per_line_in: u32,
     pub bytes_per_line_out: u32,
     pub bytes_per_plane_in: u32,
     pub bytes_per_plane_out: u32,
 }
+
+/// Transforms whole buffers, standing for the formatters and the pipeline evaluation
+pub trait TransformWorker: Send + Sync {
+    /// Transforms `line_count` lines of `pixels_per_line` pixels, laid out as described by `stride` in the
+    /// formats the worker was created for. `pipeline` is the one the factory was given.
+    fn transform(
+        &self,
+        pipeline: &Pipeline,
+        input: &[u8],
+        output: &mut [u8],
+        pixels_per_line: usize,
+        line_count: usize,
+        stride: &Stride,
+    );
+}
+
+/// Provides transform workers, registered with a `Plugin::Transform`. Factories are asked before the pipeline
+/// is optimized, and may optimize or replace it themselves. Returning `None` leaves the transform to plugins
+/// registered earlier and then to the built-in formatters.
+pub trait TransformFactory: Send + Sync {
+    fn worker(
+        &self,
+        pipeline: &mut Pipeline,
+        input_format: PixelType,
+        output_format: PixelType,
+    ) -> Option<Arc<dyn TransformWorker>>;
+}
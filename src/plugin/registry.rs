@@ -0,0 +1,181 @@
+use std::io::{Error, ErrorKind, Result};
+use std::sync::Arc;
+
+use crate::tag_types::TagDescriptor;
+use crate::{Pipeline, PixelType, Signature, LCMS_VERSION};
+
+use super::{
+    FormatSample, FormatterFactory, InterpFn, InterpSample, InterpolatorFactory, OptimizationFn,
+    PackFn, ParametricCurveEvaluator, ParametricCurves, Plugin, PluginBase, RenderingIntent,
+    TagTypeHandler, TransformFactory, TransformWorker, UnrollFn, MAX_TYPES_IN_LCMS_PLUGIN,
+    PLUGIN_MAGIC_NUMBER,
+};
+
+/// Everything provided by the plugins of a `Context`. Later registrations take precedence.
+#[derive(Clone, Default)]
+pub(crate) struct Registry {
+    tag_types: Vec<Arc<dyn TagTypeHandler>>,
+    tags: Vec<(Signature, TagDescriptor)>,
+    parametric_curves: Vec<ParametricCurves>,
+    interpolators: Vec<Arc<dyn InterpolatorFactory>>,
+    formatters: Vec<Arc<dyn FormatterFactory>>,
+    intents: Vec<RenderingIntent>,
+    optimizations: Vec<OptimizationFn>,
+    transforms: Vec<Arc<dyn TransformFactory>>,
+}
+
+fn invalid_plugin(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+impl Registry {
+    /// Checks a single plugin can be registered
+    pub fn check(plugin: &PluginBase) -> Result<()> {
+        if plugin.magic != PLUGIN_MAGIC_NUMBER {
+            return Err(invalid_plugin("Unrecognized plugin".to_string()));
+        }
+        if plugin.expected_version > LCMS_VERSION as u32 {
+            return Err(invalid_plugin(format!(
+                "plugin needs Little CMS {}, current version is {}",
+                plugin.expected_version, LCMS_VERSION
+            )));
+        }
+        if plugin.r#type != plugin.plugin.plugin_type() {
+            return Err(invalid_plugin(format!(
+                "Unrecognized plugin type '{}'",
+                String::from(plugin.r#type)
+            )));
+        }
+
+        let type_count = match &plugin.plugin {
+            Plugin::TagType(_)
+            | Plugin::Interpolation(_)
+            | Plugin::Formatters(_)
+            | Plugin::RenderingIntent(_)
+            | Plugin::Optimization(_)
+            | Plugin::Transform(_) => 1,
+            Plugin::Tag { descriptor, .. } => descriptor.supported_types.len(),
+            Plugin::ParametricCurves(curves) => {
+                if curves.functions.iter().any(|&(_, count)| count > 10) {
+                    return Err(invalid_plugin(
+                        "Parametric curves take 10 parameters at most".to_string(),
+                    ));
+                }
+                curves.functions.len()
+            }
+        };
+        if type_count == 0 || type_count > MAX_TYPES_IN_LCMS_PLUGIN as usize {
+            return Err(invalid_plugin(format!(
+                "Plugins provide between 1 and {} types",
+                MAX_TYPES_IN_LCMS_PLUGIN
+            )));
+        }
+        Ok(())
+    }
+
+    /// Registers a chain of plugins already checked
+    pub fn register(&mut self, plugin: &PluginBase) {
+        for p in plugin.iter() {
+            match &p.plugin {
+                Plugin::TagType(handler) => self.tag_types.push(handler.clone()),
+                Plugin::Tag {
+                    signature,
+                    descriptor,
+                } => self.tags.push((*signature, *descriptor)),
+                Plugin::ParametricCurves(curves) => self.parametric_curves.push(*curves),
+                Plugin::Interpolation(factory) => self.interpolators.push(factory.clone()),
+                Plugin::Formatters(factory) => self.formatters.push(factory.clone()),
+                Plugin::RenderingIntent(intent) => self.intents.push(*intent),
+                Plugin::Optimization(pass) => self.optimizations.push(*pass),
+                Plugin::Transform(factory) => self.transforms.push(factory.clone()),
+            }
+        }
+    }
+
+    pub fn tag_type_handler(&self, type_sig: Signature) -> Option<Arc<dyn TagTypeHandler>> {
+        self.tag_types
+            .iter()
+            .rev()
+            .find(|h| h.signature() == type_sig)
+            .cloned()
+    }
+
+    pub fn tag_descriptor(&self, sig: Signature) -> Option<TagDescriptor> {
+        self.tags
+            .iter()
+            .rev()
+            .find(|(s, _)| *s == sig)
+            .map(|(_, descriptor)| *descriptor)
+    }
+
+    /// Number of parameters and evaluator of a parametric curve type, or of its inverse if negative
+    pub fn parametric_curve(&self, r#type: i32) -> Option<(usize, ParametricCurveEvaluator)> {
+        self.parametric_curves.iter().rev().find_map(|curves| {
+            curves
+                .functions
+                .iter()
+                .find(|(t, _)| *t == r#type.abs())
+                .map(|&(_, count)| (count, curves.evaluator))
+        })
+    }
+
+    /// Interpolation routine registered for the given configuration
+    pub fn interpolator<T: InterpSample>(
+        &self,
+        n_inputs: usize,
+        n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<T>> {
+        self.interpolators.iter().rev().find_map(|factory| {
+            T::plugin_interpolator(factory.as_ref(), n_inputs, n_outputs, flags)
+        })
+    }
+
+    /// Unroll routine registered for the format
+    pub fn unroll<T: FormatSample>(&self, format: PixelType) -> Option<UnrollFn<T>> {
+        self.formatters
+            .iter()
+            .rev()
+            .find_map(|factory| T::plugin_unroll(factory.as_ref(), format))
+    }
+
+    /// Pack routine registered for the format
+    pub fn pack<T: FormatSample>(&self, format: PixelType) -> Option<PackFn<T>> {
+        self.formatters
+            .iter()
+            .rev()
+            .find_map(|factory| T::plugin_pack(factory.as_ref(), format))
+    }
+
+    /// Rendering intent registered with the given code
+    pub fn intent(&self, intent: u32) -> Option<RenderingIntent> {
+        self.intents
+            .iter()
+            .rev()
+            .find(|i| i.intent == intent)
+            .copied()
+    }
+
+    /// All the rendering intents registered, latest first
+    pub fn intents(&self) -> impl Iterator<Item = &RenderingIntent> {
+        self.intents.iter().rev()
+    }
+
+    /// Optimization passes registered, latest first
+    pub fn optimizations(&self) -> impl Iterator<Item = OptimizationFn> + '_ {
+        self.optimizations.iter().rev().copied()
+    }
+
+    /// Worker registered for the pipeline and formats
+    pub fn transform_worker(
+        &self,
+        pipeline: &mut Pipeline,
+        input_format: PixelType,
+        output_format: PixelType,
+    ) -> Option<Arc<dyn TransformWorker>> {
+        self.transforms
+            .iter()
+            .rev()
+            .find_map(|factory| factory.worker(pipeline, input_format, output_format))
+    }
+}
@@ -2,9 +2,8 @@ use crate::Signature;
 use std::io::Read;
 use std::io::Result;
 use std::io::Write;
-use std::mem::size_of;
 
-use super::{eof_error, write_u32};
+use super::{read_u32, write_u32};
 
 pub struct TagBase {
     pub signature: Signature,
@@ -26,17 +25,10 @@ impl From<TagBase> for u32 {
 }
 impl TagBase {
     pub fn read(reader: &mut dyn Read) -> Result<Self> {
-        let mut buf = [0u8; size_of::<Signature>()];
-        let len = reader.read(&mut buf)?;
-        if len < size_of::<Signature>() {
-            return Err(eof_error());
-        }
-        let sig = Signature::new(&buf);
+        let sig = Signature::from(read_u32(reader)?);
 
-        let len = reader.read(&mut buf)?;
-        if len < size_of::<Signature>() {
-            return Err(eof_error());
-        }
+        let mut buf = [0u8; 4];
+        reader.read_exact(&mut buf)?;
 
         Ok(Self {
             signature: sig,
@@ -44,16 +36,13 @@ impl TagBase {
         })
     }
     pub fn read_type_base(reader: &mut dyn Read) -> Signature {
-        let value = Self::read(reader);
-        if value.is_err() {
-            return Default::default();
+        match Self::read(reader) {
+            Ok(value) => value.signature,
+            Err(_) => Default::default(),
         }
-        return value.unwrap().signature;
     }
     pub fn write(self, writer: &mut dyn Write) -> Result<()> {
         write_u32(writer, u32::from(self.signature))?;
-        write_u32(writer, u32::from_ne_bytes(self.reserved))?;
-
-        Ok(())
+        writer.write_all(&self.reserved)
     }
 }
@@ -0,0 +1,155 @@
+use std::any::Any;
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::sync::Arc;
+
+use crate::Signature;
+
+use super::{IoHandler, TagBase, VecIo};
+
+/// Reads and writes a tag type not known to the library. Register it with a `Plugin::TagType` to have profiles
+/// decode tags of that type into `TagData::Custom`.
+///
+/// Like the built in handlers, `read` and `write` only deal with the payload, positions being relative to the
+/// start of the tag. The type base in front of it is handled by `TagBase`.
+pub trait TagTypeHandler: Send + Sync {
+    /// Type signature the handler takes care of
+    fn signature(&self) -> Signature;
+
+    /// Reads the payload of a tag. `size_of_tag` doesn't include the type base.
+    fn read(&self, io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Box<dyn Any + Send + Sync>>;
+
+    /// Writes the payload of a tag holding data returned by `read` or given to `CustomTag::new`
+    fn write(&self, io: &mut dyn IoHandler, data: &(dyn Any + Send + Sync)) -> Result<()>;
+
+    /// Copies data returned by `read`
+    fn duplicate(&self, data: &(dyn Any + Send + Sync)) -> Box<dyn Any + Send + Sync>;
+
+    /// Releases data returned by `read`. It is simply dropped by default.
+    fn free(&self, data: Box<dyn Any + Send + Sync>) {
+        drop(data)
+    }
+
+    /// Reads a whole tag, type base included, checking it is of this type
+    fn read_tag(
+        &self,
+        io: &mut dyn IoHandler,
+        size_of_tag: u32,
+    ) -> Result<Box<dyn Any + Send + Sync>> {
+        let base = TagBase::read(io)?;
+        if base.signature != self.signature() {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Expected tag type '{}', found '{}'",
+                    String::from(self.signature()),
+                    String::from(base.signature)
+                ),
+            ));
+        }
+        self.read(io, size_of_tag.saturating_sub(8))
+    }
+
+    /// Writes a whole tag, type base included
+    fn write_tag(&self, io: &mut dyn IoHandler, data: &(dyn Any + Send + Sync)) -> Result<()> {
+        TagBase::from(u32::from(self.signature())).write(io)?;
+        self.write(io, data)
+    }
+}
+
+/// Decoded contents of a tag whose type is handled by a `TagTypeHandler`
+pub struct CustomTag {
+    handler: Arc<dyn TagTypeHandler>,
+    // Only taken out when dropped
+    data: Option<Box<dyn Any + Send + Sync>>,
+}
+
+impl CustomTag {
+    /// Data to be written by `handler`
+    pub fn new<T: Any + Send + Sync>(handler: Arc<dyn TagTypeHandler>, data: T) -> Self {
+        Self {
+            handler,
+            data: Some(Box::new(data)),
+        }
+    }
+
+    /// Reads the payload of a tag with `handler`
+    pub fn read(
+        handler: Arc<dyn TagTypeHandler>,
+        io: &mut dyn IoHandler,
+        size_of_tag: u32,
+    ) -> Result<Self> {
+        let data = handler.read(io, size_of_tag)?;
+        Ok(Self {
+            handler,
+            data: Some(data),
+        })
+    }
+
+    /// Writes the payload of the tag
+    pub fn write(&self, io: &mut dyn IoHandler) -> Result<()> {
+        self.handler.write(io, self.data())
+    }
+
+    /// Type the tag is stored as
+    pub fn type_sig(&self) -> Signature {
+        self.handler.signature()
+    }
+
+    pub fn handler(&self) -> &Arc<dyn TagTypeHandler> {
+        &self.handler
+    }
+
+    pub fn data(&self) -> &(dyn Any + Send + Sync) {
+        self.data.as_deref().unwrap()
+    }
+
+    /// The data, if it is a `T`
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.data().downcast_ref()
+    }
+
+    fn encode(&self) -> Option<Vec<u8>> {
+        let mut io = VecIo::new();
+        self.write(&mut io).ok()?;
+        Some(io.into_inner())
+    }
+}
+
+impl Clone for CustomTag {
+    fn clone(&self) -> Self {
+        Self {
+            handler: self.handler.clone(),
+            data: Some(self.handler.duplicate(self.data())),
+        }
+    }
+}
+
+impl Drop for CustomTag {
+    fn drop(&mut self) {
+        if let Some(data) = self.data.take() {
+            self.handler.free(data);
+        }
+    }
+}
+
+/// Tags are equal if they are of the same type and encode to the same bytes
+impl PartialEq for CustomTag {
+    fn eq(&self, other: &Self) -> bool {
+        if self.type_sig() != other.type_sig() {
+            return false;
+        }
+        match (self.encode(), other.encode()) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for CustomTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomTag")
+            .field("type_sig", &self.type_sig())
+            .finish()
+    }
+}
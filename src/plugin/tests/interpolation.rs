@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use super::*;
+use crate::signatures::{color_space, profile_class, tag};
+use crate::tag_types::TagData;
+use crate::{Context, Pipeline, Profile, Stage, StageLoc};
+
+/// Table of a CLUT giving back its inputs, with `points` grid points on each of `n` inputs
+fn identity_table_16(points: u32, n: usize) -> Vec<u16> {
+    let total = (points as usize).pow(n as u32);
+    let mut result = Vec::with_capacity(total * n);
+
+    for index in 0..total {
+        let mut rest = index;
+        let mut node = vec![0u16; n];
+        for i in (0..n).rev() {
+            let k = rest % points as usize;
+            rest /= points as usize;
+            node[i] = (k as u32 * 65535 / (points - 1)) as u16;
+        }
+        result.extend(node);
+    }
+    result
+}
+
+#[test]
+fn test_lerp_table_16_hits_nodes_and_midpoints() {
+    let table = [0u16, 0x1000, 0x8000];
+    let params = InterpParams::new(&[3], 1, table.to_vec(), lerp_flags::BITS_16).unwrap();
+    let mut out = [0u16];
+
+    params.eval(&[0], &mut out);
+    assert_eq!(0, out[0]);
+    params.eval(&[0x8000], &mut out);
+    assert_eq!(0x1000, out[0]);
+    params.eval(&[0xffff], &mut out);
+    assert_eq!(0x8000, out[0]);
+    params.eval(&[0x4000], &mut out);
+    assert_eq!(0x0800, out[0]);
+}
+
+#[test]
+fn test_lin_lerp_1d_float() {
+    let params = InterpParams::new(&[3], 1, vec![0.0f32, 0.25, 1.0], lerp_flags::FLOAT).unwrap();
+    let mut out = [0f32];
+
+    params.eval(&[0.25], &mut out);
+    assert!((out[0] - 0.125).abs() < 1e-6);
+    params.eval(&[0.75], &mut out);
+    assert!((out[0] - 0.625).abs() < 1e-6);
+    params.eval(&[2.0], &mut out);
+    assert_eq!(1.0, out[0]);
+    params.eval(&[f32::NAN], &mut out);
+    assert_eq!(0.0, out[0]);
+}
+
+#[test]
+fn test_eval_1_input_many_outputs() {
+    let params = InterpParams::new(&[2], 2, vec![0u16, 0xffff, 0xffff, 0], 0).unwrap();
+    let mut out = [0u16; 2];
+
+    params.eval(&[0x8000], &mut out);
+    assert_eq!([0x8000, 0x7fff], out);
+}
+
+#[test]
+fn test_new_rejects_table_of_wrong_size() {
+    assert!(InterpParams::new(&[2, 2], 3, vec![0u16; 11], 0).is_none());
+    assert!(InterpParams::new(&[], 3, Vec::<u16>::new(), 0).is_none());
+}
+
+#[test]
+fn test_identity_cluts_give_back_their_inputs() {
+    let inputs: [[u16; 4]; 4] = [
+        [0, 0, 0, 0],
+        [0xffff, 0xffff, 0xffff, 0xffff],
+        [0x1234, 0x8000, 0xfedc, 0x4000],
+        [0xffff, 0x0101, 0x7777, 0xabcd],
+    ];
+
+    for n in 2..=4 {
+        for flags in [0, lerp_flags::TRILINEAR].iter() {
+            let params =
+                InterpParams::new(&vec![5; n], n, identity_table_16(5, n), *flags).unwrap();
+
+            for input in inputs.iter() {
+                let mut out = [0u16; 4];
+                params.eval(&input[..n], &mut out);
+
+                for i in 0..n {
+                    let diff = (out[i] as i32 - input[i] as i32).abs();
+                    assert!(diff <= 1, "{} inputs: {:?} gave {:?}", n, input, out);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tetrahedral_and_trilinear_float_agree_on_linear_tables() {
+    let table = identity_table_16(3, 3)
+        .iter()
+        .map(|v| *v as f32 / 65535.0)
+        .collect::<Vec<_>>();
+    let tetrahedral = InterpParams::new(&[3, 3, 3], 3, table.clone(), lerp_flags::FLOAT).unwrap();
+    let trilinear = InterpParams::new(
+        &[3, 3, 3],
+        3,
+        table,
+        lerp_flags::FLOAT | lerp_flags::TRILINEAR,
+    )
+    .unwrap();
+
+    let input = [0.1f32, 0.6, 0.35];
+    let mut a = [0f32; 3];
+    let mut b = [0f32; 3];
+    tetrahedral.eval(&input, &mut a);
+    trilinear.eval(&input, &mut b);
+
+    for i in 0..3 {
+        assert!((a[i] - input[i]).abs() < 1e-4);
+        assert!((b[i] - input[i]).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn test_eval_n_inputs_float_blends_hyperplanes() {
+    // Output is the first input, whatever the others are
+    let mut table = vec![0f32; 16];
+    for (i, value) in table.iter_mut().enumerate() {
+        *value = if i >= 8 { 1.0 } else { 0.0 };
+    }
+    let params = InterpParams::new(&[2, 2, 2, 2], 1, table, lerp_flags::FLOAT).unwrap();
+    let mut out = [0f32];
+
+    params.eval(&[0.3, 0.9, 0.1, 0.5], &mut out);
+
+    assert!((out[0] - 0.3).abs() < 1e-6);
+}
+
+/// Takes the value of the nearest node, only for 16 bit tables with 4 inputs
+struct NearestNode;
+
+fn nearest_node_16(input: &[u16], output: &mut [u16], p: &InterpParams<u16>) {
+    let offset: u32 = input
+        .iter()
+        .zip(p.domain.iter().zip(&p.opta))
+        .map(|(&v, (&domain, &opta))| (v as u32 * domain + 0x7fff) / 0xffff * opta)
+        .sum();
+    let offset = offset as usize;
+    output[..p.n_outputs].copy_from_slice(&p.table[offset..offset + p.n_outputs]);
+}
+
+impl InterpolatorFactory for NearestNode {
+    fn interpolator_16(
+        &self,
+        n_inputs: usize,
+        _n_outputs: usize,
+        flags: u32,
+    ) -> Option<InterpFn<u16>> {
+        if n_inputs == 4 && flags & lerp_flags::TRILINEAR == 0 {
+            Some(nearest_node_16)
+        } else {
+            None
+        }
+    }
+}
+
+fn nearest_node_context() -> Context {
+    let plugin = PluginBase::new(Plugin::Interpolation(Arc::new(NearestNode)));
+    Context::with_plugins(&plugin).unwrap()
+}
+
+#[test]
+fn test_interpolation_plugin_overrides_matching_configurations() {
+    let context = nearest_node_context();
+    let table = identity_table_16(3, 4);
+    let input = [0x3000u16, 0x5000, 0xB000, 0xFFFF];
+
+    let built_in = InterpParams::new(&[3; 4], 4, table.clone(), lerp_flags::BITS_16).unwrap();
+    let mut out = [0u16; 4];
+    built_in.eval(&input, &mut out);
+    assert_eq!(input, out);
+
+    let plugin =
+        InterpParams::new_with_context(&context, &[3; 4], 4, table.clone(), lerp_flags::BITS_16)
+            .unwrap();
+    plugin.eval(&input, &mut out);
+    assert_eq!([0, 0x7FFF, 0x7FFF, 0xFFFF], out);
+
+    // Other configurations are left to the built-in routines
+    let trilinear =
+        InterpParams::new_with_context(&context, &[3; 4], 4, table, lerp_flags::TRILINEAR).unwrap();
+    trilinear.eval(&input, &mut out);
+    assert_eq!(input, out);
+
+    let mut three_inputs = InterpParams::new(&[3; 3], 3, identity_table_16(3, 3), 0).unwrap();
+    assert!(!three_inputs.use_interpolators(&context));
+}
+
+#[test]
+fn test_pipelines_read_from_profiles_use_context_interpolators() {
+    let context = nearest_node_context();
+    let table = identity_table_16(3, 4);
+    let clut = Stage::new_clut16(3, 4, 4, Some(&table)).unwrap();
+    let mut pipeline = Pipeline::new(4, 4).unwrap();
+    for stage in [
+        Stage::new_identity_curves(4).unwrap(),
+        clut,
+        Stage::new_identity_curves(4).unwrap(),
+    ] {
+        pipeline.insert_stage(StageLoc::AtEnd, stage);
+    }
+
+    let mut profile = Profile::new();
+    profile.device_class = profile_class::LINK;
+    profile.color_space = color_space::CMYK;
+    profile.pcs = color_space::CMYK;
+    profile
+        .write_tag(tag::A_TO_B0, TagData::Pipeline(pipeline))
+        .unwrap();
+    let data = profile.save_to_mem().unwrap();
+
+    let read = |profile: &Profile| match profile.read_tag(tag::A_TO_B0) {
+        Ok(TagData::Pipeline(pipeline)) => pipeline,
+        other => panic!("Unexpected tag {:?}", other),
+    };
+    let input = [0x3000u16, 0x5000, 0xB000, 0xFFFF];
+    let mut out = [0u16; 4];
+
+    read(&Profile::open_mem(&data).unwrap()).eval_16(&input, &mut out);
+    assert_eq!(input, out);
+
+    let profile = Profile::open_mem_with_context(&context, &data).unwrap();
+    read(&profile).eval_16(&input, &mut out);
+    assert_eq!([0, 0x7FFF, 0x7FFF, 0xFFFF], out);
+}
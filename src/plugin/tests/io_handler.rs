@@ -0,0 +1,165 @@
+use std::io::ErrorKind;
+
+use super::*;
+
+#[test]
+fn test_read_u32_fails_on_short_read() {
+    let data = [0x01u8, 0x02, 0x03];
+    let mut io = MemoryIo::new(&data);
+
+    let result = read_u32(&mut io);
+
+    assert_eq!(ErrorKind::UnexpectedEof, result.unwrap_err().kind());
+}
+
+#[test]
+fn test_read_handles_readers_returning_partial_data() {
+    // Chained readers return at most one segment per call
+    let (a, b) = ([0x12u8, 0x34], [0x56u8, 0x78]);
+    let mut reader = Read::chain(&a[..], &b[..]);
+
+    assert_eq!(0x12345678, read_u32(&mut reader).unwrap());
+}
+
+#[test]
+fn test_memory_io_seek_and_tell() {
+    let data = [0u8, 0, 0, 1, 0, 0, 0, 2];
+    let mut io = MemoryIo::new(&data);
+
+    io.seek(4).unwrap();
+    assert_eq!(4, io.tell());
+    assert_eq!(2, read_u32(&mut io).unwrap());
+    assert_eq!(8, io.tell());
+
+    assert!(io.seek(9).is_err());
+    assert_eq!(8, io.reported_size());
+}
+
+#[test]
+fn test_memory_io_read_only_rejects_writes() {
+    let data = [0u8; 4];
+    let mut io = MemoryIo::new(&data);
+
+    assert_eq!(
+        ErrorKind::PermissionDenied,
+        write_u32(&mut io, 1).unwrap_err().kind()
+    );
+}
+
+#[test]
+fn test_memory_io_writable_is_bounded() {
+    let mut buf = [0u8; 6];
+    let mut io = MemoryIo::new_writable(&mut buf);
+
+    write_u32(&mut io, 0xDEADBEEF).unwrap();
+    assert!(write_u32(&mut io, 1).is_err());
+    assert_eq!(&[0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00], io.data());
+}
+
+#[test]
+fn test_vec_io_grows_and_fills_gaps() {
+    let mut io = VecIo::new();
+
+    io.seek(2).unwrap();
+    write_u16(&mut io, 0xABCD).unwrap();
+    assert_eq!(4, io.used_space());
+
+    io.seek(0).unwrap();
+    assert_eq!(0x0000ABCD, read_u32(&mut io).unwrap());
+    assert_eq!(vec![0, 0, 0xAB, 0xCD], io.into_inner());
+}
+
+#[test]
+fn test_reads_past_the_end_give_nothing() {
+    let mut buf = [0u8; 8];
+    let mut io = MemoryIo::new_writable(&mut buf);
+    write_u16(&mut io, 0xABCD).unwrap();
+    io.seek(6).unwrap();
+    assert_eq!(0, io.read(&mut [0u8; 4]).unwrap());
+    assert_eq!(0, io.read(&mut []).unwrap());
+
+    let mut io = VecIo::from(vec![1, 2]);
+    io.seek(10).unwrap();
+    assert_eq!(0, io.read(&mut [0u8; 4]).unwrap());
+    assert_eq!(0, io.read(&mut []).unwrap());
+    assert_eq!(
+        ErrorKind::UnexpectedEof,
+        read_u32(&mut io).unwrap_err().kind()
+    );
+}
+
+#[test]
+fn test_null_io_only_counts() {
+    let mut io = NullIo::new();
+
+    write_u32(&mut io, 1).unwrap();
+    write_u64(&mut io, 2).unwrap();
+    io.seek(4).unwrap();
+    write_u8(&mut io, 3).unwrap();
+
+    assert_eq!(5, io.tell());
+    assert_eq!(12, io.used_space());
+    assert!(read_u8(&mut io).is_err());
+}
+
+#[test]
+fn test_file_io_round_trip() {
+    let path = std::env::temp_dir().join(format!("lcms2-io-{}.bin", std::process::id()));
+    {
+        let mut io = FileIo::create(&path).unwrap();
+        write_u32(&mut io, 0x61637370).unwrap();
+        write_f64(&mut io, 1.5).unwrap();
+        assert_eq!(12, io.used_space());
+    }
+    {
+        let mut io = FileIo::open(&path).unwrap();
+        assert_eq!(12, io.reported_size());
+        io.seek(4).unwrap();
+        assert_eq!(1.5, read_f64(&mut io).unwrap());
+        assert!(read_u8(&mut io).is_err());
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_tag_io_is_relative_and_bounded() {
+    let data = [9u8, 9, 9, 9, 0, 0, 0, 7, 0, 0, 0, 8, 9, 9, 9, 9];
+    let mut io = MemoryIo::new(&data);
+    let mut tag = TagIo::new(&mut io, 4, 8).unwrap();
+
+    assert_eq!(0, tag.tell());
+    tag.seek(4).unwrap();
+    assert_eq!(8, read_u32(&mut tag).unwrap());
+    assert!(read_u8(&mut tag).is_err());
+
+    tag.seek(0).unwrap();
+    assert_eq!(7, read_u32(&mut tag).unwrap());
+    assert!(tag.seek(9).is_err());
+}
+
+#[test]
+fn test_alignment() {
+    let mut io = VecIo::new();
+
+    write_u8(&mut io, 1).unwrap();
+    write_alignment(&mut io).unwrap();
+    assert_eq!(4, io.tell());
+    write_alignment(&mut io).unwrap();
+    assert_eq!(4, io.tell());
+
+    io.seek(1).unwrap();
+    read_alignment(&mut io).unwrap();
+    assert_eq!(4, io.tell());
+    assert_eq!(8, align_32(5));
+}
+
+#[test]
+fn test_tag_base_keeps_reserved_bytes() {
+    let data = [b'X', b'Y', b'Z', b' ', 1, 2, 3, 4];
+
+    let tag_base = TagBase::read(&mut &data[..]).unwrap();
+    let mut buf = Vec::new();
+    tag_base.write(&mut buf).unwrap();
+
+    assert_eq!(data.to_vec(), buf);
+}
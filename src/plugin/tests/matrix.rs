@@ -0,0 +1,171 @@
+use std::convert::TryFrom;
+
+use super::*;
+
+fn assert_close(expected: f64, actual: f64, tolerance: f64) {
+    assert!(
+        (expected - actual).abs() < tolerance,
+        "Expected {} but was {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn test_matrix_from_rows_checks_length() {
+    assert!(Matrix::from_rows(2, 3, &[1.0; 6]).is_some());
+    assert!(Matrix::from_rows(2, 3, &[1.0; 5]).is_none());
+}
+
+#[test]
+fn test_matrix_transpose() {
+    let matrix = Matrix::from_rows(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+
+    let result = matrix.transpose();
+
+    assert_eq!(3, result.rows());
+    assert_eq!(2, result.cols());
+    assert_eq!(
+        Matrix::from_rows(3, 2, &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]).unwrap(),
+        result
+    );
+}
+
+#[test]
+fn test_matrix_multiply_with_mismatched_sizes_returns_none() {
+    let a = Matrix::new(2, 3);
+    let b = Matrix::new(2, 3);
+
+    assert!(a.per(&b).is_none());
+    assert_eq!(Some(Matrix::new(2, 2)), a.per(&b.transpose()));
+}
+
+#[test]
+fn test_matrix_agrees_with_mat3() {
+    let mat3 = Mat3::from([5.0, 6.0, -4.0, 7.0, 1.0, 1.0, -2.0, -9.0, 8.0]);
+    let matrix = Matrix::from(mat3);
+
+    let inverse = Mat3::try_from(&matrix.inverse().unwrap()).unwrap();
+    let expected = mat3.inverse().unwrap();
+
+    for (e, a) in <[f64; 9]>::from(expected)
+        .iter()
+        .zip(<[f64; 9]>::from(inverse).iter())
+    {
+        assert_close(*e, *a, 1e-12);
+    }
+}
+
+#[test]
+fn test_matrix_lu_solve_4x4() {
+    let matrix = Matrix::from_rows(
+        4,
+        4,
+        &[
+            2.0, 1.0, 1.0, 0.0, 4.0, 3.0, 3.0, 1.0, 8.0, 7.0, 9.0, 5.0, 6.0, 7.0, 9.0, 8.0,
+        ],
+    )
+    .unwrap();
+    let expected = [1.0, -2.0, 3.0, 0.5];
+    let b = matrix.eval(&expected).unwrap();
+
+    let result = matrix.solve(&b).unwrap();
+
+    for (e, a) in expected.iter().zip(result.iter()) {
+        assert_close(*e, *a, 1e-12);
+    }
+    assert_close(8.0, matrix.determinant().unwrap(), 1e-12);
+}
+
+#[test]
+fn test_matrix_singular_has_no_lu() {
+    let matrix = Matrix::from_rows(3, 3, &[1.0, 2.0, 3.0, 2.0, 4.0, 6.0, 1.0, 0.0, 1.0]).unwrap();
+
+    assert!(matrix.lu().is_none());
+    assert!(matrix.condition_number().is_none());
+    assert_eq!(Some(0.0), matrix.determinant());
+}
+
+#[test]
+fn test_matrix_condition_number() {
+    assert_close(1.0, Matrix::identity(5).condition_number().unwrap(), 1e-12);
+
+    let ill = Matrix::from_rows(2, 2, &[1.0, 1.0, 1.0, 1.0001]).unwrap();
+    assert!(ill.condition_number().unwrap() > 1e4);
+}
+
+#[test]
+fn test_matrix_least_squares_fits_line() {
+    // y = 2x + 1 with noise that cancels out
+    let a = Matrix::from_rows(4, 2, &[0.0, 1.0, 1.0, 1.0, 2.0, 1.0, 3.0, 1.0]).unwrap();
+    let b = Matrix::from_rows(4, 1, &[1.1, 2.9, 5.1, 6.9]).unwrap();
+
+    let x = a.least_squares(&b).unwrap();
+
+    assert_close(1.96, x[(0, 0)], 1e-12);
+    assert_close(1.06, x[(1, 0)], 1e-12);
+}
+
+#[test]
+fn test_matrix_least_squares_rank_deficient_returns_none() {
+    let a = Matrix::from_rows(3, 2, &[1.0, 2.0, 2.0, 4.0, 3.0, 6.0]).unwrap();
+    let b = Matrix::new(3, 1);
+
+    assert!(a.least_squares(&b).is_none());
+}
+
+#[test]
+fn test_fit_mat3_recovers_matrix() {
+    let m = Mat3::from([
+        0.4361, 0.3851, 0.1431, 0.2225, 0.7169, 0.0606, 0.0139, 0.0971, 0.7141,
+    ]);
+    let samples: Vec<(Vec3, Vec3)> = [
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(0.5, 0.25, 0.75),
+        Vec3::new(0.1, 0.9, 0.3),
+    ]
+    .iter()
+    .map(|v| (*v, m.eval(*v)))
+    .collect();
+
+    let result = fit_mat3(&samples).unwrap();
+
+    for (e, a) in <[f64; 9]>::from(m)
+        .iter()
+        .zip(<[f64; 9]>::from(result).iter())
+    {
+        assert_close(*e, *a, 1e-12);
+    }
+}
+
+#[test]
+fn test_fit_mat3_with_offset_recovers_offset() {
+    let m = Mat3::from([2.0, 0.0, 0.0, 0.0, 3.0, 0.0, 0.0, 1.0, 1.0]);
+    let offset = Vec3::new(0.1, -0.2, 0.3);
+    let samples: Vec<(Vec3, Vec3)> = [
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(1.0, 1.0, 1.0),
+    ]
+    .iter()
+    .map(|v| (*v, m.eval(*v) + offset))
+    .collect();
+
+    let (result, result_offset) = fit_mat3_with_offset(&samples).unwrap();
+
+    assert!((result * m.inverse().unwrap()).is_identity());
+    assert_close(offset.x, result_offset.x, 1e-12);
+    assert_close(offset.y, result_offset.y, 1e-12);
+    assert_close(offset.z, result_offset.z, 1e-12);
+}
+
+#[test]
+fn test_fit_mat3_needs_three_samples() {
+    let samples = [(Vec3::new(1.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0))];
+
+    assert!(fit_mat3(&samples).is_none());
+}
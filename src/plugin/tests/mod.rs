@@ -1,4 +1,9 @@
 use super::*;
 
+mod interpolation;
+mod io_handler;
 mod mat3;
+mod matrix;
+mod tag_type;
+mod transform;
 mod vec3;
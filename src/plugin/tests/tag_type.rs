@@ -0,0 +1,176 @@
+use std::any::Any;
+use std::io::{Error, ErrorKind};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+use crate::tag_types::{TagData, TagDescriptor};
+use crate::{Context, Profile};
+
+#[derive(Clone, PartialEq, Debug)]
+struct Calibration {
+    gain: u32,
+    offsets: Vec<u16>,
+}
+
+struct CalibrationHandler {
+    signature: Signature,
+    duplicated: AtomicUsize,
+}
+
+impl CalibrationHandler {
+    fn new(signature: &[u8; 4]) -> Arc<Self> {
+        Arc::new(Self {
+            signature: Signature::new(signature),
+            duplicated: AtomicUsize::new(0),
+        })
+    }
+}
+
+impl TagTypeHandler for CalibrationHandler {
+    fn signature(&self) -> Signature {
+        self.signature
+    }
+
+    fn read(&self, io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Box<dyn Any + Send + Sync>> {
+        let gain = read_u32(io)?;
+        let mut offsets = vec![0u16; (size_of_tag.saturating_sub(4) / 2) as usize];
+        read_u16_array(io, &mut offsets)?;
+
+        Ok(Box::new(Calibration { gain, offsets }))
+    }
+
+    fn write(&self, io: &mut dyn IoHandler, data: &(dyn Any + Send + Sync)) -> Result<()> {
+        let value = data
+            .downcast_ref::<Calibration>()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Not a calibration"))?;
+
+        write_u32(io, value.gain)?;
+        write_u16_array(io, &value.offsets)
+    }
+
+    fn duplicate(&self, data: &(dyn Any + Send + Sync)) -> Box<dyn Any + Send + Sync> {
+        self.duplicated.fetch_add(1, Ordering::SeqCst);
+        Box::new(data.downcast_ref::<Calibration>().unwrap().clone())
+    }
+}
+
+fn calibration() -> Calibration {
+    Calibration {
+        gain: 3,
+        offsets: vec![0x1234, 0xFFFF, 0],
+    }
+}
+
+#[test]
+fn test_plugin_tag_round_trips_through_profile() {
+    const TYPES: &[Signature] = &[Signature::new(b"cal1")];
+    let handler = CalibrationHandler::new(b"cal1");
+    let tag_sig = Signature::new(b"Cal1");
+
+    let tag_plugin = PluginBase::new(Plugin::Tag {
+        signature: tag_sig,
+        descriptor: TagDescriptor {
+            element_count: 1,
+            supported_types: TYPES,
+            decide_type: None,
+        },
+    });
+    let type_plugin = PluginBase::new(Plugin::TagType(handler.clone())).with_next(&tag_plugin);
+    let context = Context::with_plugins(&type_plugin).unwrap();
+    assert!(context.is_type_supported(Signature::new(b"cal1")));
+
+    let mut profile = Profile::new_with_context(&context);
+    let tag = CustomTag::new(handler.clone(), calibration());
+    profile.write_tag(tag_sig, TagData::Custom(tag)).unwrap();
+
+    let data = profile.save_to_mem().unwrap();
+    let profile = Profile::open_mem_with_context(&context, &data).unwrap();
+    assert_eq!(Some(Signature::new(b"cal1")), profile.tag_type(tag_sig));
+
+    let raw = profile.read_raw_tag(tag_sig).unwrap();
+    assert_eq!(b"cal1\0\0\0\0\0\0\0\x03\x12\x34\xFF\xFF\0\0", &raw[..]);
+
+    match profile.read_tag(tag_sig).unwrap() {
+        TagData::Custom(tag) => {
+            assert_eq!(Some(&calibration()), tag.get::<Calibration>());
+            assert_eq!(None, tag.get::<u32>());
+
+            let copy = tag.clone();
+            assert_eq!(1, handler.duplicated.load(Ordering::SeqCst));
+            assert_eq!(tag, copy);
+        }
+        other => panic!("Unexpected tag data {:?}", other),
+    }
+}
+
+#[test]
+fn test_tag_type_of_other_context_is_kept_raw() {
+    let handler = CalibrationHandler::new(b"cal2");
+    let tag_sig = Signature::new(b"Cal2");
+
+    let mut io = VecIo::new();
+    handler.write_tag(&mut io, &calibration()).unwrap();
+
+    let mut profile = Profile::new();
+    profile.write_raw_tag(tag_sig, io.data()).unwrap();
+    let data = profile.save_to_mem().unwrap();
+
+    // Only the tag type is registered, not the tag
+    let plugin = PluginBase::new(Plugin::TagType(handler.clone()));
+    let context = Context::with_plugins(&plugin).unwrap();
+    assert!(Profile::open_mem_with_context(&context, &data)
+        .unwrap()
+        .read_tag(tag_sig)
+        .is_err());
+
+    let profile = Profile::open_mem(&data).unwrap();
+
+    assert_eq!(
+        ErrorKind::InvalidData,
+        profile.read_tag(tag_sig).unwrap_err().kind()
+    );
+    assert_eq!(io.data(), &profile.read_raw_tag(tag_sig).unwrap()[..]);
+}
+
+#[test]
+fn test_read_tag_checks_type_base() {
+    let handler = CalibrationHandler::new(b"cal3");
+    let other = CalibrationHandler::new(b"cal4");
+
+    let mut io = VecIo::new();
+    other.write_tag(&mut io, &calibration()).unwrap();
+    let data = io.into_inner();
+
+    let result = handler.read_tag(&mut MemoryIo::new(&data), data.len() as u32);
+    assert_eq!(ErrorKind::InvalidData, result.unwrap_err().kind());
+
+    let result = other
+        .read_tag(&mut MemoryIo::new(&data), data.len() as u32)
+        .unwrap();
+    assert_eq!(Some(&calibration()), result.downcast_ref::<Calibration>());
+}
+
+#[test]
+fn test_register_plugin_rejects_bad_chains() {
+    let handler = CalibrationHandler::new(b"cal5");
+    let handler_sig = handler.signature();
+
+    let mut bad_magic = PluginBase::new(Plugin::TagType(handler.clone()));
+    bad_magic.magic = Signature::new(b"nope");
+    let chain = PluginBase::new(Plugin::TagType(handler.clone())).with_next(&bad_magic);
+    let mut context = Context::new();
+    assert_eq!(
+        ErrorKind::InvalidInput,
+        context.register_plugins(&chain).unwrap_err().kind()
+    );
+
+    let mut bad_type = PluginBase::new(Plugin::TagType(handler));
+    bad_type.r#type = plugin_type::TAG;
+    assert_eq!(
+        ErrorKind::InvalidInput,
+        context.register_plugins(&bad_type).unwrap_err().kind()
+    );
+
+    // Nothing from a rejected chain gets registered
+    assert!(!context.is_type_supported(handler_sig));
+}
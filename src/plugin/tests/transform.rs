@@ -0,0 +1,140 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::*;
+use crate::signatures::{color_space, profile_class, tag};
+use crate::tag_types::TagData;
+use crate::{Context, Pipeline, PixelType, Profile, Stage, StageLoc, Transform};
+
+/// RGB to RGB device link inverting the values
+fn negative_link(context: &Context) -> Profile {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    let negate = [-1.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, -1.0];
+    let stage = Stage::new_matrix(3, 3, &negate, Some(&[1.0, 1.0, 1.0])).unwrap();
+    assert!(pipeline.insert_stage(StageLoc::AtEnd, stage));
+
+    let mut profile = Profile::new_with_context(context);
+    profile.device_class = profile_class::LINK;
+    profile.color_space = color_space::RGB;
+    profile.pcs = color_space::RGB;
+    profile
+        .write_tag(tag::A_TO_B0, TagData::Pipeline(pipeline))
+        .unwrap();
+    profile
+}
+
+/// RGB 8 to RGB 8 worker going through a table of the pipeline for each channel
+struct Rgb8Worker {
+    tables: Vec<[u8; 256]>,
+}
+
+impl TransformWorker for Rgb8Worker {
+    fn transform(
+        &self,
+        _pipeline: &Pipeline,
+        input: &[u8],
+        output: &mut [u8],
+        pixels_per_line: usize,
+        line_count: usize,
+        stride: &Stride,
+    ) {
+        for line in 0..line_count {
+            let start_in = line * stride.bytes_per_line_in as usize;
+            let start_out = line * stride.bytes_per_line_out as usize;
+            let pixels_in = input[start_in..start_in + pixels_per_line * 3].chunks_exact(3);
+            let pixels_out = output[start_out..start_out + pixels_per_line * 3].chunks_exact_mut(3);
+
+            for (pixel_in, pixel_out) in pixels_in.zip(pixels_out) {
+                for c in 0..3 {
+                    pixel_out[c] = self.tables[c][pixel_in[c] as usize];
+                }
+            }
+        }
+    }
+}
+
+struct Rgb8Factory {
+    created: AtomicUsize,
+}
+
+impl TransformFactory for Rgb8Factory {
+    fn worker(
+        &self,
+        pipeline: &mut Pipeline,
+        input_format: PixelType,
+        output_format: PixelType,
+    ) -> Option<Arc<dyn TransformWorker>> {
+        if input_format != PixelType::RGB_8 || output_format != PixelType::RGB_8 {
+            return None;
+        }
+
+        // Only works for pipelines keeping channels apart, which this test knows it has
+        let mut tables = vec![[0u8; 256]; 3];
+        for v in 0..=255u8 {
+            let mut out = [0u16; 3];
+            pipeline.eval_16(&[v as u16 * 257; 3], &mut out);
+            for c in 0..3 {
+                tables[c][v as usize] = (out[c] >> 8) as u8;
+            }
+        }
+
+        self.created.fetch_add(1, Ordering::SeqCst);
+        Some(Arc::new(Rgb8Worker { tables }))
+    }
+}
+
+#[test]
+fn test_plugin_worker_takes_over_matching_formats() {
+    let factory = Arc::new(Rgb8Factory {
+        created: AtomicUsize::new(0),
+    });
+    let context =
+        Context::with_plugins(&PluginBase::new(Plugin::Transform(factory.clone()))).unwrap();
+    let link = negative_link(&context);
+
+    let transform = Transform::new_multiprofile(&[&link], 0)
+        .unwrap()
+        .with_formats(PixelType::RGB_8, PixelType::RGB_8)
+        .unwrap();
+    assert_eq!(1, factory.created.load(Ordering::SeqCst));
+
+    // Two lines of one pixel, with padding after each line
+    let stride = Stride {
+        bytes_per_line_in: 4,
+        bytes_per_line_out: 5,
+        ..Stride::default()
+    };
+    let mut out = [0xAAu8; 8];
+    transform
+        .transform_lines(&[0, 0x80, 0xff, 0, 1, 2, 3, 0], &mut out, 1, 2, &stride)
+        .unwrap();
+    assert_eq!([0xff, 0x7f, 0, 0xAA, 0xAA, 0xfe, 0xfd, 0xfc], out);
+
+    // Other formats go through the pipeline
+    let transform = Transform::new_multiprofile(&[&link], 0)
+        .unwrap()
+        .with_formats(PixelType::RGB_8, PixelType::RGB_16)
+        .unwrap();
+    assert_eq!(1, factory.created.load(Ordering::SeqCst));
+
+    let mut out = [0u8; 6];
+    transform
+        .transform_buffer(&[0, 0x80, 0xff], &mut out, 1)
+        .unwrap();
+    let words: Vec<u16> = out
+        .chunks_exact(2)
+        .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(vec![0xffff, 0x7f7f, 0], words);
+}
+
+#[test]
+fn test_transform_plugin_type_must_match() {
+    let factory = Arc::new(Rgb8Factory {
+        created: AtomicUsize::new(0),
+    });
+    let mut plugin = PluginBase::new(Plugin::Transform(factory));
+    assert_eq!(plugin_type::TRANSFORM, plugin.r#type);
+
+    plugin.r#type = plugin_type::OPTIMIZATION;
+    assert!(Context::with_plugins(&plugin).is_err());
+}
@@ -26,6 +26,7 @@ fn assert_vec3(expected: &Vec3, actual: &Vec3) {
 }
 
 #[test]
+#[allow(clippy::redundant_field_names)]
 fn test_vec3_new() {
     let (x, y, z) = (1.0, 69.0, 420.0);
 
@@ -65,6 +66,7 @@ fn test_vec3_dot() {
 }
 
 #[test]
+#[allow(clippy::excessive_precision)]
 fn test_vec3_length() {
     let value = Vec3::new(2.0, 3.0, 4.0);
 
@@ -74,6 +76,7 @@ fn test_vec3_length() {
 }
 
 #[test]
+#[allow(clippy::excessive_precision)]
 fn test_vec3_distance() {
     let left = Vec3::new(1.0, 69.0, 420.0);
     let right = Vec3::new(2.0, 3.0, 4.0);
@@ -7,6 +7,7 @@ pub struct Vec3 {
 
 impl Vec3 {
     /// Initializes a new vector
+    #[allow(clippy::redundant_field_names)]
     pub const fn new(x: f64, y: f64, z: f64) -> Self {
         Self { x: x, y: y, z: z }
     }
@@ -0,0 +1,36 @@
+use std::io::Result;
+
+use super::Profile;
+use crate::signatures::tag;
+use crate::tag_types::TagData;
+use crate::{ICCMeasurementConditions, ICCViewingConditions};
+
+impl Profile {
+    /// Reads the conditions the profile data was measured under
+    pub fn read_measurement(&self) -> Result<ICCMeasurementConditions> {
+        match self.read_tag(tag::MEASUREMENT)? {
+            TagData::Measurement(value) => Ok(value),
+            _ => Err(super::invalid_data(
+                "'meas' is not a measurement".to_string(),
+            )),
+        }
+    }
+
+    pub fn write_measurement(&mut self, value: ICCMeasurementConditions) -> Result<()> {
+        self.write_tag(tag::MEASUREMENT, TagData::Measurement(value))
+    }
+
+    /// Reads the viewing conditions the profile is meant for
+    pub fn read_viewing_conditions(&self) -> Result<ICCViewingConditions> {
+        match self.read_tag(tag::VIEWING_CONDITIONS)? {
+            TagData::ViewingConditions(value) => Ok(value),
+            _ => Err(super::invalid_data(
+                "'view' is not viewing conditions".to_string(),
+            )),
+        }
+    }
+
+    pub fn write_viewing_conditions(&mut self, value: ICCViewingConditions) -> Result<()> {
+        self.write_tag(tag::VIEWING_CONDITIONS, TagData::ViewingConditions(value))
+    }
+}
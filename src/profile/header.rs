@@ -0,0 +1,65 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::Profile;
+use crate::plugin::*;
+use crate::signatures::MAGIC_NUMBER;
+use crate::{d50, Signature};
+
+impl Profile {
+    /// Reads the fixed part of the header. Returns the profile size stored in it.
+    pub(super) fn read_header(&mut self, io: &mut dyn IoHandler) -> Result<u32> {
+        let size = read_u32(io)?;
+        self.cmm_id = Signature::from(read_u32(io)?);
+        self.version = read_u32(io)?;
+        self.device_class = Signature::from(read_u32(io)?);
+        self.color_space = Signature::from(read_u32(io)?);
+        self.pcs = Signature::from(read_u32(io)?);
+        self.created = read_date_time(io)?;
+
+        if Signature::from(read_u32(io)?) != MAGIC_NUMBER {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "not an ICC profile, invalid signature",
+            ));
+        }
+
+        self.platform = Signature::from(read_u32(io)?);
+        self.flags = read_u32(io)?;
+        self.manufacturer = Signature::from(read_u32(io)?);
+        self.model = read_u32(io)?;
+        self.attributes = read_u64(io)?;
+        self.rendering_intent = read_u32(io)?;
+
+        // The illuminant is always D50
+        read_encoded_xyz(io)?;
+
+        self.creator = Signature::from(read_u32(io)?);
+        io.read_exact(&mut self.profile_id)?;
+
+        let mut reserved = [0u8; 28];
+        io.read_exact(&mut reserved)?;
+
+        Ok(size)
+    }
+
+    pub(super) fn write_header(&self, io: &mut dyn IoHandler, size: u32) -> Result<()> {
+        write_u32(io, size)?;
+        write_u32(io, u32::from(self.cmm_id))?;
+        write_u32(io, self.version)?;
+        write_u32(io, u32::from(self.device_class))?;
+        write_u32(io, u32::from(self.color_space))?;
+        write_u32(io, u32::from(self.pcs))?;
+        write_date_time(io, self.created)?;
+        write_u32(io, u32::from(MAGIC_NUMBER))?;
+        write_u32(io, u32::from(self.platform))?;
+        write_u32(io, self.flags)?;
+        write_u32(io, u32::from(self.manufacturer))?;
+        write_u32(io, self.model)?;
+        write_u64(io, self.attributes)?;
+        write_u32(io, self.rendering_intent)?;
+        write_xyz(io, d50::XYZ)?;
+        write_u32(io, u32::from(self.creator))?;
+        io.write_all(&self.profile_id)?;
+        io.write_all(&[0u8; 28])
+    }
+}
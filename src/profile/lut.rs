@@ -0,0 +1,379 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::{not_found, Profile};
+use crate::internal::quick_saturate_word;
+use crate::pipeline::{Stage, StageData, StageLoc};
+use crate::plugin::NullIo;
+use crate::signatures::{color_space, profile_class, stage, tag, tag_type};
+use crate::tag_types::{write_tag_type, TagData};
+use crate::{Pipeline, Signature, ToneCurve};
+
+/// Scale applied to v4 Lab to get the v2 16 bit encoding
+const V4_TO_V2: f64 = 65280.0 / 65535.0;
+
+/// Tags holding the device to PCS tables of each intent. Absolute colorimetric uses the relative one.
+const DEVICE_TO_PCS_16: [Signature; 4] = [tag::A_TO_B0, tag::A_TO_B1, tag::A_TO_B2, tag::A_TO_B1];
+const DEVICE_TO_PCS_FLOAT: [Signature; 4] =
+    [tag::D_TO_B0, tag::D_TO_B1, tag::D_TO_B2, tag::D_TO_B3];
+/// Tags holding the PCS to device tables of each intent. Absolute colorimetric uses the relative one.
+const PCS_TO_DEVICE_16: [Signature; 4] = [tag::B_TO_A0, tag::B_TO_A1, tag::B_TO_A2, tag::B_TO_A1];
+const PCS_TO_DEVICE_FLOAT: [Signature; 4] =
+    [tag::B_TO_D0, tag::B_TO_D1, tag::B_TO_D2, tag::B_TO_D3];
+
+impl Profile {
+    /// Space of the values a lookup table tag takes
+    fn lut_input_space(&self, sig: Signature) -> Signature {
+        match sig {
+            tag::B_TO_A0
+            | tag::B_TO_A1
+            | tag::B_TO_A2
+            | tag::GAMUT
+            | tag::PREVIEW0
+            | tag::PREVIEW1
+            | tag::PREVIEW2
+            | tag::B_TO_D0
+            | tag::B_TO_D1
+            | tag::B_TO_D2
+            | tag::B_TO_D3 => self.pcs,
+            _ => self.color_space,
+        }
+    }
+
+    /// Space of the values a lookup table tag gives
+    fn lut_output_space(&self, sig: Signature) -> Option<Signature> {
+        match sig {
+            tag::B_TO_A0
+            | tag::B_TO_A1
+            | tag::B_TO_A2
+            | tag::B_TO_D0
+            | tag::B_TO_D1
+            | tag::B_TO_D2
+            | tag::B_TO_D3 => Some(self.color_space),
+            tag::GAMUT => None,
+            _ => Some(self.pcs),
+        }
+    }
+
+    /// Reads a lookup table tag as a pipeline taking and giving Lab in the v4 encoding. The matrix of lut8 and
+    /// lut16 types is dropped unless the input is XYZ, and the v2 Lab encoding of lut16 types is converted.
+    /// Multi process elements work on actual Lab and XYZ values, so those get normalized to the 0..1 range.
+    pub fn read_pipeline(&self, sig: Signature) -> Result<Pipeline> {
+        let type_sig = self.tag_type(sig).ok_or_else(|| not_found(sig))?;
+        let mut result = match self.read_tag(sig)? {
+            TagData::Pipeline(pipeline) => pipeline,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Tag is not a lookup table",
+                ))
+            }
+        };
+
+        let input_space = self.lut_input_space(sig);
+        let output_space = self.lut_output_space(sig);
+
+        if type_sig == tag_type::MULTI_PROCESS_ELEMENT {
+            normalize(
+                &mut result,
+                from_normalized(Some(input_space)),
+                to_normalized(output_space),
+            )?;
+            return Ok(result);
+        }
+
+        if type_sig != tag_type::LUT8 && type_sig != tag_type::LUT16 {
+            return Ok(result);
+        }
+
+        if input_space != color_space::XYZ && first_is_matrix(&result) {
+            result.remove_stage(StageLoc::AtBegin);
+        }
+
+        if type_sig == tag_type::LUT16 {
+            let begin = Some(Stage::new_lab_v4_to_v2()).filter(|_| input_space == color_space::LAB);
+            let end =
+                Some(Stage::new_lab_v2_to_v4()).filter(|_| output_space == Some(color_space::LAB));
+            normalize(&mut result, begin, end)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Writes a pipeline taking and giving Lab in the v4 encoding to a lookup table tag, converting to the v2 Lab
+    /// encoding if it ends up as a lut16 type, or to actual Lab and XYZ values for multi process elements
+    pub fn write_pipeline(&mut self, sig: Signature, pipeline: &Pipeline) -> Result<()> {
+        let type_sig = self
+            .context
+            .tag_descriptor(sig)
+            .map(|d| d.type_for(self.version(), &TagData::Pipeline(pipeline.clone())))
+            .unwrap_or(tag_type::LUT16);
+
+        if type_sig == tag_type::MULTI_PROCESS_ELEMENT {
+            let mut pipeline = pipeline.clone();
+            normalize(
+                &mut pipeline,
+                to_normalized(Some(self.lut_input_space(sig))),
+                from_normalized(self.lut_output_space(sig)),
+            )?;
+            return self.store_pipeline(sig, type_sig, pipeline);
+        }
+
+        if type_sig != tag_type::LUT8 && type_sig != tag_type::LUT16 {
+            return self.store_pipeline(sig, type_sig, pipeline.clone());
+        }
+
+        let input_space = self.lut_input_space(sig);
+        let mut pipeline = pipeline.clone();
+        let mut lab_in = input_space == color_space::LAB;
+        let mut lab_out = self.lut_output_space(sig) == Some(color_space::LAB);
+
+        // Encoding stages added by read_pipeline are dropped rather than converted once more
+        if type_sig == tag_type::LUT16 {
+            if lab_in && implements(pipeline.first_stage(), stage::LAB_V4_TO_V2) {
+                pipeline.remove_stage(StageLoc::AtBegin);
+                lab_in = false;
+            }
+            if lab_out && implements(pipeline.last_stage(), stage::LAB_V2_TO_V4) {
+                pipeline.remove_stage(StageLoc::AtEnd);
+                lab_out = false;
+            }
+        }
+
+        if input_space != color_space::XYZ && first_is_matrix(&pipeline) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Matrix of a LUT only applies for XYZ input",
+            ));
+        }
+
+        if type_sig == tag_type::LUT16 {
+            if lab_in {
+                from_v2_at_begin(&mut pipeline)?;
+            }
+            if lab_out {
+                to_v2_at_end(&mut pipeline)?;
+            }
+        }
+
+        self.store_pipeline(sig, type_sig, pipeline)
+    }
+
+    /// Picks the float table of the intent if there is one, then the 16 bit one, falling back to the perceptual
+    /// table
+    fn pick_lut_tag(
+        &self,
+        intent: u32,
+        tags_16: &[Signature; 4],
+        tags_float: &[Signature; 4],
+    ) -> Signature {
+        let intent = intent as usize;
+        if intent < tags_float.len() {
+            if self.has_tag(tags_float[intent]) {
+                return tags_float[intent];
+            }
+            if self.has_tag(tags_16[intent]) {
+                return tags_16[intent];
+            }
+        }
+        tags_16[0]
+    }
+
+    /// Pipeline of a named color profile, mapping color indices to PCS values in the v4 encoding if `use_pcs` is
+    /// set, or to device values otherwise
+    pub fn read_named_color_pipeline(&self, use_pcs: bool) -> Result<Pipeline> {
+        let list = match self.read_tag(tag::NAMED_COLOR2)? {
+            TagData::NamedColorList(list) => list,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Tag is not a named color list",
+                ))
+            }
+        };
+
+        let stage = Stage::new_named_color(list, use_pcs).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidData, "Named color list has no colorants")
+        })?;
+        let space = if use_pcs { self.pcs } else { self.color_space };
+
+        // Named colors keep Lab in the v2 encoding
+        let mut result = Pipeline::new(1, stage.output_channels()).unwrap();
+        result.insert_stage(StageLoc::AtEnd, stage);
+        if space == color_space::LAB {
+            result.insert_stage(StageLoc::AtEnd, Stage::new_lab_v2_to_v4());
+        }
+        Ok(result)
+    }
+
+    /// Pipeline taking device values to the PCS for the given intent
+    pub fn read_input_pipeline(&self, intent: u32) -> Result<Pipeline> {
+        if self.device_class == profile_class::NAMED_COLOR {
+            return self.read_named_color_pipeline(true);
+        }
+        self.read_lut_pipeline(self.pick_lut_tag(intent, &DEVICE_TO_PCS_16, &DEVICE_TO_PCS_FLOAT))
+    }
+
+    /// Pipeline taking PCS values to the device for the given intent
+    pub fn read_output_pipeline(&self, intent: u32) -> Result<Pipeline> {
+        self.read_lut_pipeline(self.pick_lut_tag(intent, &PCS_TO_DEVICE_16, &PCS_TO_DEVICE_FLOAT))
+    }
+
+    /// Reads the lookup table used for linking. Profiles without one are matrix-shaper based, which can't be
+    /// linked yet.
+    fn read_lut_pipeline(&self, sig: Signature) -> Result<Pipeline> {
+        if !self.has_tag(sig) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Profile has no '{}' tag, matrix-shaper profiles are not supported",
+                    String::from(sig)
+                ),
+            ));
+        }
+        self.read_pipeline(sig)
+    }
+
+    /// Pipeline of a device link or abstract profile for the given intent. Named color profiles give device
+    /// values.
+    pub fn read_devicelink_pipeline(&self, intent: u32) -> Result<Pipeline> {
+        if self.device_class == profile_class::NAMED_COLOR {
+            return self.read_named_color_pipeline(false);
+        }
+        self.read_lut_pipeline(self.pick_lut_tag(intent, &DEVICE_TO_PCS_16, &DEVICE_TO_PCS_FLOAT))
+    }
+
+    /// Writes the tag once it's known the pipeline can be encoded as the type, so unsuitable pipelines fail
+    /// here rather than when saving
+    fn store_pipeline(
+        &mut self,
+        sig: Signature,
+        type_sig: Signature,
+        pipeline: Pipeline,
+    ) -> Result<()> {
+        let data = TagData::Pipeline(pipeline);
+        write_tag_type(type_sig, &mut NullIo::new(), &data, self.version())?;
+
+        self.write_tag(sig, data)
+    }
+}
+
+/// Stage converting Lab or XYZ from the 0..1 range used by pipelines to actual values
+fn from_normalized(space: Option<Signature>) -> Option<Stage> {
+    match space {
+        Some(color_space::LAB) => Some(Stage::new_normalize_to_lab_float()),
+        Some(color_space::XYZ) => Some(Stage::new_normalize_to_xyz_float()),
+        _ => None,
+    }
+}
+
+/// Stage converting actual Lab or XYZ values to the 0..1 range used by pipelines
+fn to_normalized(space: Option<Signature>) -> Option<Stage> {
+    match space {
+        Some(color_space::LAB) => Some(Stage::new_normalize_from_lab_float()),
+        Some(color_space::XYZ) => Some(Stage::new_normalize_from_xyz_float()),
+        _ => None,
+    }
+}
+
+/// Puts the given stages around a pipeline
+fn normalize(pipeline: &mut Pipeline, begin: Option<Stage>, end: Option<Stage>) -> Result<()> {
+    let inserted = begin.is_none_or(|s| pipeline.insert_stage(StageLoc::AtBegin, s))
+        && end.is_none_or(|s| pipeline.insert_stage(StageLoc::AtEnd, s));
+    if inserted {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            "Channels of the LUT don't match its color spaces",
+        ))
+    }
+}
+
+fn implements(stage: Option<&Stage>, sig: Signature) -> bool {
+    stage.is_some_and(|s| s.implements() == sig)
+}
+
+fn first_is_matrix(pipeline: &Pipeline) -> bool {
+    pipeline
+        .first_stage()
+        .is_some_and(|s| s.stage_type() == stage::MATRIX_ELEM_TYPE)
+}
+
+fn unsuitable() -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        "LUT is not suitable to be saved as LUT16",
+    )
+}
+
+/// Makes the pipeline take v2 Lab, folding the conversion into the input curves. Grid points of the new curves
+/// fall on multiples of 1/256 in the v4 encoding, so the conversion itself is exact.
+fn from_v2_at_begin(pipeline: &mut Pipeline) -> Result<()> {
+    let curves = match pipeline.first_stage().map(|s| s.data()) {
+        Some(StageData::Curves(curves)) => {
+            let curves = curves.clone();
+            pipeline.remove_stage(StageLoc::AtBegin);
+            curves
+        }
+        _ => vec![ToneCurve::linear(); 3],
+    };
+
+    let steps = curves
+        .iter()
+        .map(|c| (c.n_entries() + 254) / 256)
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let n_entries = 257 * steps + 1;
+
+    let curves = curves
+        .iter()
+        .map(|curve| {
+            let table = (0..n_entries)
+                .map(|j| {
+                    let v4 = (j as f64 / (256 * steps) as f64).min(1.0);
+                    curve.eval_u16(quick_saturate_word(v4 * 65535.0))
+                })
+                .collect::<Vec<_>>();
+            ToneCurve::from_table16(&table).ok_or_else(unsuitable)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let stage = Stage::new_tone_curves(curves).ok_or_else(unsuitable)?;
+    if pipeline.insert_stage(StageLoc::AtBegin, stage) {
+        Ok(())
+    } else {
+        Err(unsuitable())
+    }
+}
+
+/// Makes the pipeline give v2 Lab, folding the conversion into the output curves
+fn to_v2_at_end(pipeline: &mut Pipeline) -> Result<()> {
+    let curves = match pipeline.last_stage().map(|s| s.data()) {
+        Some(StageData::Curves(curves)) => {
+            let curves = curves.clone();
+            pipeline.remove_stage(StageLoc::AtEnd);
+            curves
+        }
+        _ => vec![ToneCurve::linear(); 3],
+    };
+
+    let curves = curves
+        .iter()
+        .map(|curve| {
+            let table = curve
+                .table16()
+                .iter()
+                .map(|v| quick_saturate_word(*v as f64 * V4_TO_V2))
+                .collect::<Vec<_>>();
+            ToneCurve::from_table16(&table).ok_or_else(unsuitable)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let stage = Stage::new_tone_curves(curves).ok_or_else(unsuitable)?;
+    if pipeline.insert_stage(StageLoc::AtEnd, stage) {
+        Ok(())
+    } else {
+        Err(unsuitable())
+    }
+}
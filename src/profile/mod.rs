@@ -0,0 +1,562 @@
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::internal::MAX_TABLE_TAG;
+use crate::plugin::*;
+use crate::signatures::{self, color_space, profile_class};
+use crate::tag_types::{self, TagData};
+use crate::{d50, signatures::tag, Context, DateTimeNumber, Signature, TagEntry};
+
+mod conditions;
+mod header;
+mod lut;
+mod sequence;
+pub use sequence::ProfileSequenceDesc;
+
+#[cfg(test)]
+mod tests;
+
+/// Size of the fixed part of the header
+const HEADER_SIZE: u32 = 128;
+
+/// Tags that can be read as text with `Profile::info`
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum InfoType {
+    Description,
+    Manufacturer,
+    Model,
+    Copyright,
+}
+
+impl InfoType {
+    fn tag(self) -> Signature {
+        match self {
+            InfoType::Description => tag::PROFILE_DESCRIPTION,
+            InfoType::Manufacturer => tag::DEVICE_MFG_DESC,
+            InfoType::Model => tag::DEVICE_MODEL_DESC,
+            InfoType::Copyright => tag::COPYRIGHT,
+        }
+    }
+}
+
+enum TagContent {
+    /// Tag as found in the profile, including its type base. Decoded on demand.
+    Stored(Vec<u8>),
+    Decoded {
+        type_sig: Signature,
+        data: TagData,
+    },
+}
+
+struct ProfileTag {
+    signature: Signature,
+    /// Tag this one shares its contents with
+    linked: Option<Signature>,
+    content: TagContent,
+}
+
+/// An ICC profile. Tags are kept as found until they are read, and are decoded each time `read_tag` is called.
+/// Tags that aren't rewritten are saved back unchanged, whether they are understood or not.
+pub struct Profile {
+    pub cmm_id: Signature,
+    version: u32,
+    pub device_class: Signature,
+    pub color_space: Signature,
+    pub pcs: Signature,
+    pub created: DateTimeNumber,
+    pub platform: Signature,
+    pub flags: u32,
+    pub manufacturer: Signature,
+    pub model: u32,
+    pub attributes: u64,
+    pub rendering_intent: u32,
+    pub creator: Signature,
+    pub profile_id: [u8; 16],
+    tags: Vec<ProfileTag>,
+    context: Context,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn not_found(sig: Signature) -> Error {
+    Error::new(
+        ErrorKind::NotFound,
+        format!("Tag '{}' not found", String::from(sig)),
+    )
+}
+
+fn invalid_data(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Converts between the BCD encoding of the header version and decimal
+fn base_to_base(mut value: u32, base_in: u32, base_out: u32) -> u32 {
+    let mut digits = Vec::new();
+    while value > 0 {
+        digits.push(value % base_in);
+        value /= base_in;
+    }
+    digits.iter().rev().fold(0, |acc, d| acc * base_out + d)
+}
+
+impl Profile {
+    /// Empty v4.3 display profile with no tags
+    pub fn new() -> Self {
+        Self::new_with_context(&Context::new())
+    }
+
+    /// Empty v4.3 display profile with no tags, using the plugins of `context`
+    pub fn new_with_context(context: &Context) -> Self {
+        let mut result = Self {
+            cmm_id: signatures::LCMS_SIGNATURE,
+            version: 0,
+            device_class: profile_class::DISPLAY,
+            color_space: color_space::RGB,
+            pcs: color_space::XYZ,
+            created: DateTimeNumber::now(),
+            platform: Signature::from(0),
+            flags: 0,
+            manufacturer: Signature::from(0),
+            model: 0,
+            attributes: 0,
+            rendering_intent: 0,
+            creator: signatures::LCMS_SIGNATURE,
+            profile_id: [0; 16],
+            tags: Vec::new(),
+            context: context.clone(),
+        };
+        result.set_version(4.3);
+        result
+    }
+
+    /// Reads a profile from memory
+    pub fn open_mem(data: &[u8]) -> Result<Self> {
+        Self::open_mem_with_context(&Context::new(), data)
+    }
+
+    /// Reads a profile from memory, decoding its tags with the plugins of `context`
+    pub fn open_mem_with_context(context: &Context, data: &[u8]) -> Result<Self> {
+        Self::open_io_with_context(context, &mut MemoryIo::new(data))
+    }
+
+    /// Reads a profile from a file
+    pub fn open_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_file_with_context(&Context::new(), path)
+    }
+
+    /// Reads a profile from a file, decoding its tags with the plugins of `context`
+    pub fn open_file_with_context<P: AsRef<Path>>(context: &Context, path: P) -> Result<Self> {
+        Self::open_io_with_context(context, &mut FileIo::open(path)?)
+    }
+
+    /// Reads a profile from an IO handler positioned at the start of the profile
+    pub fn open_io(io: &mut dyn IoHandler) -> Result<Self> {
+        Self::open_io_with_context(&Context::new(), io)
+    }
+
+    /// Reads a profile from an IO handler positioned at the start of the profile, decoding its tags with the
+    /// plugins of `context`
+    pub fn open_io_with_context(context: &Context, io: &mut dyn IoHandler) -> Result<Self> {
+        let mut result = Self::new_with_context(context);
+        let base = io.tell();
+        let header_size = result.read_header(io)?;
+        let reported = io.reported_size().saturating_sub(base);
+        let header_size = if reported > 0 {
+            header_size.min(reported)
+        } else {
+            header_size
+        };
+
+        let tag_count = read_u32(io)?;
+        if tag_count > MAX_TABLE_TAG {
+            return Err(invalid_data(format!("Too many tags ({})", tag_count)));
+        }
+
+        let mut entries: Vec<TagEntry> = Vec::with_capacity(tag_count as usize);
+        for _ in 0..tag_count {
+            let entry = TagEntry {
+                signature: Signature::from(read_u32(io)?),
+                offset: read_u32(io)?,
+                size: read_u32(io)?,
+            };
+
+            // Offset + size should fall inside the profile
+            match entry.offset.checked_add(entry.size) {
+                Some(end) if end <= header_size && entry.size >= 8 => entries.push(entry),
+                _ => continue,
+            }
+        }
+
+        for (i, entry) in entries.iter().enumerate() {
+            // Tags pointing to the same data are linked
+            let linked = entries[..i]
+                .iter()
+                .find(|e| e.offset == entry.offset && e.size == entry.size)
+                .map(|e| e.signature);
+
+            let content = if linked.is_some() {
+                TagContent::Stored(Vec::new())
+            } else {
+                let mut data = vec![0u8; entry.size as usize];
+                io.seek(base + entry.offset)?;
+                io.read_exact(&mut data)?;
+                TagContent::Stored(data)
+            };
+
+            result.tags.push(ProfileTag {
+                signature: entry.signature,
+                linked,
+                content,
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// Context whose plugins are used to decode and encode tags
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Profile version as a number, like 4.3
+    pub fn version(&self) -> f64 {
+        base_to_base(self.version >> 16, 16, 10) as f64 / 100.0
+    }
+
+    pub fn set_version(&mut self, version: f64) {
+        // 4.2 -> 0x4200000
+        self.version = base_to_base(f64::floor(version * 100.0 + 0.5) as u32, 10, 16) << 16;
+    }
+
+    /// Version as stored in the header
+    pub fn encoded_version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn set_encoded_version(&mut self, version: u32) {
+        self.version = version;
+    }
+
+    pub fn tag_count(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Signatures of all the tags, in directory order
+    pub fn tag_signatures(&self) -> impl Iterator<Item = Signature> + '_ {
+        self.tags.iter().map(|t| t.signature)
+    }
+
+    pub fn has_tag(&self, sig: Signature) -> bool {
+        self.find(sig).is_some()
+    }
+
+    /// The tag that `sig` shares its contents with, if any
+    pub fn tag_linked_to(&self, sig: Signature) -> Option<Signature> {
+        self.find(sig).and_then(|i| self.tags[i].linked)
+    }
+
+    fn find(&self, sig: Signature) -> Option<usize> {
+        self.tags.iter().position(|t| t.signature == sig)
+    }
+
+    /// Finds a tag following links
+    fn find_content(&self, sig: Signature) -> Option<&TagContent> {
+        let mut sig = sig;
+        // Links are never chained on write, but corrupted profiles could do so
+        for _ in 0..=self.tags.len() {
+            let tag = &self.tags[self.find(sig)?];
+            match tag.linked {
+                Some(linked) => sig = linked,
+                None => return Some(&tag.content),
+            }
+        }
+        None
+    }
+
+    /// Type the tag is stored as
+    pub fn tag_type(&self, sig: Signature) -> Option<Signature> {
+        match self.find_content(sig)? {
+            TagContent::Stored(data) => Some(TagBase::read_type_base(&mut data.as_slice())),
+            TagContent::Decoded { type_sig, .. } => Some(*type_sig),
+        }
+    }
+
+    fn decode(&self, sig: Signature, data: &[u8]) -> Result<(Signature, TagData)> {
+        let descriptor = self
+            .context
+            .tag_descriptor(sig)
+            .ok_or_else(|| invalid_data(format!("Unknown tag '{}' found", String::from(sig))))?;
+
+        let mut io = MemoryIo::new(data);
+        let type_sig = TagBase::read(&mut io)?.signature;
+
+        if !descriptor.supported_types.contains(&type_sig) {
+            return Err(invalid_data(format!(
+                "Tag '{}' can't be of type '{}'",
+                String::from(sig),
+                String::from(type_sig)
+            )));
+        }
+
+        let result = self
+            .context
+            .read_tag_type(type_sig, &mut io, data.len() as u32 - 8)?;
+
+        if result.element_count() < descriptor.element_count {
+            return Err(invalid_data(format!(
+                "'{}' inconsistent number of items: expected {}, got {}",
+                String::from(sig),
+                descriptor.element_count,
+                result.element_count()
+            )));
+        }
+        Ok((type_sig, result))
+    }
+
+    /// Decodes a tag
+    pub fn read_tag(&self, sig: Signature) -> Result<TagData> {
+        let mut result = match self.find_content(sig).ok_or_else(|| not_found(sig))? {
+            TagContent::Stored(data) => self.decode(sig, data)?.1,
+            TagContent::Decoded { data, .. } => data.clone(),
+        };
+
+        // Lookup tables use the interpolation routines of the context
+        if let TagData::Pipeline(pipeline) = &mut result {
+            pipeline.use_interpolators(&self.context);
+        }
+        Ok(result)
+    }
+
+    /// Sets a tag, replacing any existing one. The type is picked according to the profile version.
+    pub fn write_tag(&mut self, sig: Signature, data: TagData) -> Result<()> {
+        let descriptor = self
+            .context
+            .tag_descriptor(sig)
+            .ok_or_else(|| invalid_data(format!("Unsupported tag '{}'", String::from(sig))))?;
+
+        if data.element_count() < descriptor.element_count {
+            return Err(invalid_data(format!(
+                "'{}' needs at least {} items",
+                String::from(sig),
+                descriptor.element_count
+            )));
+        }
+
+        let mut type_sig = descriptor.type_for(self.version(), &data);
+        if !data.fits_type(type_sig) {
+            type_sig = *descriptor
+                .supported_types
+                .iter()
+                .find(|t| data.fits_type(**t))
+                .ok_or_else(|| {
+                    invalid_data(format!(
+                        "Tag '{}' can't hold this kind of data",
+                        String::from(sig)
+                    ))
+                })?;
+        }
+
+        self.set_content(sig, TagContent::Decoded { type_sig, data });
+        Ok(())
+    }
+
+    /// Raw contents of a tag, including its type base. Tags set with `write_tag` are encoded.
+    pub fn read_raw_tag(&self, sig: Signature) -> Result<Vec<u8>> {
+        match self.find_content(sig).ok_or_else(|| not_found(sig))? {
+            TagContent::Stored(data) => Ok(data.clone()),
+            TagContent::Decoded { type_sig, data } => self.encode(*type_sig, data),
+        }
+    }
+
+    /// Sets a tag from its raw contents, type base included, replacing any existing one. Any tag signature and
+    /// type is accepted, and the data is saved unchanged.
+    pub fn write_raw_tag(&mut self, sig: Signature, data: &[u8]) -> Result<()> {
+        if data.len() < 8 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Raw tags need at least their type base",
+            ));
+        }
+
+        self.set_content(sig, TagContent::Stored(data.to_vec()));
+        Ok(())
+    }
+
+    fn set_content(&mut self, sig: Signature, content: TagContent) {
+        let tag = ProfileTag {
+            signature: sig,
+            linked: None,
+            content,
+        };
+        match self.find(sig) {
+            Some(i) => {
+                self.unlink_from(sig, i);
+                self.tags[i] = tag;
+            }
+            None => self.tags.push(tag),
+        }
+    }
+
+    /// Tags linked to the one at `index` get a copy of its contents before it changes
+    fn unlink_from(&mut self, sig: Signature, index: usize) {
+        let content = match &self.tags[index].content {
+            TagContent::Stored(data) => TagContent::Stored(data.clone()),
+            TagContent::Decoded { type_sig, data } => TagContent::Decoded {
+                type_sig: *type_sig,
+                data: data.clone(),
+            },
+        };
+
+        let mut content = Some(content);
+        let mut owner = None;
+        for tag in self.tags.iter_mut() {
+            if tag.linked == Some(sig) {
+                match owner {
+                    None => {
+                        tag.linked = None;
+                        tag.content = content.take().unwrap_or(TagContent::Stored(Vec::new()));
+                        owner = Some(tag.signature);
+                    }
+                    Some(owner) => tag.linked = Some(owner),
+                }
+            }
+        }
+    }
+
+    /// Makes `sig` share the contents of `dest`. If `dest` is linked itself, `sig` links to where it leads.
+    pub fn link_tag(&mut self, sig: Signature, dest: Signature) -> Result<()> {
+        let mut dest = dest;
+        for _ in 0..self.tags.len() {
+            match self.tag_linked_to(dest) {
+                Some(linked) => dest = linked,
+                None => break,
+            }
+        }
+        if !self.has_tag(dest) || sig == dest {
+            return Err(not_found(dest));
+        }
+        let tag = ProfileTag {
+            signature: sig,
+            linked: Some(dest),
+            content: TagContent::Stored(Vec::new()),
+        };
+        match self.find(sig) {
+            Some(i) => {
+                self.unlink_from(sig, i);
+                self.tags[i] = tag;
+            }
+            None => self.tags.push(tag),
+        }
+        Ok(())
+    }
+
+    /// Removes a tag. Tags linked to it keep its contents.
+    pub fn remove_tag(&mut self, sig: Signature) -> bool {
+        match self.find(sig) {
+            Some(i) => {
+                self.unlink_from(sig, i);
+                self.tags.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reads one of the text tags, in the given language or the closest available
+    pub fn info(&self, info: InfoType, language: &str, country: &str) -> Option<String> {
+        match self.read_tag(info.tag()).ok()? {
+            TagData::Mlu(mlu) => mlu.get(language, country),
+            _ => None,
+        }
+    }
+
+    /// Encodes a tag including its type base
+    fn encode(&self, type_sig: Signature, data: &TagData) -> Result<Vec<u8>> {
+        let mut io = VecIo::new();
+        TagBase::from(u32::from(type_sig)).write(&mut io)?;
+        tag_types::write_tag_type(type_sig, &mut io, data, self.version())?;
+        Ok(io.into_inner())
+    }
+
+    /// Writes the profile. Returns the number of bytes used.
+    pub fn save_to_io(&self, io: &mut dyn IoHandler) -> Result<u32> {
+        let base = io.tell();
+
+        // Encode all the tags first so their sizes are known. Tags still as found are written back unchanged,
+        // which keeps private and unknown ones.
+        let mut encoded: Vec<(Signature, Vec<u8>)> = Vec::new();
+        for tag in self.tags.iter().filter(|t| t.linked.is_none()) {
+            let data = match &tag.content {
+                TagContent::Stored(data) => data.clone(),
+                TagContent::Decoded { type_sig, data } => self.encode(*type_sig, data)?,
+            };
+            encoded.push((tag.signature, data));
+        }
+        let linked: Vec<(Signature, Signature)> = self
+            .tags
+            .iter()
+            .filter_map(|t| t.linked.map(|l| (t.signature, l)))
+            .filter(|(_, l)| encoded.iter().any(|(s, _)| s == l))
+            .collect();
+
+        let count = (encoded.len() + linked.len()) as u32;
+        let mut offset = HEADER_SIZE + 4 + 12 * count;
+        let mut directory = Vec::with_capacity(count as usize);
+        for (sig, data) in encoded.iter() {
+            directory.push(TagEntry {
+                signature: *sig,
+                offset,
+                size: data.len() as u32,
+            });
+            offset = align_32(offset + data.len() as u32);
+        }
+        for (sig, dest) in linked.iter() {
+            let target = directory
+                .iter()
+                .find(|e| e.signature == *dest)
+                .map(|e| (e.offset, e.size));
+            if let Some((offset, size)) = target {
+                directory.push(TagEntry {
+                    signature: *sig,
+                    offset,
+                    size,
+                });
+            }
+        }
+
+        self.write_header(io, offset)?;
+        write_u32(io, directory.len() as u32)?;
+        for entry in directory.iter() {
+            write_u32(io, u32::from(entry.signature))?;
+            write_u32(io, entry.offset)?;
+            write_u32(io, entry.size)?;
+        }
+        for (_, data) in encoded.iter() {
+            io.write_all(data)?;
+            write_alignment(io)?;
+        }
+
+        Ok(io.tell() - base)
+    }
+
+    pub fn save_to_mem(&self) -> Result<Vec<u8>> {
+        let mut io = VecIo::new();
+        self.save_to_io(&mut io)?;
+        Ok(io.into_inner())
+    }
+
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut io = FileIo::create(path)?;
+        self.save_to_io(&mut io)?;
+        Ok(())
+    }
+
+    /// Profile illuminant, which is always D50
+    pub fn illuminant(&self) -> crate::CIEXYZ {
+        d50::XYZ
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::Result;
+
+use super::Profile;
+use crate::signatures::tag;
+use crate::tag_types::TagData;
+use crate::{Mlu, Signature};
+
+/// Description of a profile taking part in a profile sequence, as kept by the `pseq` and `psid` tags
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ProfileSequenceDesc {
+    pub device_mfg: Signature,
+    pub device_model: Signature,
+    pub attributes: u64,
+    pub technology: Signature,
+    pub profile_id: [u8; 16],
+    pub manufacturer: Mlu,
+    pub model: Mlu,
+    pub description: Mlu,
+}
+
+impl Profile {
+    fn read_mlu(&self, sig: Signature) -> Mlu {
+        match self.read_tag(sig) {
+            Ok(TagData::Mlu(mlu)) => mlu,
+            _ => Mlu::new(),
+        }
+    }
+
+    /// Describes this profile for a profile sequence
+    pub fn sequence_desc(&self) -> ProfileSequenceDesc {
+        let technology = match self.read_tag(tag::TECHNOLOGY) {
+            Ok(TagData::Signature(sig)) => sig,
+            _ => Signature::from(0),
+        };
+
+        ProfileSequenceDesc {
+            device_mfg: self.manufacturer,
+            device_model: Signature::from(self.model),
+            attributes: self.attributes,
+            technology,
+            profile_id: self.profile_id,
+            manufacturer: self.read_mlu(tag::DEVICE_MFG_DESC),
+            model: self.read_mlu(tag::DEVICE_MODEL_DESC),
+            description: self.read_mlu(tag::PROFILE_DESCRIPTION),
+        }
+    }
+
+    /// Reads the profile sequence, merging the profile IDs and descriptions of the `psid` tag into the `pseq`
+    /// one. Either tag is used alone if the other is missing or doesn't match.
+    pub fn read_profile_sequence(&self) -> Result<Vec<ProfileSequenceDesc>> {
+        let descs = self.read_sequence_tag(tag::PROFILE_SEQUENCE_DESC);
+        let ids = self.read_sequence_tag(tag::PROFILE_SEQUENCE_ID);
+
+        match (descs, ids) {
+            (Ok(mut descs), Ok(ids)) if descs.len() == ids.len() => {
+                for (desc, id) in descs.iter_mut().zip(ids) {
+                    desc.profile_id = id.profile_id;
+                    desc.description = id.description;
+                }
+                Ok(descs)
+            }
+            (Ok(descs), _) => Ok(descs),
+            (Err(_), ids) => ids,
+        }
+    }
+
+    fn read_sequence_tag(&self, sig: Signature) -> Result<Vec<ProfileSequenceDesc>> {
+        match self.read_tag(sig)? {
+            TagData::ProfileSequence(seq) => Ok(seq),
+            _ => Err(super::invalid_data(format!(
+                "'{}' is not a profile sequence",
+                String::from(sig)
+            ))),
+        }
+    }
+
+    /// Writes the profile sequence to the `pseq` tag, and to the `psid` one for v4 profiles
+    pub fn write_profile_sequence(&mut self, seq: &[ProfileSequenceDesc]) -> Result<()> {
+        self.write_tag(
+            tag::PROFILE_SEQUENCE_DESC,
+            TagData::ProfileSequence(seq.to_vec()),
+        )?;
+        if self.version() >= 4.0 {
+            self.write_tag(
+                tag::PROFILE_SEQUENCE_ID,
+                TagData::ProfileSequence(seq.to_vec()),
+            )?;
+        }
+        Ok(())
+    }
+}
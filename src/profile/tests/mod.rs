@@ -0,0 +1,507 @@
+use super::*;
+use crate::pipeline::{Stage, StageLoc};
+use crate::signatures::tag_type;
+use crate::{Mlu, Pipeline, ToneCurve, CIEXYZ};
+
+fn english_and_spanish() -> Mlu {
+    let mut mlu = Mlu::new();
+    mlu.set("en", "US", "Display");
+    mlu.set("es", "ES", "Pantalla");
+    mlu
+}
+
+#[test]
+fn test_profile_version() {
+    let mut profile = Profile::new();
+
+    assert_eq!(4.3, profile.version());
+    assert_eq!(0x04300000, profile.encoded_version());
+
+    profile.set_version(2.1);
+    assert_eq!(0x02100000, profile.encoded_version());
+    assert_eq!(2.1, profile.version());
+}
+
+#[test]
+fn test_profile_round_trips_header() {
+    let mut profile = Profile::new();
+    profile.device_class = signatures::profile_class::OUTPUT;
+    profile.color_space = color_space::CMYK;
+    profile.pcs = color_space::LAB;
+    profile.manufacturer = Signature::new(b"ACME");
+    profile.model = 42;
+    profile.attributes = crate::device_attributes::MATTE as u64;
+    profile.rendering_intent = 1;
+
+    let data = profile.save_to_mem().unwrap();
+    assert_eq!(132, data.len());
+
+    let result = Profile::open_mem(&data).unwrap();
+    assert_eq!(profile.device_class, result.device_class);
+    assert_eq!(profile.color_space, result.color_space);
+    assert_eq!(profile.pcs, result.pcs);
+    assert_eq!(profile.manufacturer, result.manufacturer);
+    assert_eq!(42, result.model);
+    assert_eq!(2, result.attributes);
+    assert_eq!(1, result.rendering_intent);
+    assert_eq!(profile.created, result.created);
+    assert_eq!(4.3, result.version());
+}
+
+#[test]
+fn test_profile_rejects_bad_magic() {
+    let mut data = Profile::new().save_to_mem().unwrap();
+    data[36] = b'x';
+
+    assert_eq!(
+        ErrorKind::InvalidData,
+        Profile::open_mem(&data).err().unwrap().kind()
+    );
+}
+
+#[test]
+fn test_profile_v4_description_is_localized() {
+    let mut profile = Profile::new();
+    profile
+        .write_tag(
+            tag::PROFILE_DESCRIPTION,
+            TagData::Mlu(english_and_spanish()),
+        )
+        .unwrap();
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    assert_eq!(
+        Some(tag_type::MULTI_LOCALIZED_UNICODE),
+        result.tag_type(tag::PROFILE_DESCRIPTION)
+    );
+    assert_eq!(
+        Some("Pantalla".to_string()),
+        result.info(InfoType::Description, "es", "ES")
+    );
+    assert_eq!(
+        Some("Display".to_string()),
+        result.info(InfoType::Description, "en", "GB")
+    );
+    assert_eq!(
+        Some("Display".to_string()),
+        result.info(InfoType::Description, "de", "DE")
+    );
+}
+
+#[test]
+fn test_profile_v2_text_tags() {
+    let mut profile = Profile::new();
+    profile.set_version(2.1);
+    profile
+        .write_tag(tag::PROFILE_DESCRIPTION, TagData::Mlu(Mlu::from("sRGB")))
+        .unwrap();
+    profile
+        .write_tag(tag::COPYRIGHT, TagData::Mlu(Mlu::from("No copyright")))
+        .unwrap();
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    assert_eq!(
+        Some(tag_type::TEXT_DESCRIPTION),
+        result.tag_type(tag::PROFILE_DESCRIPTION)
+    );
+    assert_eq!(Some(tag_type::TEXT), result.tag_type(tag::COPYRIGHT));
+    assert_eq!(
+        Some("sRGB".to_string()),
+        result.info(InfoType::Description, "en", "US")
+    );
+    assert_eq!(
+        Some("No copyright".to_string()),
+        result.info(InfoType::Copyright, "en", "US")
+    );
+    assert_eq!(None, result.info(InfoType::Model, "en", "US"));
+}
+
+#[test]
+fn test_profile_write_tag_checks_data_kind() {
+    let mut profile = Profile::new();
+
+    assert!(profile
+        .write_tag(tag::COPYRIGHT, TagData::Xyz(vec![CIEXYZ::default()]))
+        .is_err());
+    assert!(profile
+        .write_tag(tag::MEDIA_WHITE_POINT, TagData::Xyz(vec![]))
+        .is_err());
+    assert_eq!(0, profile.tag_count());
+}
+
+#[test]
+fn test_profile_linked_tags_share_data() {
+    let mut profile = Profile::new();
+    profile
+        .write_tag(tag::MEDIA_WHITE_POINT, TagData::Xyz(vec![d50::XYZ]))
+        .unwrap();
+    profile
+        .link_tag(tag::LUMINANCE, tag::MEDIA_WHITE_POINT)
+        .unwrap();
+
+    let data = profile.save_to_mem().unwrap();
+    let mut result = Profile::open_mem(&data).unwrap();
+
+    assert_eq!(2, result.tag_count());
+    assert_eq!(
+        Some(tag::MEDIA_WHITE_POINT),
+        result.tag_linked_to(tag::LUMINANCE)
+    );
+    assert_eq!(
+        result.read_tag(tag::MEDIA_WHITE_POINT).unwrap(),
+        result.read_tag(tag::LUMINANCE).unwrap()
+    );
+
+    // Removing the target hands its contents to the linked tag
+    assert!(result.remove_tag(tag::MEDIA_WHITE_POINT));
+    assert_eq!(None, result.tag_linked_to(tag::LUMINANCE));
+    assert!(matches!(
+        result.read_tag(tag::LUMINANCE).unwrap(),
+        TagData::Xyz(_)
+    ));
+}
+
+#[test]
+fn test_profile_links_to_linked_tags_keep_the_data() {
+    let mut profile = Profile::new();
+    profile
+        .write_tag(tag::MEDIA_WHITE_POINT, TagData::Xyz(vec![d50::XYZ]))
+        .unwrap();
+    profile
+        .link_tag(tag::LUMINANCE, tag::MEDIA_WHITE_POINT)
+        .unwrap();
+    profile
+        .link_tag(tag::MEDIA_BLACK_POINT, tag::LUMINANCE)
+        .unwrap();
+
+    let data = profile.save_to_mem().unwrap();
+    let result = Profile::open_mem(&data).unwrap();
+
+    assert_eq!(3, result.tag_count());
+    assert_eq!(
+        Some(tag::MEDIA_WHITE_POINT),
+        result.tag_linked_to(tag::MEDIA_BLACK_POINT)
+    );
+    assert_eq!(
+        result.read_tag(tag::MEDIA_WHITE_POINT).unwrap(),
+        result.read_tag(tag::MEDIA_BLACK_POINT).unwrap()
+    );
+}
+
+#[test]
+fn test_profile_skips_tags_outside_of_profile() {
+    let mut profile = Profile::new();
+    profile
+        .write_tag(tag::MEDIA_WHITE_POINT, TagData::Xyz(vec![d50::XYZ]))
+        .unwrap();
+    let mut data = profile.save_to_mem().unwrap();
+    // Make the tag size point past the end
+    data[140..144].copy_from_slice(&1000u32.to_be_bytes());
+
+    let result = Profile::open_mem(&data).unwrap();
+
+    assert_eq!(0, result.tag_count());
+}
+
+#[test]
+fn test_profile_missing_tag_is_not_found() {
+    let profile = Profile::new();
+
+    assert_eq!(
+        ErrorKind::NotFound,
+        profile.read_tag(tag::COPYRIGHT).unwrap_err().kind()
+    );
+}
+
+/// Gray to Lab, with L following the input and neutral a and b
+fn gray_to_lab() -> Pipeline {
+    let mut result = Pipeline::new(1, 3).unwrap();
+    let table = [0, 0x8080, 0x8080, 0xffff, 0x8080, 0x8080];
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_clut16(2, 1, 3, Some(&table)).unwrap(),
+    );
+    result
+}
+
+#[test]
+fn test_profile_lut16_stores_v2_lab() {
+    let mut profile = Profile::new();
+    profile.set_version(2.1);
+    profile.color_space = color_space::GRAY;
+    profile.pcs = color_space::LAB;
+    profile
+        .write_pipeline(tag::A_TO_B0, &gray_to_lab())
+        .unwrap();
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+    assert_eq!(Some(tag_type::LUT16), result.tag_type(tag::A_TO_B0));
+
+    let mut out = [0u16; 3];
+    match result.read_tag(tag::A_TO_B0).unwrap() {
+        TagData::Pipeline(stored) => stored.eval_16(&[0xffff], &mut out),
+        _ => panic!("not a pipeline"),
+    }
+    assert_eq!([0xff00, 0x8000, 0x8000], out);
+
+    let pipeline = result.read_pipeline(tag::A_TO_B0).unwrap();
+    pipeline.eval_16(&[0xffff], &mut out);
+    assert_eq!([0xffff, 0x8080, 0x8080], out);
+}
+
+#[test]
+fn test_profile_lut16_lab_to_lab_round_trips() {
+    let mut profile = Profile::new();
+    profile.set_version(2.1);
+    profile.device_class = profile_class::ABSTRACT;
+    profile.color_space = color_space::LAB;
+    profile.pcs = color_space::LAB;
+    profile
+        .write_pipeline(tag::A_TO_B0, &Pipeline::new(3, 3).unwrap())
+        .unwrap();
+
+    let pipeline = profile.read_pipeline(tag::A_TO_B0).unwrap();
+
+    for input in [
+        [0u16, 0, 0],
+        [0x8080, 0x8080, 0x8080],
+        [0xffff, 0x1234, 0xfedc],
+    ]
+    .iter()
+    {
+        let mut out = [0u16; 3];
+        pipeline.eval_16(input, &mut out);
+        for i in 0..3 {
+            assert!(
+                (out[i] as i32 - input[i] as i32).abs() <= 2,
+                "{:?} gave {:?}",
+                input,
+                out
+            );
+        }
+    }
+}
+
+#[test]
+fn test_profile_lut16_from_v2_lab_writes_back() {
+    let mut clut = Stage::new_clut16(3, 3, 3, None).unwrap();
+    clut.sample_clut16(|input, output| output.copy_from_slice(input));
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    assert!(pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap()));
+    assert!(pipeline.insert_stage(StageLoc::AtEnd, clut));
+
+    let mut profile = Profile::new();
+    profile.set_version(2.1);
+    profile.pcs = color_space::LAB;
+    profile.write_pipeline(tag::B_TO_A0, &pipeline).unwrap();
+    let mut profile = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    let read = profile.read_pipeline(tag::B_TO_A0).unwrap();
+    assert_eq!(
+        Some(crate::signatures::stage::LAB_V4_TO_V2),
+        read.first_stage().map(|s| s.implements())
+    );
+
+    profile.write_pipeline(tag::B_TO_A1, &read).unwrap();
+    let profile = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+    assert_eq!(Some(tag_type::LUT16), profile.tag_type(tag::B_TO_A1));
+    assert_eq!(read, profile.read_pipeline(tag::B_TO_A1).unwrap());
+}
+
+#[test]
+fn test_profile_lut_matrix_only_applies_for_xyz_input() {
+    let matrix =
+        Stage::new_matrix(3, 3, &[0.5, 0.0, 0.0, 0.0, 0.5, 0.0, 0.0, 0.0, 0.5], None).unwrap();
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    pipeline.insert_stage(StageLoc::AtEnd, matrix);
+
+    let mut profile = Profile::new();
+    profile.pcs = color_space::XYZ;
+    profile.set_version(2.1);
+
+    // RGB input can't take it
+    assert_eq!(
+        ErrorKind::InvalidInput,
+        profile
+            .write_pipeline(tag::A_TO_B0, &pipeline)
+            .unwrap_err()
+            .kind()
+    );
+    profile.write_pipeline(tag::B_TO_A0, &pipeline).unwrap();
+    assert_eq!(
+        Some(crate::signatures::stage::MATRIX_ELEM_TYPE),
+        profile
+            .read_pipeline(tag::B_TO_A0)
+            .unwrap()
+            .first_stage()
+            .map(|s| s.stage_type())
+    );
+
+    // A matrix stored anyway is ignored
+    profile
+        .write_tag(tag::A_TO_B0, TagData::Pipeline(pipeline))
+        .unwrap();
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+    let pipeline = result.read_pipeline(tag::A_TO_B0).unwrap();
+    // Only the linear curves are left
+    assert_eq!(2, pipeline.stage_count());
+    assert!(pipeline
+        .stages()
+        .iter()
+        .all(|s| s.stage_type() == crate::signatures::stage::CURVE_SET_ELEM_TYPE));
+}
+
+#[test]
+fn test_profile_lut_type_depends_on_version() {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap());
+
+    let mut profile = Profile::new();
+    profile.write_pipeline(tag::A_TO_B0, &pipeline).unwrap();
+    profile.write_pipeline(tag::B_TO_A0, &pipeline).unwrap();
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    assert_eq!(Some(tag_type::LUTA_TO_B), result.tag_type(tag::A_TO_B0));
+    assert_eq!(Some(tag_type::LUTB_TO_A), result.tag_type(tag::B_TO_A0));
+    assert_eq!(1, result.read_pipeline(tag::A_TO_B0).unwrap().stage_count());
+
+    profile.set_version(2.1);
+    pipeline.set_save_as_8_bits(true);
+    profile.write_pipeline(tag::A_TO_B0, &pipeline).unwrap();
+    assert_eq!(Some(tag_type::LUT8), profile.tag_type(tag::A_TO_B0));
+}
+
+#[test]
+fn test_profile_trc_type_depends_on_version() {
+    let curve = ToneCurve::build_gamma(2.2).unwrap();
+
+    let mut profile = Profile::new();
+    profile
+        .write_tag(tag::RED_TRC, TagData::ToneCurve(curve.clone()))
+        .unwrap();
+    assert_eq!(
+        Some(tag_type::PARAMETRIC_CURVE),
+        profile.tag_type(tag::RED_TRC)
+    );
+
+    profile.set_version(2.1);
+    profile
+        .write_tag(tag::RED_TRC, TagData::ToneCurve(curve))
+        .unwrap();
+    assert_eq!(Some(tag_type::CURVE), profile.tag_type(tag::RED_TRC));
+}
+
+#[test]
+fn test_profile_float_tags_hold_actual_lab() {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap());
+
+    let mut profile = Profile::new();
+    profile.pcs = color_space::LAB;
+    profile.write_pipeline(tag::D_TO_B0, &pipeline).unwrap();
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+    assert_eq!(
+        Some(tag_type::MULTI_PROCESS_ELEMENT),
+        result.tag_type(tag::D_TO_B0)
+    );
+
+    let mut out = [0f32; 3];
+    match result.read_tag(tag::D_TO_B0).unwrap() {
+        TagData::Pipeline(stored) => stored.eval_float(&[1.0, 0.5, 0.0], &mut out),
+        _ => panic!("not a pipeline"),
+    }
+    for (expected, actual) in [100.0, -0.5, -128.0].iter().zip(out.iter()) {
+        assert!((expected - actual).abs() < 1e-4, "{:?}", out);
+    }
+
+    let pipeline = result.read_pipeline(tag::D_TO_B0).unwrap();
+    pipeline.eval_float(&[1.0, 0.5, 0.0], &mut out);
+    for (expected, actual) in [1.0, 0.5, 0.0].iter().zip(out.iter()) {
+        assert!((expected - actual).abs() < 1e-6, "{:?}", out);
+    }
+}
+
+#[test]
+fn test_profile_measurement_and_viewing_conditions() {
+    let measurement = crate::ICCMeasurementConditions {
+        observer: crate::StandardObserver::Cie1964,
+        geometry: crate::MeasurementGeometry::ZeroDiffuse,
+        illuminant_type: crate::IlluminantType::D65,
+        ..Default::default()
+    };
+    let viewing = crate::ICCViewingConditions {
+        illuminant_type: crate::IlluminantType::D50,
+        ..Default::default()
+    };
+
+    let mut profile = Profile::new();
+    assert!(profile.read_measurement().is_err());
+    profile.write_measurement(measurement).unwrap();
+    profile.write_viewing_conditions(viewing).unwrap();
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+    assert_eq!(measurement, result.read_measurement().unwrap());
+    assert_eq!(viewing, result.read_viewing_conditions().unwrap());
+}
+
+#[test]
+fn test_profile_keeps_unknown_tags() {
+    let private_sig = Signature::new(b"ACME");
+    let private = b"acmT\0\0\0\0calibration data\x01\x02".to_vec();
+    // Argyll stores its absolute to relative transform as a s15Fixed16ArrayType
+    let mut arts = b"sf32\0\0\0\0".to_vec();
+    arts.extend_from_slice(&[0, 1, 0, 0].repeat(9));
+
+    let mut profile = Profile::new();
+    profile.write_raw_tag(private_sig, &private).unwrap();
+    profile.write_raw_tag(tag::ARGYLL_ARTS, &arts).unwrap();
+    profile
+        .write_tag(tag::COPYRIGHT, TagData::Mlu(Mlu::from("Public domain")))
+        .unwrap();
+    assert!(profile.write_raw_tag(private_sig, b"abc").is_err());
+
+    let data = profile.save_to_mem().unwrap();
+    let result = Profile::open_mem(&data).unwrap();
+
+    assert_eq!(Some(Signature::new(b"acmT")), result.tag_type(private_sig));
+    assert!(result.read_tag(private_sig).is_err());
+    assert_eq!(private, result.read_raw_tag(private_sig).unwrap());
+    assert_eq!(arts, result.read_raw_tag(tag::ARGYLL_ARTS).unwrap());
+
+    // Saving again gives the same profile
+    assert_eq!(data, result.save_to_mem().unwrap());
+}
+
+#[test]
+fn test_profile_keeps_tags_it_cant_decode() {
+    // A text description too short for its own length
+    let corrupted = b"desc\0\0\0\0\0\0\0\x40ab".to_vec();
+
+    let mut profile = Profile::new();
+    profile
+        .write_raw_tag(tag::PROFILE_DESCRIPTION, &corrupted)
+        .unwrap();
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    assert!(result.read_tag(tag::PROFILE_DESCRIPTION).is_err());
+    assert_eq!(
+        corrupted,
+        result.read_raw_tag(tag::PROFILE_DESCRIPTION).unwrap()
+    );
+}
+
+#[test]
+fn test_profile_raw_tag_of_written_tag_is_encoded() {
+    let mut profile = Profile::new();
+    profile
+        .write_tag(tag::TECHNOLOGY, TagData::Signature(Signature::new(b"fscn")))
+        .unwrap();
+
+    assert_eq!(
+        b"sig \0\0\0\0fscn".to_vec(),
+        profile.read_raw_tag(tag::TECHNOLOGY).unwrap()
+    );
+}
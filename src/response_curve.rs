@@ -0,0 +1,35 @@
+use crate::{Signature, CIEXYZ};
+
+/// A measurement of a single channel
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Response16 {
+    pub device: u16,
+    pub measurement: f64,
+}
+
+/// Responses of each channel measured in a given unit
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ResponseCurve {
+    /// One of the `STATUS_*` or `DN*` signatures
+    pub measurement_unit: Signature,
+    /// PCS values of the maximum of each colorant
+    pub max_colorant_pcs: Vec<CIEXYZ>,
+    /// Measurements of each channel
+    pub responses: Vec<Vec<Response16>>,
+}
+
+/// Printer response curves, as stored by the `OUTPUT_RESPONSE` tag
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ResponseCurveSet {
+    pub channels: usize,
+    pub curves: Vec<ResponseCurve>,
+}
+
+impl ResponseCurveSet {
+    /// Curve measured in the given unit, such as `signatures::STATUS_T`
+    pub fn curve(&self, measurement_unit: Signature) -> Option<&ResponseCurve> {
+        self.curves
+            .iter()
+            .find(|c| c.measurement_unit == measurement_unit)
+    }
+}
@@ -1,3 +1,4 @@
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Signature(u32);
 
 impl Signature {
@@ -31,6 +32,7 @@ impl From<&[u8; 4]> for Signature {
     }
 }
 impl From<&[u8; 3]> for Signature {
+    #[allow(clippy::borrow_deref_ref)]
     fn from(value: &[u8; 3]) -> Self {
         let mut result: [u8; 4] = [0x20; 4];
         result[..3].copy_from_slice(&*value);
@@ -38,6 +40,7 @@ impl From<&[u8; 3]> for Signature {
     }
 }
 impl From<&[u8; 2]> for Signature {
+    #[allow(clippy::borrow_deref_ref)]
     fn from(value: &[u8; 2]) -> Self {
         let mut result: [u8; 4] = [0x20; 4];
         result[..2].copy_from_slice(&*value);
@@ -45,6 +48,7 @@ impl From<&[u8; 2]> for Signature {
     }
 }
 impl From<&[u8; 1]> for Signature {
+    #[allow(clippy::borrow_deref_ref)]
     fn from(value: &[u8; 1]) -> Self {
         let mut result: [u8; 4] = [0x20; 4];
         result[..1].copy_from_slice(&*value);
@@ -52,6 +56,7 @@ impl From<&[u8; 1]> for Signature {
     }
 }
 impl From<&[u8]> for Signature {
+    #[allow(clippy::absurd_extreme_comparisons, clippy::redundant_guards)]
     fn from(value: &[u8]) -> Self {
         let len = value.len();
         let mut result: [u8; 4] = [0x20; 4];
@@ -1,3 +1,10 @@
 use crate::Signature;
 
+pub const TAG_TYPE: Signature = Signature::new(b"typH");
+pub const TAG: Signature = Signature::new(b"tagH");
+pub const FORMATTERS: Signature = Signature::new(b"frmH");
+pub const INTERPOLATION: Signature = Signature::new(b"inpH");
+pub const OPTIMIZATION: Signature = Signature::new(b"optH");
+pub const RENDERING_INTENT: Signature = Signature::new(b"intH");
+pub const PARAMETRIC_CURVE: Signature = Signature::new(b"parH");
 pub const TRANSFORM: Signature = Signature::new(b"xfmH");
@@ -0,0 +1,56 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{
+    f64_to_s15f16, read_s15f16, read_u16, s15f16_to_f64, write_s15f16, write_u16, IoHandler,
+};
+use crate::{CIExyY, CIExyYTripple, Chromaticity};
+
+fn read_xy(io: &mut dyn IoHandler) -> Result<CIExyY> {
+    Ok(CIExyY {
+        x: s15f16_to_f64(read_s15f16(io)?),
+        y: s15f16_to_f64(read_s15f16(io)?),
+        Y: 1.0,
+    })
+}
+
+fn write_xy(io: &mut dyn IoHandler, value: CIExyY) -> Result<()> {
+    write_s15f16(io, f64_to_s15f16(value.x))?;
+    write_s15f16(io, f64_to_s15f16(value.y))
+}
+
+/// Reads a chromaticityType. Only three channels are supported.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Chromaticity> {
+    let mut channels = read_u16(io)?;
+
+    // Let's recover from a bug introduced in early versions of lcms1
+    if channels == 0 && size_of_tag == 32 {
+        read_u16(io)?;
+        channels = read_u16(io)?;
+    }
+
+    if channels != 3 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unsupported number of chromaticity channels '{}'", channels),
+        ));
+    }
+
+    let colorant_type = read_u16(io)?;
+    Ok(Chromaticity {
+        colorant_type,
+        primaries: CIExyYTripple {
+            red: read_xy(io)?,
+            green: read_xy(io)?,
+            blue: read_xy(io)?,
+        },
+    })
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &Chromaticity) -> Result<()> {
+    write_u16(io, 3)?;
+    write_u16(io, value.colorant_type)?;
+
+    write_xy(io, value.primaries.red)?;
+    write_xy(io, value.primaries.green)?;
+    write_xy(io, value.primaries.blue)
+}
@@ -0,0 +1,30 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{read_u32, write_u32, IoHandler};
+use crate::MAX_CHANNELS;
+
+fn too_many(count: usize, kind: ErrorKind) -> Error {
+    Error::new(kind, format!("Too many colorants '{}'", count))
+}
+
+/// Reads a colorantOrderType, the channel of each colorant in laydown order
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<Vec<u8>> {
+    let count = read_u32(io)? as usize;
+
+    if count > MAX_CHANNELS {
+        return Err(too_many(count, ErrorKind::InvalidData));
+    }
+
+    let mut result = vec![0u8; count];
+    io.read_exact(&mut result)?;
+    Ok(result)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &[u8]) -> Result<()> {
+    if value.len() > MAX_CHANNELS {
+        return Err(too_many(value.len(), ErrorKind::InvalidInput));
+    }
+
+    write_u32(io, value.len() as u32)?;
+    io.write_all(value)
+}
@@ -0,0 +1,43 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::named_color2::{read_name, write_name};
+use crate::plugin::{read_u16, read_u32, write_u16_array, write_u32, IoHandler};
+use crate::{NamedColorList, MAX_CHANNELS};
+
+/// Reads a colorantTableType as a list of colors without device values
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<NamedColorList> {
+    let count = read_u32(io)?;
+
+    if count as usize > MAX_CHANNELS {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Too many colorants '{}'", count),
+        ));
+    }
+
+    let mut result = NamedColorList::new(0, "", "").unwrap();
+    for _ in 0..count {
+        let name = read_name(io)?;
+        let pcs = [read_u16(io)?, read_u16(io)?, read_u16(io)?];
+
+        result.append(&name, pcs, &[]);
+    }
+    Ok(result)
+}
+
+/// Writes the names and PCS values of the colors, device values are left out
+pub fn write(io: &mut dyn IoHandler, value: &NamedColorList) -> Result<()> {
+    if value.len() > MAX_CHANNELS {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Too many colorants '{}'", value.len()),
+        ));
+    }
+
+    write_u32(io, value.len() as u32)?;
+    for color in value.colors() {
+        write_name(io, &color.name)?;
+        write_u16_array(io, &color.pcs)?;
+    }
+    Ok(())
+}
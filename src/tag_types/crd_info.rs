@@ -0,0 +1,43 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{read_u32, write_u32, IoHandler};
+use crate::Mlu;
+
+use super::text::ascii_to_string;
+
+/// Language the strings are stored with
+const LANGUAGE: &str = "PS";
+/// Country codes of the product name and the CRD names of the four intents
+const SECTIONS: [&str; 5] = ["nm", "#0", "#1", "#2", "#3"];
+
+/// Reads a crdInfoType. The PostScript product name and CRD names are stored as language "PS", with the "nm"
+/// country for the product name and "#0" to "#3" for each intent.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Mlu> {
+    let mut remaining = size_of_tag;
+    let mut result = Mlu::new();
+
+    for section in SECTIONS.iter() {
+        let count = read_u32(io)?;
+        remaining = count
+            .checked_add(4)
+            .and_then(|size| remaining.checked_sub(size))
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Corrupted crdInfo tag"))?;
+
+        let mut text = vec![0u8; count as usize];
+        io.read_exact(&mut text)?;
+        result.set(LANGUAGE, section, &ascii_to_string(&text));
+    }
+    Ok(result)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &Mlu) -> Result<()> {
+    for section in SECTIONS.iter() {
+        let text = value.get_ascii(LANGUAGE, section).unwrap_or_default();
+
+        // NUL terminator included
+        write_u32(io, text.len() as u32 + 1)?;
+        io.write_all(text.as_bytes())?;
+        io.write_all(&[0])?;
+    }
+    Ok(())
+}
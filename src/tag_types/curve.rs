@@ -0,0 +1,56 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use crate::plugin::{
+    f64_to_u8f8, read_u16, read_u16_array, read_u32, u8f8_to_f64, write_u16, write_u16_array,
+    write_u32,
+};
+use crate::{Context, ToneCurve};
+
+/// Largest number of entries accepted when reading
+const MAX_ENTRIES: u32 = 0x7fff;
+
+fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted curve")
+}
+
+/// Reads a curveType. No entries means the identity, and a single entry is the exponent of a gamma function.
+pub fn read(reader: &mut dyn Read, size_of_tag: u32) -> Result<ToneCurve> {
+    read_with_context(&Context::new(), reader, size_of_tag)
+}
+
+/// Reads a curveType, with the gamma functions of plugins registered in `context`
+pub fn read_with_context(
+    context: &Context,
+    reader: &mut dyn Read,
+    _size_of_tag: u32,
+) -> Result<ToneCurve> {
+    let count = read_u32(reader)?;
+    let gamma = |gamma| ToneCurve::build_parametric_with_context(context, 1, &[gamma]);
+
+    match count {
+        // Linear
+        0 => gamma(1.0).ok_or_else(corrupted),
+        // Specified as the exponent of gamma function
+        1 => gamma(u8f8_to_f64(read_u16(reader)?)).ok_or_else(corrupted),
+        _ => {
+            if count > MAX_ENTRIES {
+                return Err(corrupted());
+            }
+            let mut table = vec![0u16; count as usize];
+            read_u16_array(reader, &mut table)?;
+            ToneCurve::from_table16(&table).ok_or_else(corrupted)
+        }
+    }
+}
+
+/// Writes a curveType. Single gamma curves keep their exponent, others are written as their 16 bit table.
+pub fn write(writer: &mut dyn Write, curve: &ToneCurve) -> Result<()> {
+    if curve.parametric_type() == Some(1) {
+        // Single gamma, preserve number
+        write_u32(writer, 1)?;
+        return write_u16(writer, f64_to_u8f8(curve.segments()[0].params[0]));
+    }
+
+    write_u32(writer, curve.n_entries() as u32)?;
+    write_u16_array(writer, curve.table16())
+}
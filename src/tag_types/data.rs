@@ -0,0 +1,22 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{read_u32, write_u32, IoHandler};
+use crate::ICCData;
+
+/// Reads a dataType, the flag followed by the rest of the tag
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<ICCData> {
+    let len = size_of_tag
+        .checked_sub(4)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Corrupted data tag"))?;
+
+    let flag = read_u32(io)?;
+    let mut data = vec![0u8; len as usize];
+    io.read_exact(&mut data)?;
+
+    Ok(ICCData { flag, data })
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &ICCData) -> Result<()> {
+    write_u32(io, value.flag)?;
+    io.write_all(&value.data)
+}
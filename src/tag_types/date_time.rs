@@ -0,0 +1,13 @@
+use std::io::Result;
+
+use crate::plugin::{read_date_time, write_date_time, IoHandler};
+use crate::DateTimeNumber;
+
+/// Reads a dateTimeType
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<DateTimeNumber> {
+    read_date_time(io)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: DateTimeNumber) -> Result<()> {
+    write_date_time(io, value)
+}
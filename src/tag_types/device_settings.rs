@@ -0,0 +1,108 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{read_u32, write_u32, IoHandler};
+use crate::{DevicePlatform, DeviceSetting, DeviceSettings, Signature};
+
+fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted device settings")
+}
+
+/// Reads a count of items taking at least `min_size` bytes each, failing if they can't fit in the tag
+fn read_count(io: &mut dyn IoHandler, min_size: u32) -> Result<u32> {
+    let count = read_u32(io)?;
+    if count as u64 * min_size as u64 > io.reported_size().saturating_sub(io.tell()) as u64 {
+        return Err(corrupted());
+    }
+    Ok(count)
+}
+
+fn read_setting(io: &mut dyn IoHandler) -> Result<DeviceSetting> {
+    let id = Signature::from(read_u32(io)?);
+    let value_size = read_u32(io)?;
+    let count = read_u32(io)?;
+
+    let size = value_size.checked_mul(count).ok_or_else(corrupted)?;
+    if size > io.reported_size().saturating_sub(io.tell()) {
+        return Err(corrupted());
+    }
+    let mut data = vec![0u8; size as usize];
+    io.read_exact(&mut data)?;
+
+    Ok(DeviceSetting {
+        id,
+        value_size,
+        data,
+    })
+}
+
+/// Reads a deviceSettingsType. Sizes of platforms and combinations aren't checked, their contents are read in
+/// sequence.
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<DeviceSettings> {
+    let platform_count = read_count(io, 12)?;
+
+    let mut platforms = Vec::with_capacity(platform_count as usize);
+    for _ in 0..platform_count {
+        let platform = Signature::from(read_u32(io)?);
+        read_u32(io)?; // Size of the platform entry
+        let combination_count = read_count(io, 8)?;
+
+        let mut combinations = Vec::with_capacity(combination_count as usize);
+        for _ in 0..combination_count {
+            read_u32(io)?; // Size of the combination
+            let setting_count = read_count(io, 12)?;
+
+            let mut settings = Vec::with_capacity(setting_count as usize);
+            for _ in 0..setting_count {
+                settings.push(read_setting(io)?);
+            }
+            combinations.push(settings);
+        }
+        platforms.push(DevicePlatform {
+            platform,
+            combinations,
+        });
+    }
+    Ok(DeviceSettings { platforms })
+}
+
+/// Fills the size field at `size_pos` with the size of the structure written since `start`
+fn write_size(io: &mut dyn IoHandler, size_pos: u32, start: u32) -> Result<()> {
+    let end = io.tell();
+    io.seek(size_pos)?;
+    write_u32(io, end - start)?;
+    io.seek(end)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &DeviceSettings) -> Result<()> {
+    write_u32(io, value.platforms.len() as u32)?;
+
+    for platform in &value.platforms {
+        let platform_start = io.tell();
+        write_u32(io, u32::from(platform.platform))?;
+        write_u32(io, 0)?; // Filled once known
+        write_u32(io, platform.combinations.len() as u32)?;
+
+        for combination in &platform.combinations {
+            let combination_start = io.tell();
+            write_u32(io, 0)?; // Filled once known
+            write_u32(io, combination.len() as u32)?;
+
+            for setting in combination {
+                if setting.value_size == 0 || setting.data.len() % setting.value_size as usize != 0
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Device setting data doesn't hold whole values",
+                    ));
+                }
+                write_u32(io, u32::from(setting.id))?;
+                write_u32(io, setting.value_size)?;
+                write_u32(io, setting.data.len() as u32 / setting.value_size)?;
+                io.write_all(&setting.data)?;
+            }
+            write_size(io, combination_start, combination_start)?;
+        }
+        write_size(io, platform_start + 4, platform_start)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::{mluc, TagIo};
+use crate::plugin::{read_u16, read_u32, write_u16_array, write_u32, IoHandler};
+use crate::signatures::tag_type;
+use crate::{Dict, Mlu, Signature};
+
+/// Record lengths, depending on whether entries have display names and display values
+const RECORD_LEN_PLAIN: u32 = 16;
+const RECORD_LEN_DISPLAY_NAME: u32 = 24;
+const RECORD_LEN_DISPLAY_VALUE: u32 = 32;
+
+fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted dictionary")
+}
+
+/// Reads a UTF-16 string, `None` if the offset is zero
+fn read_string(io: &mut dyn IoHandler, offset: u32, size: u32) -> Result<Option<String>> {
+    if offset == 0 {
+        return Ok(None);
+    }
+
+    if size > io.reported_size().saturating_sub(offset) {
+        return Err(corrupted());
+    }
+
+    io.seek(offset)?;
+    let mut text = vec![0u16; (size / 2) as usize];
+    for item in text.iter_mut() {
+        *item = read_u16(io)?;
+    }
+    Ok(Some(String::from_utf16_lossy(&text)))
+}
+
+/// Reads a localized display string, `None` if the offset or size is zero. Little CMS stores these without the
+/// type base, so it is optional here.
+fn read_display(io: &mut dyn IoHandler, offset: u32, size: u32) -> Result<Option<Mlu>> {
+    if offset == 0 || size == 0 {
+        return Ok(None);
+    }
+
+    io.seek(offset)?;
+    let mut signature = [0u8; 4];
+    io.read_exact(&mut signature)?;
+
+    // String offsets are relative to where the type base is, or would be
+    let (start, size) = if Signature::from(&signature) == tag_type::MULTI_LOCALIZED_UNICODE {
+        (offset, size.checked_sub(8).ok_or_else(corrupted)?)
+    } else {
+        (offset.checked_sub(8).ok_or_else(corrupted)?, size)
+    };
+    let tag_size = size.checked_add(8).ok_or_else(corrupted)?;
+    if start as u64 + tag_size as u64 > io.reported_size() as u64 {
+        return Err(corrupted());
+    }
+    let mut io = TagIo::new(io, start, tag_size)?;
+    io.seek(8)?;
+    Ok(Some(mluc::read(&mut io, size)?))
+}
+
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<Dict> {
+    let count = read_u32(io)?;
+    let record_len = read_u32(io)?;
+
+    if ![
+        RECORD_LEN_PLAIN,
+        RECORD_LEN_DISPLAY_NAME,
+        RECORD_LEN_DISPLAY_VALUE,
+    ]
+    .contains(&record_len)
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unknown dictionary record length '{}'", record_len),
+        ));
+    }
+    if count as u64 * record_len as u64 > io.reported_size().saturating_sub(io.tell()) as u64 {
+        return Err(corrupted());
+    }
+
+    // Offset and size of the name, value, display name and display value of each entry
+    let mut records = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let mut record = [0u32; 8];
+        for item in record.iter_mut().take((record_len / 4) as usize) {
+            *item = read_u32(io)?;
+        }
+        records.push(record);
+    }
+
+    let mut result = Dict::new();
+    for record in records {
+        let name = read_string(io, record[0], record[1])?.ok_or_else(corrupted)?;
+        let value = read_string(io, record[2], record[3])?;
+        let display_name = read_display(io, record[4], record[5])?;
+        let display_value = read_display(io, record[6], record[7])?;
+
+        result.add(&name, value.as_deref(), display_name, display_value);
+    }
+    Ok(result)
+}
+
+/// Writes a UTF-16 string, returning its offset and size
+fn write_string(io: &mut dyn IoHandler, value: Option<&str>) -> Result<(u32, u32)> {
+    match value {
+        Some(text) => {
+            let before = io.tell();
+            write_u16_array(io, &text.encode_utf16().collect::<Vec<u16>>())?;
+            Ok((before, io.tell() - before))
+        }
+        None => Ok((0, 0)),
+    }
+}
+
+/// Writes a localized display string without the type base, as Little CMS does, returning its offset and size
+fn write_display(io: &mut dyn IoHandler, value: Option<&Mlu>) -> Result<(u32, u32)> {
+    match value {
+        Some(mlu) => {
+            let before = io.tell();
+            mluc::write(io, mlu)?;
+            Ok((before, io.tell() - before))
+        }
+        None => Ok((0, 0)),
+    }
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &Dict) -> Result<()> {
+    let entries = value.entries();
+    let record_len = if entries.iter().any(|e| e.display_value.is_some()) {
+        RECORD_LEN_DISPLAY_VALUE
+    } else if entries.iter().any(|e| e.display_name.is_some()) {
+        RECORD_LEN_DISPLAY_NAME
+    } else {
+        RECORD_LEN_PLAIN
+    };
+
+    write_u32(io, entries.len() as u32)?;
+    write_u32(io, record_len)?;
+
+    // Write a fake directory to be filled later on
+    let directory_pos = io.tell();
+    for _ in 0..entries.len() as u32 * record_len / 4 {
+        write_u32(io, 0)?;
+    }
+
+    let mut records = Vec::with_capacity(entries.len());
+    for entry in entries {
+        records.push([
+            write_string(io, Some(&entry.name))?,
+            write_string(io, entry.value.as_deref())?,
+            write_display(io, entry.display_name.as_ref())?,
+            write_display(io, entry.display_value.as_ref())?,
+        ]);
+    }
+
+    let current_pos = io.tell();
+    io.seek(directory_pos)?;
+    for record in records {
+        for (offset, size) in record.iter().take((record_len / 8) as usize) {
+            write_u32(io, *offset)?;
+            write_u32(io, *size)?;
+        }
+    }
+    io.seek(current_pos)
+}
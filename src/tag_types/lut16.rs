@@ -0,0 +1,297 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use crate::pipeline::{Stage, StageData, StageLoc};
+use crate::plugin::{
+    f64_to_s15f16, read_s15f16, read_u16, read_u16_array, read_u8, s15f16_to_f64, write_s15f16,
+    write_u16, write_u16_array, write_u8, InterpParams, Mat3,
+};
+use crate::{Pipeline, ToneCurve, MAX_CHANNELS};
+
+/// Largest number of entries of the curves of a lut16
+const MAX_ENTRIES: u16 = 0x7fff;
+
+pub(super) fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted LUT tag")
+}
+
+pub(super) fn unsuitable(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+/// Reads the channel counts and CLUT grid points shared by lut8 and lut16
+pub(super) fn read_layout(reader: &mut dyn Read) -> Result<(usize, usize, u32)> {
+    let input_channels = read_u8(reader)? as usize;
+    let output_channels = read_u8(reader)? as usize;
+    let clut_points = read_u8(reader)? as u32;
+    read_u8(reader)?; // Padding
+
+    // 0 means no CLUT, and there are at least 2 points otherwise
+    if clut_points == 1 {
+        return Err(corrupted());
+    }
+    if input_channels == 0 || input_channels > MAX_CHANNELS {
+        return Err(corrupted());
+    }
+    if output_channels == 0 || output_channels > MAX_CHANNELS {
+        return Err(corrupted());
+    }
+
+    Ok((input_channels, output_channels, clut_points))
+}
+
+/// Reads the matrix, which only takes part of the pipeline if the input has 3 channels and isn't the identity
+pub(super) fn read_matrix(reader: &mut dyn Read, pipeline: &mut Pipeline) -> Result<()> {
+    let mut matrix = [0f64; 9];
+    for value in matrix.iter_mut() {
+        *value = s15f16_to_f64(read_s15f16(reader)?);
+    }
+
+    if pipeline.input_channels() == 3 && !Mat3::from(matrix).is_identity() {
+        insert(pipeline, Stage::new_matrix(3, 3, &matrix, None))?;
+    }
+    Ok(())
+}
+
+/// Number of entries of a CLUT, or zero if there is none
+pub(super) fn clut_size(
+    output_channels: usize,
+    clut_points: u32,
+    input_channels: usize,
+) -> Result<usize> {
+    if clut_points == 0 {
+        return Ok(0);
+    }
+    (0..input_channels)
+        .try_fold(output_channels, |size, _| {
+            size.checked_mul(clut_points as usize)
+        })
+        .ok_or_else(corrupted)
+}
+
+/// Checks a CLUT of `size` samples of `sample_size` bytes fits in the tag, `consumed` bytes of `size_of_tag`
+/// being already read
+pub(super) fn check_clut_fits(
+    size: usize,
+    sample_size: usize,
+    consumed: usize,
+    size_of_tag: u32,
+) -> Result<()> {
+    let remaining = (size_of_tag as u64).saturating_sub(consumed as u64);
+    match (size as u64).checked_mul(sample_size as u64) {
+        Some(bytes) if bytes <= remaining => Ok(()),
+        _ => Err(corrupted()),
+    }
+}
+
+pub(super) fn insert(pipeline: &mut Pipeline, stage: Option<Stage>) -> Result<()> {
+    let stage = stage.ok_or_else(corrupted)?;
+    if pipeline.insert_stage(StageLoc::AtEnd, stage) {
+        Ok(())
+    } else {
+        Err(corrupted())
+    }
+}
+
+/// Pieces of a pipeline laid out as a lut8 or lut16 stores them
+pub(super) struct LutParts<'a> {
+    pub matrix: Option<&'a [f64]>,
+    pub pre: Option<&'a [ToneCurve]>,
+    pub clut: Option<&'a InterpParams<u16>>,
+    pub post: Option<&'a [ToneCurve]>,
+}
+
+impl<'a> LutParts<'a> {
+    /// Takes the pipeline apart. It may only hold a matrix, curves, a 16 bit CLUT and curves, in that order.
+    pub fn split(pipeline: &'a Pipeline, type_name: &str) -> Result<Self> {
+        let mut stages = pipeline.stages().iter().peekable();
+        let mut result = LutParts {
+            matrix: None,
+            pre: None,
+            clut: None,
+            post: None,
+        };
+
+        if let Some(StageData::Matrix { matrix, offset }) = stages.peek().map(|s| s.data()) {
+            if matrix.len() != 9 || offset.as_ref().is_some_and(|o| o.iter().any(|v| *v != 0.0)) {
+                return Err(unsuitable(&format!(
+                    "Matrix is not suitable to be saved as {}",
+                    type_name
+                )));
+            }
+            result.matrix = Some(matrix);
+            stages.next();
+        }
+        if let Some(StageData::Curves(curves)) = stages.peek().map(|s| s.data()) {
+            result.pre = Some(curves);
+            stages.next();
+        }
+        match stages.peek().map(|s| s.data()) {
+            Some(StageData::CLut16(params)) => {
+                result.clut = Some(params);
+                stages.next();
+            }
+            Some(StageData::CLutFloat(_)) => {
+                return Err(unsuitable(&format!(
+                    "Float CLUT is not suitable to be saved as {}",
+                    type_name
+                )));
+            }
+            _ => {}
+        }
+        if let Some(StageData::Curves(curves)) = stages.peek().map(|s| s.data()) {
+            result.post = Some(curves);
+            stages.next();
+        }
+
+        // That should be all
+        if stages.next().is_some() {
+            return Err(unsuitable(&format!(
+                "LUT is not suitable to be saved as {}",
+                type_name
+            )));
+        }
+
+        Ok(result)
+    }
+
+    /// Grid points of the CLUT, which must be the same on every input
+    pub fn clut_points(&self, type_name: &str) -> Result<u32> {
+        match self.clut {
+            None => Ok(0),
+            Some(clut) => {
+                let points = clut.n_samples[0];
+                if clut.n_samples.iter().any(|n| *n != points) || points > 0xff {
+                    return Err(unsuitable(&format!(
+                        "LUT with different samples per dimension not suitable to be saved as {}",
+                        type_name
+                    )));
+                }
+                Ok(points)
+            }
+        }
+    }
+
+    pub fn write_matrix(&self, writer: &mut dyn Write) -> Result<()> {
+        let identity: [f64; 9] = Mat3::IDENTITY.into();
+        let matrix = self.matrix.unwrap_or(&identity);
+
+        for value in matrix {
+            write_s15f16(writer, f64_to_s15f16(*value))?;
+        }
+        Ok(())
+    }
+}
+
+fn read_16bit_tables(
+    reader: &mut dyn Read,
+    pipeline: &mut Pipeline,
+    n_channels: usize,
+    n_entries: u16,
+) -> Result<()> {
+    // No tables at all
+    if n_entries == 0 {
+        return Ok(());
+    }
+
+    let mut curves = Vec::with_capacity(n_channels);
+    let mut table = vec![0u16; n_entries as usize];
+    for _ in 0..n_channels {
+        read_u16_array(reader, &mut table)?;
+        curves.push(ToneCurve::from_table16(&table).ok_or_else(corrupted)?);
+    }
+
+    insert(pipeline, Stage::new_tone_curves(curves))
+}
+
+/// Reads a lut16Type into a pipeline of an optional matrix, input curves, CLUT and output curves
+pub fn read(reader: &mut dyn Read, size_of_tag: u32) -> Result<Pipeline> {
+    let (input_channels, output_channels, clut_points) = read_layout(reader)?;
+    let mut result = Pipeline::new(input_channels, output_channels).ok_or_else(corrupted)?;
+
+    read_matrix(reader, &mut result)?;
+
+    let input_entries = read_u16(reader)?;
+    let output_entries = read_u16(reader)?;
+    if input_entries > MAX_ENTRIES || output_entries > MAX_ENTRIES {
+        return Err(corrupted());
+    }
+
+    read_16bit_tables(reader, &mut result, input_channels, input_entries)?;
+
+    let size = clut_size(output_channels, clut_points, input_channels)?;
+    if size > 0 {
+        let consumed = 44 + input_channels * input_entries as usize * 2;
+        check_clut_fits(size, 2, consumed, size_of_tag)?;
+
+        let mut table = vec![0u16; size];
+        read_u16_array(reader, &mut table)?;
+        insert(
+            &mut result,
+            Stage::new_clut16(clut_points, input_channels, output_channels, Some(&table)),
+        )?;
+    }
+
+    read_16bit_tables(reader, &mut result, output_channels, output_entries)?;
+
+    Ok(result)
+}
+
+fn entries(curves: Option<&[ToneCurve]>) -> Result<u16> {
+    match curves {
+        None => Ok(2),
+        Some(curves) => {
+            let n = curves[0].n_entries();
+            if curves.iter().any(|c| c.n_entries() != n) || n > MAX_ENTRIES as usize {
+                return Err(unsuitable(
+                    "Curves of a lut16 must have the same number of entries",
+                ));
+            }
+            Ok(n as u16)
+        }
+    }
+}
+
+fn write_16bit_tables(
+    writer: &mut dyn Write,
+    curves: Option<&[ToneCurve]>,
+    n_channels: usize,
+) -> Result<()> {
+    match curves {
+        Some(curves) => {
+            for curve in curves {
+                write_u16_array(writer, curve.table16())?;
+            }
+        }
+        // Linear tables
+        None => {
+            for _ in 0..n_channels {
+                write_u16_array(writer, &[0, 0xffff])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a pipeline as a lut16Type
+pub fn write(writer: &mut dyn Write, pipeline: &Pipeline) -> Result<()> {
+    let parts = LutParts::split(pipeline, "LUT16")?;
+    let clut_points = parts.clut_points("LUT16")?;
+    let input_entries = entries(parts.pre)?;
+    let output_entries = entries(parts.post)?;
+
+    write_u8(writer, pipeline.input_channels() as u8)?;
+    write_u8(writer, pipeline.output_channels() as u8)?;
+    write_u8(writer, clut_points as u8)?;
+    write_u8(writer, 0)?; // Padding
+
+    parts.write_matrix(writer)?;
+
+    write_u16(writer, input_entries)?;
+    write_u16(writer, output_entries)?;
+
+    write_16bit_tables(writer, parts.pre, pipeline.input_channels())?;
+    if let Some(clut) = parts.clut {
+        write_u16_array(writer, &clut.table)?;
+    }
+    write_16bit_tables(writer, parts.post, pipeline.output_channels())
+}
@@ -0,0 +1,105 @@
+use std::io::{Read, Result, Write};
+
+use super::lut16::{
+    check_clut_fits, clut_size, corrupted, insert, read_layout, read_matrix, unsuitable, LutParts,
+};
+use crate::internal::{from_16_to_8, from_8_to_16};
+use crate::pipeline::Stage;
+use crate::plugin::write_u8;
+use crate::{Pipeline, ToneCurve};
+
+/// Number of entries of each lut8 curve
+const N_ENTRIES: usize = 256;
+
+fn read_8bit_tables(
+    reader: &mut dyn Read,
+    pipeline: &mut Pipeline,
+    n_channels: usize,
+) -> Result<()> {
+    let mut curves = Vec::with_capacity(n_channels);
+    let mut buf = [0u8; N_ENTRIES];
+
+    for _ in 0..n_channels {
+        reader.read_exact(&mut buf)?;
+        let table = buf.iter().map(|v| from_8_to_16(*v)).collect::<Vec<_>>();
+        curves.push(ToneCurve::from_table16(&table).ok_or_else(corrupted)?);
+    }
+
+    insert(pipeline, Stage::new_tone_curves(curves))
+}
+
+/// Reads a lut8Type into a pipeline of an optional matrix, input curves, CLUT and output curves
+pub fn read(reader: &mut dyn Read, size_of_tag: u32) -> Result<Pipeline> {
+    let (input_channels, output_channels, clut_points) = read_layout(reader)?;
+    let mut result = Pipeline::new(input_channels, output_channels).ok_or_else(corrupted)?;
+
+    read_matrix(reader, &mut result)?;
+    read_8bit_tables(reader, &mut result, input_channels)?;
+
+    let size = clut_size(output_channels, clut_points, input_channels)?;
+    if size > 0 {
+        let consumed = 40 + input_channels * N_ENTRIES;
+        check_clut_fits(size, 1, consumed, size_of_tag)?;
+
+        let mut temp = vec![0u8; size];
+        reader.read_exact(&mut temp)?;
+        let table = temp.iter().map(|v| from_8_to_16(*v)).collect::<Vec<_>>();
+        insert(
+            &mut result,
+            Stage::new_clut16(clut_points, input_channels, output_channels, Some(&table)),
+        )?;
+    }
+
+    read_8bit_tables(reader, &mut result, output_channels)?;
+
+    Ok(result)
+}
+
+fn write_8bit_tables(
+    writer: &mut dyn Write,
+    curves: Option<&[ToneCurve]>,
+    n_channels: usize,
+) -> Result<()> {
+    for i in 0..n_channels {
+        let table = curves.map(|c| c[i].table16());
+        match table {
+            // Linear curves are expanded
+            None | Some([0, 0xffff]) => {
+                for j in 0..N_ENTRIES {
+                    write_u8(writer, j as u8)?;
+                }
+            }
+            Some(table) if table.len() == N_ENTRIES => {
+                for value in table {
+                    write_u8(writer, from_16_to_8(*value))?;
+                }
+            }
+            Some(_) => return Err(unsuitable("LUT8 needs 256 entries on prelinearization")),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a pipeline as a lut8Type. Curves must have 256 entries, or be linear.
+pub fn write(writer: &mut dyn Write, pipeline: &Pipeline) -> Result<()> {
+    let parts = LutParts::split(pipeline, "LUT8")?;
+    let clut_points = parts.clut_points("LUT8")?;
+
+    write_u8(writer, pipeline.input_channels() as u8)?;
+    write_u8(writer, pipeline.output_channels() as u8)?;
+    write_u8(writer, clut_points as u8)?;
+    write_u8(writer, 0)?; // Padding
+
+    parts.write_matrix(writer)?;
+
+    write_8bit_tables(writer, parts.pre, pipeline.input_channels())?;
+    if let Some(clut) = parts.clut {
+        let table = clut
+            .table
+            .iter()
+            .map(|v| from_16_to_8(*v))
+            .collect::<Vec<_>>();
+        writer.write_all(&table)?;
+    }
+    write_8bit_tables(writer, parts.post, pipeline.output_channels())
+}
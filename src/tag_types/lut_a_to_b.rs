@@ -0,0 +1,388 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::{curve, parametric_curve};
+use crate::internal::{from_16_to_8, from_8_to_16};
+use crate::pipeline::{Stage, StageData, StageLoc};
+use crate::plugin::{
+    f64_to_s15f16, read_alignment, read_s15f16, read_u16, read_u16_array, read_u32, read_u8,
+    s15f16_to_f64, write_alignment, write_s15f16, write_u16, write_u16_array, write_u32, write_u8,
+    IoHandler, TagBase,
+};
+use crate::signatures::{stage, tag_type};
+use crate::{Context, Pipeline, ToneCurve, MAX_CHANNELS};
+
+pub(super) fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted LUT tag")
+}
+
+fn unsuitable(type_name: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("LUT is not suitable to be saved as {}", type_name),
+    )
+}
+
+/// Offsets of the elements, relative to the start of the tag. Zero means the element is missing.
+#[derive(Default)]
+pub(super) struct Directory {
+    pub b: u32,
+    pub matrix: u32,
+    pub m: u32,
+    pub clut: u32,
+    pub a: u32,
+}
+
+/// Reads the channel counts and the directory shared by lutAtoB and lutBtoA
+pub(super) fn read_header(io: &mut dyn IoHandler) -> Result<(usize, usize, Directory)> {
+    let input_channels = read_u8(io)? as usize;
+    let output_channels = read_u8(io)? as usize;
+    read_u16(io)?; // Padding
+
+    let directory = Directory {
+        b: read_u32(io)?,
+        matrix: read_u32(io)?,
+        m: read_u32(io)?,
+        clut: read_u32(io)?,
+        a: read_u32(io)?,
+    };
+
+    if input_channels == 0 || input_channels >= MAX_CHANNELS {
+        return Err(corrupted());
+    }
+    if output_channels == 0 || output_channels >= MAX_CHANNELS {
+        return Err(corrupted());
+    }
+
+    Ok((input_channels, output_channels, directory))
+}
+
+pub(super) fn insert(pipeline: &mut Pipeline, stage: Stage) -> Result<()> {
+    if pipeline.insert_stage(StageLoc::AtEnd, stage) {
+        Ok(())
+    } else {
+        Err(corrupted())
+    }
+}
+
+/// Reads a curve along with its type base. Either curveType or parametricCurveType.
+fn read_embedded_curve(context: &Context, io: &mut dyn IoHandler) -> Result<ToneCurve> {
+    let base = TagBase::read(io)?;
+
+    match base.signature {
+        tag_type::CURVE => curve::read_with_context(context, io, 0),
+        tag_type::PARAMETRIC_CURVE => parametric_curve::read_with_context(context, io, 0),
+        other => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unknown curve type '{}'", String::from(other)),
+        )),
+    }
+}
+
+pub(super) fn read_set_of_curves(
+    context: &Context,
+    io: &mut dyn IoHandler,
+    offset: u32,
+    n_curves: usize,
+) -> Result<Stage> {
+    if n_curves > MAX_CHANNELS {
+        return Err(corrupted());
+    }
+    io.seek(offset)?;
+
+    let mut curves = Vec::with_capacity(n_curves);
+    for _ in 0..n_curves {
+        curves.push(read_embedded_curve(context, io)?);
+        read_alignment(io)?;
+    }
+
+    Stage::new_tone_curves(curves).ok_or_else(corrupted)
+}
+
+pub(super) fn read_matrix(io: &mut dyn IoHandler, offset: u32) -> Result<Stage> {
+    io.seek(offset)?;
+
+    let mut matrix = [0f64; 9];
+    for value in matrix.iter_mut() {
+        *value = s15f16_to_f64(read_s15f16(io)?);
+    }
+    let mut offsets = [0f64; 3];
+    for value in offsets.iter_mut() {
+        *value = s15f16_to_f64(read_s15f16(io)?);
+    }
+
+    Stage::new_matrix(3, 3, &matrix, Some(&offsets)).ok_or_else(corrupted)
+}
+
+pub(super) fn read_clut(
+    io: &mut dyn IoHandler,
+    offset: u32,
+    input_channels: usize,
+    output_channels: usize,
+) -> Result<Stage> {
+    io.seek(offset)?;
+
+    let mut grid_points8 = [0u8; MAX_CHANNELS];
+    io.read_exact(&mut grid_points8)?;
+
+    // Impossible value, 0 for no CLUT and then 2 at least
+    if grid_points8.contains(&1) {
+        return Err(corrupted());
+    }
+    let grid_points = grid_points8[..input_channels]
+        .iter()
+        .map(|v| *v as u32)
+        .collect::<Vec<_>>();
+
+    let precision = read_u8(io)?;
+    let mut padding = [0u8; 3];
+    io.read_exact(&mut padding)?;
+
+    // Precision can be 1 or 2 bytes
+    if precision != 1 && precision != 2 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unknown precision of '{}'", precision),
+        ));
+    }
+
+    let size = grid_points
+        .iter()
+        .try_fold(output_channels, |size, n| size.checked_mul(*n as usize))
+        .filter(|size| *size > 0)
+        .ok_or_else(corrupted)?;
+    if size as u64 * precision as u64 > io.reported_size().saturating_sub(io.tell()) as u64 {
+        return Err(corrupted());
+    }
+
+    let mut table = vec![0u16; size];
+    if precision == 1 {
+        for value in table.iter_mut() {
+            *value = from_8_to_16(read_u8(io)?);
+        }
+    } else {
+        read_u16_array(io, &mut table)?;
+    }
+
+    Stage::new_clut16_granular(&grid_points, output_channels, Some(&table)).ok_or_else(corrupted)
+}
+
+/// Reads a lutAtoBType. Elements go A curves, CLUT, M curves, matrix and B curves, any of them may be missing.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Pipeline> {
+    read_with_context(&Context::new(), io, size_of_tag)
+}
+
+/// Reads a lutAtoBType, whose parametric curves may use types registered by plugins in `context`
+pub fn read_with_context(
+    context: &Context,
+    io: &mut dyn IoHandler,
+    _size_of_tag: u32,
+) -> Result<Pipeline> {
+    let (input_channels, output_channels, directory) = read_header(io)?;
+    let mut result = Pipeline::new(input_channels, output_channels).ok_or_else(corrupted)?;
+
+    if directory.a != 0 {
+        insert(
+            &mut result,
+            read_set_of_curves(context, io, directory.a, input_channels)?,
+        )?;
+    }
+    if directory.clut != 0 {
+        insert(
+            &mut result,
+            read_clut(io, directory.clut, input_channels, output_channels)?,
+        )?;
+    }
+    if directory.m != 0 {
+        insert(
+            &mut result,
+            read_set_of_curves(context, io, directory.m, output_channels)?,
+        )?;
+    }
+    if directory.matrix != 0 {
+        insert(&mut result, read_matrix(io, directory.matrix)?)?;
+    }
+    if directory.b != 0 {
+        insert(
+            &mut result,
+            read_set_of_curves(context, io, directory.b, output_channels)?,
+        )?;
+    }
+
+    Ok(result)
+}
+
+/// Writes curves with their type base. Parametric curves are kept as such, others go as curveType.
+fn write_set_of_curves(io: &mut dyn IoHandler, stage: &Stage) -> Result<()> {
+    let curves = match stage.data() {
+        StageData::Curves(curves) => curves,
+        _ => return Err(unsuitable("set of curves")),
+    };
+
+    for curve in curves {
+        if parametric_curve::fits(curve) {
+            TagBase::from(u32::from(tag_type::PARAMETRIC_CURVE)).write(io)?;
+            parametric_curve::write(io, curve)?;
+        } else {
+            TagBase::from(u32::from(tag_type::CURVE)).write(io)?;
+            curve::write(io, curve)?;
+        }
+        write_alignment(io)?;
+    }
+    Ok(())
+}
+
+fn write_matrix(io: &mut dyn IoHandler, stage: &Stage) -> Result<()> {
+    let (matrix, offset) = match stage.data() {
+        StageData::Matrix { matrix, offset } if matrix.len() == 9 => (matrix, offset),
+        _ => return Err(unsuitable("3x3 matrix")),
+    };
+
+    for value in matrix {
+        write_s15f16(io, f64_to_s15f16(*value))?;
+    }
+    match offset {
+        Some(offset) => {
+            for value in offset {
+                write_s15f16(io, f64_to_s15f16(*value))?;
+            }
+        }
+        None => {
+            for _ in 0..3 {
+                write_s15f16(io, 0)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_clut(io: &mut dyn IoHandler, precision: u8, stage: &Stage) -> Result<()> {
+    let params = match stage.data() {
+        StageData::CLut16(params) => params,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Cannot save floating point data, CLUT are 8 or 16 bit only",
+            ))
+        }
+    };
+
+    let mut grid_points = [0u8; MAX_CHANNELS];
+    for (i, n) in params.n_samples.iter().enumerate() {
+        grid_points[i] = *n as u8;
+    }
+    io.write_all(&grid_points)?;
+
+    write_u8(io, precision)?;
+    io.write_all(&[0u8; 3])?;
+
+    if precision == 1 {
+        for value in &params.table {
+            write_u8(io, from_16_to_8(*value))?;
+        }
+    } else {
+        write_u16_array(io, &params.table)?;
+    }
+
+    write_alignment(io)
+}
+
+/// Elements of a lutAtoB or lutBtoA, as found in a pipeline
+#[derive(Default)]
+pub(super) struct Elements<'a> {
+    pub a: Option<&'a Stage>,
+    pub clut: Option<&'a Stage>,
+    pub m: Option<&'a Stage>,
+    pub matrix: Option<&'a Stage>,
+    pub b: Option<&'a Stage>,
+}
+
+impl<'a> Elements<'a> {
+    /// Writes the elements after the header, then fills in the directory
+    pub fn write(&self, io: &mut dyn IoHandler, pipeline: &Pipeline) -> Result<()> {
+        write_u8(io, pipeline.input_channels() as u8)?;
+        write_u8(io, pipeline.output_channels() as u8)?;
+        write_u16(io, 0)?; // Padding
+
+        // Keep directory to be filled later
+        let directory_pos = io.tell();
+        for _ in 0..5 {
+            write_u32(io, 0)?;
+        }
+
+        let mut directory = Directory::default();
+
+        if let Some(a) = self.a {
+            directory.a = io.tell();
+            write_set_of_curves(io, a)?;
+        }
+        if let Some(clut) = self.clut {
+            directory.clut = io.tell();
+            write_clut(io, if pipeline.save_as_8_bits() { 1 } else { 2 }, clut)?;
+        }
+        if let Some(m) = self.m {
+            directory.m = io.tell();
+            write_set_of_curves(io, m)?;
+        }
+        if let Some(matrix) = self.matrix {
+            directory.matrix = io.tell();
+            write_matrix(io, matrix)?;
+        }
+        if let Some(b) = self.b {
+            directory.b = io.tell();
+            write_set_of_curves(io, b)?;
+        }
+
+        let current_pos = io.tell();
+        io.seek(directory_pos)?;
+        write_u32(io, directory.b)?;
+        write_u32(io, directory.matrix)?;
+        write_u32(io, directory.m)?;
+        write_u32(io, directory.clut)?;
+        write_u32(io, directory.a)?;
+        io.seek(current_pos)
+    }
+}
+
+/// Writes a pipeline as a lutAtoBType. It must be made of B curves; M curves, matrix and B curves; A curves, CLUT
+/// and B curves; or all of them.
+pub fn write(io: &mut dyn IoHandler, pipeline: &Pipeline) -> Result<()> {
+    use stage::{
+        CURVE_SET_ELEM_TYPE as CURVES, C_LUT_ELEM_TYPE as CLUT, MATRIX_ELEM_TYPE as MATRIX,
+    };
+
+    let s = pipeline.stages();
+    let elements = if s.is_empty() {
+        Elements::default()
+    } else if pipeline.has_stage_types(&[CURVES]) {
+        Elements {
+            b: Some(&s[0]),
+            ..Default::default()
+        }
+    } else if pipeline.has_stage_types(&[CURVES, MATRIX, CURVES]) {
+        Elements {
+            m: Some(&s[0]),
+            matrix: Some(&s[1]),
+            b: Some(&s[2]),
+            ..Default::default()
+        }
+    } else if pipeline.has_stage_types(&[CURVES, CLUT, CURVES]) {
+        Elements {
+            a: Some(&s[0]),
+            clut: Some(&s[1]),
+            b: Some(&s[2]),
+            ..Default::default()
+        }
+    } else if pipeline.has_stage_types(&[CURVES, CLUT, CURVES, MATRIX, CURVES]) {
+        Elements {
+            a: Some(&s[0]),
+            clut: Some(&s[1]),
+            m: Some(&s[2]),
+            matrix: Some(&s[3]),
+            b: Some(&s[4]),
+        }
+    } else {
+        return Err(unsuitable("LutAToB"));
+    };
+
+    elements.write(io, pipeline)
+}
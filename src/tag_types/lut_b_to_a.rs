@@ -0,0 +1,100 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::lut_a_to_b::{
+    corrupted, insert, read_clut, read_header, read_matrix, read_set_of_curves, Elements,
+};
+use crate::plugin::IoHandler;
+use crate::signatures::stage;
+use crate::{Context, Pipeline};
+
+/// Reads a lutBtoAType. Elements go B curves, matrix, M curves, CLUT and A curves, any of them may be missing.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Pipeline> {
+    read_with_context(&Context::new(), io, size_of_tag)
+}
+
+/// Reads a lutBtoAType, whose parametric curves may use types registered by plugins in `context`
+pub fn read_with_context(
+    context: &Context,
+    io: &mut dyn IoHandler,
+    _size_of_tag: u32,
+) -> Result<Pipeline> {
+    let (input_channels, output_channels, directory) = read_header(io)?;
+    let mut result = Pipeline::new(input_channels, output_channels).ok_or_else(corrupted)?;
+
+    if directory.b != 0 {
+        insert(
+            &mut result,
+            read_set_of_curves(context, io, directory.b, input_channels)?,
+        )?;
+    }
+    if directory.matrix != 0 {
+        insert(&mut result, read_matrix(io, directory.matrix)?)?;
+    }
+    if directory.m != 0 {
+        insert(
+            &mut result,
+            read_set_of_curves(context, io, directory.m, input_channels)?,
+        )?;
+    }
+    if directory.clut != 0 {
+        insert(
+            &mut result,
+            read_clut(io, directory.clut, input_channels, output_channels)?,
+        )?;
+    }
+    if directory.a != 0 {
+        insert(
+            &mut result,
+            read_set_of_curves(context, io, directory.a, output_channels)?,
+        )?;
+    }
+
+    Ok(result)
+}
+
+/// Writes a pipeline as a lutBtoAType. It must be made of B curves; B curves, matrix and M curves; B curves, CLUT
+/// and A curves; or all of them.
+pub fn write(io: &mut dyn IoHandler, pipeline: &Pipeline) -> Result<()> {
+    use stage::{
+        CURVE_SET_ELEM_TYPE as CURVES, C_LUT_ELEM_TYPE as CLUT, MATRIX_ELEM_TYPE as MATRIX,
+    };
+
+    let s = pipeline.stages();
+    let elements = if s.is_empty() {
+        Elements::default()
+    } else if pipeline.has_stage_types(&[CURVES]) {
+        Elements {
+            b: Some(&s[0]),
+            ..Default::default()
+        }
+    } else if pipeline.has_stage_types(&[CURVES, MATRIX, CURVES]) {
+        Elements {
+            b: Some(&s[0]),
+            matrix: Some(&s[1]),
+            m: Some(&s[2]),
+            ..Default::default()
+        }
+    } else if pipeline.has_stage_types(&[CURVES, CLUT, CURVES]) {
+        Elements {
+            b: Some(&s[0]),
+            clut: Some(&s[1]),
+            a: Some(&s[2]),
+            ..Default::default()
+        }
+    } else if pipeline.has_stage_types(&[CURVES, MATRIX, CURVES, CLUT, CURVES]) {
+        Elements {
+            b: Some(&s[0]),
+            matrix: Some(&s[1]),
+            m: Some(&s[2]),
+            clut: Some(&s[3]),
+            a: Some(&s[4]),
+        }
+    } else {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "LUT is not suitable to be saved as LutBToA",
+        ));
+    };
+
+    elements.write(io, pipeline)
+}
@@ -0,0 +1,26 @@
+use std::io::Result;
+
+use crate::plugin::{
+    f64_to_s15f16, read_s15f16, read_u32, read_xyz, s15f16_to_f64, write_s15f16, write_u32,
+    write_xyz, IoHandler,
+};
+use crate::ICCMeasurementConditions;
+
+/// Reads a measurementType
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<ICCMeasurementConditions> {
+    Ok(ICCMeasurementConditions {
+        observer: read_u32(io)?.into(),
+        backing: read_xyz(io)?,
+        geometry: read_u32(io)?.into(),
+        flare: s15f16_to_f64(read_s15f16(io)?),
+        illuminant_type: read_u32(io)?.into(),
+    })
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &ICCMeasurementConditions) -> Result<()> {
+    write_u32(io, value.observer.into())?;
+    write_xyz(io, value.backing)?;
+    write_u32(io, value.geometry.into())?;
+    write_s15f16(io, f64_to_s15f16(value.flare))?;
+    write_u32(io, value.illuminant_type.into())
+}
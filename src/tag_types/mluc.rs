@@ -0,0 +1,86 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{read_u16, read_u32, write_u16_array, write_u32, IoHandler};
+use crate::Mlu;
+
+/// Size of each record in the directory of the tag
+const RECORD_SIZE: u32 = 12;
+
+/// Reads a multiLocalizedUnicodeType. String offsets are relative to the start of the tag, so `io` positions
+/// must be too.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Mlu> {
+    let count = read_u32(io)?;
+    let record_len = read_u32(io)?;
+
+    if record_len != RECORD_SIZE {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "multiLocalizedUnicodeType of len != 12 is not supported.",
+        ));
+    }
+
+    let size_of_header = count
+        .checked_mul(RECORD_SIZE)
+        .and_then(|v| v.checked_add(16))
+        .ok_or_else(corrupted)?;
+    if size_of_header > size_of_tag + 8 {
+        return Err(corrupted());
+    }
+
+    let mut records = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let language = read_u16(io)?.to_be_bytes();
+        let country = read_u16(io)?.to_be_bytes();
+        let len = read_u32(io)?;
+        let offset = read_u32(io)?;
+
+        // Check for overflow
+        let end = offset.checked_add(len).ok_or_else(corrupted)?;
+        if offset < size_of_header || end > size_of_tag + 8 {
+            return Err(corrupted());
+        }
+        records.push((language, country, len, offset));
+    }
+
+    let mut result = Mlu::new();
+    for (language, country, len, offset) in records {
+        io.seek(offset)?;
+
+        let mut text = vec![0u16; (len / 2) as usize];
+        for item in text.iter_mut() {
+            *item = read_u16(io)?;
+        }
+        result.push_entry(language, country, text);
+    }
+    Ok(result)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &Mlu) -> Result<()> {
+    let count = value.len() as u32;
+    write_u32(io, count)?;
+    write_u32(io, RECORD_SIZE)?;
+
+    let mut offset = 16 + RECORD_SIZE * count;
+    for (language, country, text) in value.entries() {
+        let len = (text.len() * 2) as u32;
+
+        io.write_all(&language)?;
+        io.write_all(&country)?;
+        write_u32(io, len)?;
+        write_u32(io, offset)?;
+
+        offset += len;
+    }
+
+    for (_, _, text) in value.entries() {
+        write_u16_array(io, text)?;
+    }
+    Ok(())
+}
+
+fn corrupted() -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        "Corrupted multiLocalizedUnicodeType",
+    )
+}
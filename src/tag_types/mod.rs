@@ -0,0 +1,490 @@
+// Tag type handlers only deal with the payload of a tag. The type signature
+// and reserved bytes in front of it are handled by `plugin::TagBase`. Offsets
+// stored inside tags are relative to the start of the tag, so handlers expect
+// `IoHandler` positions to be relative to it as well, which `plugin::TagIo`
+// takes care of.
+
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{read_u32, write_u32, CustomTag, IoHandler, TagBase, TagIo};
+use crate::signatures::tag_type;
+use crate::{
+    Chromaticity, Context, DateTimeNumber, DeviceSettings, Dict, ICCData, ICCMeasurementConditions,
+    ICCViewingConditions, Mlu, NamedColorList, Pipeline, ProfileSequenceDesc, ResponseCurveSet,
+    Screening, Signature, ToneCurve, UcrBg, CIEXYZ,
+};
+
+pub mod chromaticity;
+pub mod colorant_order;
+pub mod colorant_table;
+pub mod crd_info;
+pub mod curve;
+pub mod data;
+pub mod date_time;
+pub mod device_settings;
+pub mod dict;
+pub mod lut16;
+pub mod lut8;
+pub mod lut_a_to_b;
+pub mod lut_b_to_a;
+pub mod measurement;
+pub mod mluc;
+pub mod multi_process_element;
+pub mod named_color2;
+pub mod parametric_curve;
+pub mod profile_sequence_desc;
+pub mod profile_sequence_id;
+pub mod response_curve_set16;
+pub mod s15_fixed16_array;
+pub mod screening;
+pub mod signature;
+pub mod text;
+pub mod text_description;
+pub mod u16_fixed16_array;
+pub mod ucr_bg;
+pub mod uint16_array;
+pub mod uint32_array;
+pub mod uint64_array;
+pub mod uint8_array;
+pub mod vcgt;
+pub mod viewing_conditions;
+pub mod xyz;
+
+mod tags;
+pub use tags::{tag_descriptor, TagDescriptor};
+
+#[cfg(test)]
+mod tests;
+
+/// Decoded contents of a tag
+#[derive(Clone, PartialEq, Debug)]
+pub enum TagData {
+    /// `XYZ ` type
+    Xyz(Vec<CIEXYZ>),
+    /// `mluc`, `desc`, `text` and `crdi` types
+    Mlu(Mlu),
+    /// `curv` and `para` types
+    ToneCurve(ToneCurve),
+    /// `mft1`, `mft2`, `mAB `, `mBA ` and `mpet` types
+    Pipeline(Pipeline),
+    /// `ncl2` and `clrt` types
+    NamedColorList(NamedColorList),
+    /// `pseq` and `psid` types
+    ProfileSequence(Vec<ProfileSequenceDesc>),
+    /// `sig ` type
+    Signature(Signature),
+    /// `dict` type
+    Dict(Dict),
+    /// `vcgt` type, with the red, green and blue curves
+    Vcgt([ToneCurve; 3]),
+    /// `meas` type
+    Measurement(ICCMeasurementConditions),
+    /// `view` type
+    ViewingConditions(ICCViewingConditions),
+    /// `chrm` type
+    Chromaticity(Chromaticity),
+    /// `clro` type, the channel of each colorant in laydown order
+    ColorantOrder(Vec<u8>),
+    /// `sf32` type
+    S15Fixed16Array(Vec<f64>),
+    /// `uf32` type
+    U16Fixed16Array(Vec<f64>),
+    /// `ui08` type
+    UInt8Array(Vec<u8>),
+    /// `ui16` type
+    UInt16Array(Vec<u16>),
+    /// `ui32` type
+    UInt32Array(Vec<u32>),
+    /// `ui64` type
+    UInt64Array(Vec<u64>),
+    /// `data` type
+    Data(ICCData),
+    /// `dtim` type
+    DateTime(DateTimeNumber),
+    /// `scrn` type
+    Screening(Screening),
+    /// `bfd ` type
+    UcrBg(UcrBg),
+    /// `devs` type
+    DeviceSettings(DeviceSettings),
+    /// `rcs2` type
+    ResponseCurveSet(ResponseCurveSet),
+    /// Types handled by a `plugin::TagTypeHandler`
+    Custom(CustomTag),
+}
+
+impl TagData {
+    /// Number of elements held, checked against `TagDescriptor::element_count`
+    pub fn element_count(&self) -> usize {
+        match self {
+            TagData::Xyz(values) => values.len(),
+            TagData::S15Fixed16Array(values) | TagData::U16Fixed16Array(values) => values.len(),
+            TagData::UInt8Array(values) => values.len(),
+            TagData::UInt16Array(values) => values.len(),
+            TagData::UInt32Array(values) => values.len(),
+            TagData::UInt64Array(values) => values.len(),
+            _ => 1,
+        }
+    }
+
+    /// Whether this can be written as the given tag type
+    pub fn fits_type(&self, type_sig: Signature) -> bool {
+        match self {
+            TagData::Xyz(_) => type_sig == tag_type::XYZ,
+            TagData::Mlu(_) => {
+                type_sig == tag_type::MULTI_LOCALIZED_UNICODE
+                    || type_sig == tag_type::TEXT_DESCRIPTION
+                    || type_sig == tag_type::TEXT
+                    || type_sig == tag_type::CRD_INFO
+            }
+            TagData::ToneCurve(curve) => {
+                type_sig == tag_type::CURVE
+                    || (type_sig == tag_type::PARAMETRIC_CURVE && parametric_curve::fits(curve))
+            }
+            TagData::Pipeline(_) => {
+                type_sig == tag_type::LUT16
+                    || type_sig == tag_type::LUT8
+                    || type_sig == tag_type::LUTA_TO_B
+                    || type_sig == tag_type::LUTB_TO_A
+                    || type_sig == tag_type::MULTI_PROCESS_ELEMENT
+            }
+            TagData::NamedColorList(list) => {
+                type_sig == tag_type::NAMED_COLOR2
+                    || (type_sig == tag_type::COLORANT_TABLE && list.colorant_count() == 0)
+            }
+            TagData::ProfileSequence(_) => {
+                type_sig == tag_type::PROFILE_SEQUENCE_DESC
+                    || type_sig == tag_type::PROFILE_SEQUENCE_ID
+            }
+            TagData::Signature(_) => type_sig == tag_type::SIGNATURE,
+            TagData::Dict(_) => type_sig == tag_type::DICT,
+            TagData::Vcgt(_) => type_sig == tag_type::VCGT,
+            TagData::Measurement(_) => type_sig == tag_type::MEASUREMENT,
+            TagData::ViewingConditions(_) => type_sig == tag_type::VIEWING_CONDITIONS,
+            TagData::Chromaticity(_) => type_sig == tag_type::CHROMATICITY,
+            TagData::ColorantOrder(_) => type_sig == tag_type::COLORANT_ORDER,
+            TagData::S15Fixed16Array(_) => type_sig == tag_type::S15_FIXED16_ARRAY,
+            TagData::U16Fixed16Array(_) => type_sig == tag_type::U16_FIXED16_ARRAY,
+            TagData::UInt8Array(_) => type_sig == tag_type::UINT8_ARRAY,
+            TagData::UInt16Array(_) => type_sig == tag_type::UINT16_ARRAY,
+            TagData::UInt32Array(_) => type_sig == tag_type::UINT32_ARRAY,
+            TagData::UInt64Array(_) => type_sig == tag_type::UINT64_ARRAY,
+            TagData::Data(_) => type_sig == tag_type::DATA,
+            TagData::DateTime(_) => type_sig == tag_type::DATE_TIME,
+            TagData::Screening(_) => type_sig == tag_type::SCREENING,
+            TagData::UcrBg(_) => type_sig == tag_type::UCR_BG,
+            TagData::DeviceSettings(_) => type_sig == tag_type::DEVICE_SETTINGS,
+            TagData::ResponseCurveSet(_) => type_sig == tag_type::RESPONSE_CURVE_SET16,
+            TagData::Custom(tag) => type_sig == tag.type_sig(),
+        }
+    }
+}
+
+fn unsupported_type(type_sig: Signature) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Unsupported tag type '{}'", String::from(type_sig)),
+    )
+}
+
+fn corrupted_position_table() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted position table")
+}
+
+/// Reads `count` offset and size pairs. Offsets are made relative to the tag by adding `base`.
+fn read_position_table(io: &mut dyn IoHandler, count: u32, base: u32) -> Result<Vec<(u32, u32)>> {
+    // Each entry takes 8 bytes, so the count can't be bigger than what is left
+    if count as u64 * 8 > io.reported_size().saturating_sub(io.tell()) as u64 {
+        return Err(corrupted_position_table());
+    }
+
+    let mut result = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let offset = read_u32(io)?
+            .checked_add(base)
+            .ok_or_else(corrupted_position_table)?;
+        let size = read_u32(io)?;
+        result.push((offset, size));
+    }
+    Ok(result)
+}
+
+/// Writes a table of `count` entries, filled by `write_element`, with offsets relative to `base`
+fn write_position_table<F>(
+    io: &mut dyn IoHandler,
+    count: usize,
+    base: u32,
+    mut write_element: F,
+) -> Result<()>
+where
+    F: FnMut(&mut dyn IoHandler, usize) -> Result<()>,
+{
+    // Write a fake directory to be filled later on
+    let directory_pos = io.tell();
+    for _ in 0..count * 2 {
+        write_u32(io, 0)?;
+    }
+
+    let mut positions = Vec::with_capacity(count);
+    for i in 0..count {
+        let before = io.tell();
+        write_element(io, i)?;
+        positions.push((before - base, io.tell() - before));
+    }
+
+    let current_pos = io.tell();
+    io.seek(directory_pos)?;
+    for (offset, size) in positions {
+        write_u32(io, offset)?;
+        write_u32(io, size)?;
+    }
+    io.seek(current_pos)
+}
+
+/// Reads a text type embedded in another tag, type base included. `io` is left at its end.
+fn read_embedded_text(io: &mut dyn IoHandler) -> Result<Mlu> {
+    let start = io.tell();
+    let base = TagBase::read(io)?;
+    let size = io.reported_size().saturating_sub(start + 8);
+
+    match base.signature {
+        tag_type::TEXT => text::read(io, size),
+        tag_type::TEXT_DESCRIPTION => text_description::read(io, size),
+        tag_type::MULTI_LOCALIZED_UNICODE => {
+            // Strings are placed relative to the start of the embedded tag
+            let mut io = TagIo::new(io, start, size + 8)?;
+            io.seek(8)?;
+            mluc::read(&mut io, size)
+        }
+        other => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unknown text type '{}'", String::from(other)),
+        )),
+    }
+}
+
+/// Writes a text embedded in another tag, type base included. Profiles older than v4 get a textDescriptionType,
+/// newer ones a multiLocalizedUnicodeType.
+fn write_embedded_text(io: &mut dyn IoHandler, value: &Mlu, version: f64) -> Result<()> {
+    if version < 4.0 {
+        TagBase::from(u32::from(tag_type::TEXT_DESCRIPTION)).write(io)?;
+        text_description::write_unpadded(io, value)?;
+        Ok(())
+    } else {
+        TagBase::from(u32::from(tag_type::MULTI_LOCALIZED_UNICODE)).write(io)?;
+        mluc::write(io, value)
+    }
+}
+
+/// Whether there is a built-in handler for the given tag type. See `Context::is_type_supported` for the ones
+/// registered by plugins.
+pub fn is_type_supported(type_sig: Signature) -> bool {
+    [
+        tag_type::XYZ,
+        tag_type::MULTI_LOCALIZED_UNICODE,
+        tag_type::TEXT_DESCRIPTION,
+        tag_type::TEXT,
+        tag_type::CURVE,
+        tag_type::PARAMETRIC_CURVE,
+        tag_type::LUT16,
+        tag_type::LUT8,
+        tag_type::LUTA_TO_B,
+        tag_type::LUTB_TO_A,
+        tag_type::MULTI_PROCESS_ELEMENT,
+        tag_type::NAMED_COLOR2,
+        tag_type::PROFILE_SEQUENCE_DESC,
+        tag_type::PROFILE_SEQUENCE_ID,
+        tag_type::SIGNATURE,
+        tag_type::DICT,
+        tag_type::VCGT,
+        tag_type::MEASUREMENT,
+        tag_type::VIEWING_CONDITIONS,
+        tag_type::CHROMATICITY,
+        tag_type::COLORANT_ORDER,
+        tag_type::COLORANT_TABLE,
+        tag_type::S15_FIXED16_ARRAY,
+        tag_type::U16_FIXED16_ARRAY,
+        tag_type::UINT8_ARRAY,
+        tag_type::UINT16_ARRAY,
+        tag_type::UINT32_ARRAY,
+        tag_type::UINT64_ARRAY,
+        tag_type::DATA,
+        tag_type::DATE_TIME,
+        tag_type::SCREENING,
+        tag_type::UCR_BG,
+        tag_type::DEVICE_SETTINGS,
+        tag_type::CRD_INFO,
+        tag_type::RESPONSE_CURVE_SET16,
+    ]
+    .contains(&type_sig)
+}
+
+/// Reads the payload of a tag of a built-in type. See `Context::read_tag_type` for the ones registered by plugins.
+pub fn read_tag_type(
+    type_sig: Signature,
+    io: &mut dyn IoHandler,
+    size_of_tag: u32,
+) -> Result<TagData> {
+    read_tag_type_with_context(&Context::new(), type_sig, io, size_of_tag)
+}
+
+/// Reads the payload of a tag of a built-in type, building curves with the plugins registered in `context`
+pub fn read_tag_type_with_context(
+    context: &Context,
+    type_sig: Signature,
+    io: &mut dyn IoHandler,
+    size_of_tag: u32,
+) -> Result<TagData> {
+    match type_sig {
+        tag_type::XYZ => Ok(TagData::Xyz(xyz::read(io, size_of_tag)?)),
+        tag_type::MULTI_LOCALIZED_UNICODE => Ok(TagData::Mlu(mluc::read(io, size_of_tag)?)),
+        tag_type::TEXT_DESCRIPTION => Ok(TagData::Mlu(text_description::read(io, size_of_tag)?)),
+        tag_type::TEXT => Ok(TagData::Mlu(text::read(io, size_of_tag)?)),
+        tag_type::CURVE => Ok(TagData::ToneCurve(curve::read_with_context(
+            context,
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::PARAMETRIC_CURVE => Ok(TagData::ToneCurve(parametric_curve::read_with_context(
+            context,
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::LUT16 => Ok(TagData::Pipeline(lut16::read(io, size_of_tag)?)),
+        tag_type::LUT8 => Ok(TagData::Pipeline(lut8::read(io, size_of_tag)?)),
+        tag_type::LUTA_TO_B => Ok(TagData::Pipeline(lut_a_to_b::read_with_context(
+            context,
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::LUTB_TO_A => Ok(TagData::Pipeline(lut_b_to_a::read_with_context(
+            context,
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::MULTI_PROCESS_ELEMENT => Ok(TagData::Pipeline(
+            multi_process_element::read_with_context(context, io, size_of_tag)?,
+        )),
+        tag_type::NAMED_COLOR2 => Ok(TagData::NamedColorList(named_color2::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::PROFILE_SEQUENCE_DESC => Ok(TagData::ProfileSequence(
+            profile_sequence_desc::read(io, size_of_tag)?,
+        )),
+        tag_type::PROFILE_SEQUENCE_ID => Ok(TagData::ProfileSequence(profile_sequence_id::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::SIGNATURE => Ok(TagData::Signature(signature::read(io, size_of_tag)?)),
+        tag_type::DICT => Ok(TagData::Dict(dict::read(io, size_of_tag)?)),
+        tag_type::VCGT => Ok(TagData::Vcgt(vcgt::read(io, size_of_tag)?)),
+        tag_type::MEASUREMENT => Ok(TagData::Measurement(measurement::read(io, size_of_tag)?)),
+        tag_type::VIEWING_CONDITIONS => Ok(TagData::ViewingConditions(viewing_conditions::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::CHROMATICITY => Ok(TagData::Chromaticity(chromaticity::read(io, size_of_tag)?)),
+        tag_type::COLORANT_ORDER => Ok(TagData::ColorantOrder(colorant_order::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::COLORANT_TABLE => Ok(TagData::NamedColorList(colorant_table::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::S15_FIXED16_ARRAY => Ok(TagData::S15Fixed16Array(s15_fixed16_array::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::U16_FIXED16_ARRAY => Ok(TagData::U16Fixed16Array(u16_fixed16_array::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::UINT8_ARRAY => Ok(TagData::UInt8Array(uint8_array::read(io, size_of_tag)?)),
+        tag_type::UINT16_ARRAY => Ok(TagData::UInt16Array(uint16_array::read(io, size_of_tag)?)),
+        tag_type::UINT32_ARRAY => Ok(TagData::UInt32Array(uint32_array::read(io, size_of_tag)?)),
+        tag_type::UINT64_ARRAY => Ok(TagData::UInt64Array(uint64_array::read(io, size_of_tag)?)),
+        tag_type::DATA => Ok(TagData::Data(data::read(io, size_of_tag)?)),
+        tag_type::DATE_TIME => Ok(TagData::DateTime(date_time::read(io, size_of_tag)?)),
+        tag_type::SCREENING => Ok(TagData::Screening(screening::read(io, size_of_tag)?)),
+        tag_type::UCR_BG => Ok(TagData::UcrBg(ucr_bg::read(io, size_of_tag)?)),
+        tag_type::DEVICE_SETTINGS => Ok(TagData::DeviceSettings(device_settings::read(
+            io,
+            size_of_tag,
+        )?)),
+        tag_type::CRD_INFO => Ok(TagData::Mlu(crd_info::read(io, size_of_tag)?)),
+        tag_type::RESPONSE_CURVE_SET16 => Ok(TagData::ResponseCurveSet(
+            response_curve_set16::read(io, size_of_tag)?,
+        )),
+        _ => Err(unsupported_type(type_sig)),
+    }
+}
+
+/// Writes the payload of a tag as the given type, for a profile of the given version
+pub fn write_tag_type(
+    type_sig: Signature,
+    io: &mut dyn IoHandler,
+    data: &TagData,
+    version: f64,
+) -> Result<()> {
+    match (type_sig, data) {
+        (tag_type::XYZ, TagData::Xyz(values)) => xyz::write(io, values),
+        (tag_type::MULTI_LOCALIZED_UNICODE, TagData::Mlu(mlu)) => mluc::write(io, mlu),
+        (tag_type::TEXT_DESCRIPTION, TagData::Mlu(mlu)) => text_description::write(io, mlu),
+        (tag_type::TEXT, TagData::Mlu(mlu)) => text::write(io, mlu),
+        (tag_type::CURVE, TagData::ToneCurve(c)) => curve::write(io, c),
+        (tag_type::PARAMETRIC_CURVE, TagData::ToneCurve(c)) => parametric_curve::write(io, c),
+        (tag_type::LUT16, TagData::Pipeline(lut)) => lut16::write(io, lut),
+        (tag_type::LUT8, TagData::Pipeline(lut)) => lut8::write(io, lut),
+        (tag_type::LUTA_TO_B, TagData::Pipeline(lut)) => lut_a_to_b::write(io, lut),
+        (tag_type::LUTB_TO_A, TagData::Pipeline(lut)) => lut_b_to_a::write(io, lut),
+        (tag_type::MULTI_PROCESS_ELEMENT, TagData::Pipeline(lut)) => {
+            multi_process_element::write(io, lut)
+        }
+        (tag_type::NAMED_COLOR2, TagData::NamedColorList(list)) => named_color2::write(io, list),
+        (tag_type::PROFILE_SEQUENCE_DESC, TagData::ProfileSequence(seq)) => {
+            profile_sequence_desc::write(io, seq, version)
+        }
+        (tag_type::PROFILE_SEQUENCE_ID, TagData::ProfileSequence(seq)) => {
+            profile_sequence_id::write(io, seq, version)
+        }
+        (tag_type::SIGNATURE, TagData::Signature(sig)) => signature::write(io, *sig),
+        (tag_type::DICT, TagData::Dict(dict)) => dict::write(io, dict),
+        (tag_type::VCGT, TagData::Vcgt(curves)) => vcgt::write(io, curves),
+        (tag_type::MEASUREMENT, TagData::Measurement(value)) => measurement::write(io, value),
+        (tag_type::VIEWING_CONDITIONS, TagData::ViewingConditions(value)) => {
+            viewing_conditions::write(io, value)
+        }
+        (tag_type::CHROMATICITY, TagData::Chromaticity(value)) => chromaticity::write(io, value),
+        (tag_type::COLORANT_ORDER, TagData::ColorantOrder(value)) => {
+            colorant_order::write(io, value)
+        }
+        (tag_type::COLORANT_TABLE, TagData::NamedColorList(list)) => {
+            colorant_table::write(io, list)
+        }
+        (tag_type::S15_FIXED16_ARRAY, TagData::S15Fixed16Array(values)) => {
+            s15_fixed16_array::write(io, values)
+        }
+        (tag_type::U16_FIXED16_ARRAY, TagData::U16Fixed16Array(values)) => {
+            u16_fixed16_array::write(io, values)
+        }
+        (tag_type::UINT8_ARRAY, TagData::UInt8Array(values)) => uint8_array::write(io, values),
+        (tag_type::UINT16_ARRAY, TagData::UInt16Array(values)) => uint16_array::write(io, values),
+        (tag_type::UINT32_ARRAY, TagData::UInt32Array(values)) => uint32_array::write(io, values),
+        (tag_type::UINT64_ARRAY, TagData::UInt64Array(values)) => uint64_array::write(io, values),
+        (tag_type::DATA, TagData::Data(value)) => data::write(io, value),
+        (tag_type::DATE_TIME, TagData::DateTime(value)) => date_time::write(io, *value),
+        (tag_type::SCREENING, TagData::Screening(value)) => screening::write(io, value),
+        (tag_type::UCR_BG, TagData::UcrBg(value)) => ucr_bg::write(io, value),
+        (tag_type::DEVICE_SETTINGS, TagData::DeviceSettings(value)) => {
+            device_settings::write(io, value)
+        }
+        (tag_type::CRD_INFO, TagData::Mlu(mlu)) => crd_info::write(io, mlu),
+        (tag_type::RESPONSE_CURVE_SET16, TagData::ResponseCurveSet(value)) => {
+            response_curve_set16::write(io, value)
+        }
+        (_, TagData::Custom(tag)) if tag.type_sig() == type_sig => tag.write(io),
+        _ => Err(unsupported_type(type_sig)),
+    }
+}
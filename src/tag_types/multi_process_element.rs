@@ -0,0 +1,385 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::{read_position_table, write_position_table};
+use crate::pipeline::{Stage, StageData, StageLoc};
+use crate::plugin::{
+    read_f32, read_u16, read_u32, write_alignment, write_f32, write_u16, write_u32, IoHandler,
+    MAX_INPUT_DIMENSIONS,
+};
+use crate::signatures::{curve_segment, stage};
+use crate::{
+    Context, CurveSegment, Pipeline, Signature, ToneCurve, MAX_CHANNELS, MINUS_INF, PLUS_INF,
+};
+
+/// Number of parameters of each formula segment type, which are the curve types 6 to 8
+const PARAMS_BY_TYPE: [usize; 3] = [4, 5, 5];
+
+fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted multiProcessElementType")
+}
+
+fn unsuitable(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+fn read_segmented_curve(context: &Context, io: &mut dyn IoHandler) -> Result<ToneCurve> {
+    // That should be a segmented curve
+    if Signature::from(read_u32(io)?) != curve_segment::SEGMENTED {
+        return Err(corrupted());
+    }
+    read_u32(io)?; // Reserved
+
+    let count = read_u16(io)? as usize;
+    read_u16(io)?; // Reserved
+    if count < 1 {
+        return Err(corrupted());
+    }
+
+    // Break points
+    let mut breaks = Vec::with_capacity(count + 1);
+    breaks.push(MINUS_INF);
+    for _ in 0..count - 1 {
+        breaks.push(read_f32(io)?);
+    }
+    breaks.push(PLUS_INF);
+
+    let mut segments = Vec::with_capacity(count);
+    for i in 0..count {
+        let mut segment = CurveSegment {
+            x0: breaks[i],
+            x1: breaks[i + 1],
+            r#type: 0,
+            params: [0.0; 10],
+            n_grid_points: 0,
+            sampled_points: Vec::new(),
+        };
+
+        let element_sig = Signature::from(read_u32(io)?);
+        read_u32(io)?; // Reserved
+
+        match element_sig {
+            curve_segment::FORMULA => {
+                let function_type = read_u16(io)? as usize;
+                read_u16(io)?; // Reserved
+
+                if function_type >= PARAMS_BY_TYPE.len() {
+                    return Err(corrupted());
+                }
+                segment.r#type = function_type as i32 + 6;
+                for param in segment
+                    .params
+                    .iter_mut()
+                    .take(PARAMS_BY_TYPE[function_type])
+                {
+                    *param = read_f32(io)? as f64;
+                }
+            }
+            curve_segment::SAMPLED => {
+                let n = read_u32(io)?;
+                if n as u64 * 4 > io.reported_size().saturating_sub(io.tell()) as u64 {
+                    return Err(corrupted());
+                }
+
+                // The first point is implicit, it is the end of the previous segment. It gets filled in below.
+                segment.n_grid_points = n + 1;
+                segment.sampled_points = vec![0.0; n as usize + 1];
+                for point in segment.sampled_points.iter_mut().skip(1) {
+                    *point = read_f32(io)?;
+                }
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Unknown curve element type '{}' found.",
+                        String::from(element_sig)
+                    ),
+                ))
+            }
+        }
+        segments.push(segment);
+    }
+
+    // Sampled segments start where the previous segment ends
+    let curve = ToneCurve::from_segments_with_context(context, &segments).ok_or_else(corrupted)?;
+    for segment in segments.iter_mut().filter(|s| s.r#type == 0) {
+        segment.sampled_points[0] = curve.eval_f32(segment.x0);
+    }
+
+    ToneCurve::from_segments_with_context(context, &segments).ok_or_else(corrupted)
+}
+
+fn write_segmented_curve(io: &mut dyn IoHandler, curve: &ToneCurve) -> Result<()> {
+    // Tabulated curves go as a single sampled segment
+    let sampled;
+    let segments = if curve.segments().is_empty() {
+        let table = curve
+            .table16()
+            .iter()
+            .map(|v| *v as f32 / 65535.0)
+            .collect::<Vec<_>>();
+        sampled = ToneCurve::from_float_table(&table)
+            .ok_or_else(|| unsuitable("Curve can't be sampled"))?;
+        sampled.segments()
+    } else {
+        curve.segments()
+    };
+
+    write_u32(io, u32::from(curve_segment::SEGMENTED))?;
+    write_u32(io, 0)?;
+    write_u16(io, segments.len() as u16)?;
+    write_u16(io, 0)?;
+
+    // Break points
+    for segment in &segments[..segments.len() - 1] {
+        write_f32(io, segment.x1)?;
+    }
+
+    for segment in segments {
+        if segment.r#type == 0 {
+            // The first point is implicit in the ICC format, but not in our representation
+            write_u32(io, u32::from(curve_segment::SAMPLED))?;
+            write_u32(io, 0)?;
+            write_u32(io, segment.n_grid_points - 1)?;
+            for point in &segment.sampled_points[1..] {
+                write_f32(io, *point)?;
+            }
+        } else {
+            // Only types 6 to 8 are allowed
+            let function_type = segment.r#type - 6;
+            if !(0..=2).contains(&function_type) {
+                return Err(unsuitable(
+                    "Only formula segments of types 6 to 8 can be written",
+                ));
+            }
+
+            write_u32(io, u32::from(curve_segment::FORMULA))?;
+            write_u32(io, 0)?;
+            write_u16(io, function_type as u16)?;
+            write_u16(io, 0)?;
+            for param in &segment.params[..PARAMS_BY_TYPE[function_type as usize]] {
+                write_f32(io, *param as f32)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_curve_set(context: &Context, io: &mut dyn IoHandler, element_start: u32) -> Result<Stage> {
+    let input_channels = read_u16(io)?;
+    let output_channels = read_u16(io)?;
+    if input_channels != output_channels || input_channels as usize >= MAX_CHANNELS {
+        return Err(corrupted());
+    }
+
+    let positions = read_position_table(io, input_channels as u32, element_start)?;
+    let mut curves = Vec::with_capacity(positions.len());
+    for (offset, _) in positions {
+        io.seek(offset)?;
+        curves.push(read_segmented_curve(context, io)?);
+    }
+
+    Stage::new_tone_curves(curves).ok_or_else(corrupted)
+}
+
+fn write_curve_set(io: &mut dyn IoHandler, element_start: u32, curves: &[ToneCurve]) -> Result<()> {
+    // Since those are curves, input and output channels are the same
+    write_u16(io, curves.len() as u16)?;
+    write_u16(io, curves.len() as u16)?;
+
+    write_position_table(io, curves.len(), element_start, |io, i| {
+        write_segmented_curve(io, &curves[i])
+    })
+}
+
+fn read_matrix(io: &mut dyn IoHandler) -> Result<Stage> {
+    let input_channels = read_u16(io)? as usize;
+    let output_channels = read_u16(io)? as usize;
+
+    // Channels may go up to 0xffff, but we limit them to what ICC profiles can have
+    if input_channels >= MAX_CHANNELS || output_channels >= MAX_CHANNELS {
+        return Err(corrupted());
+    }
+
+    let mut matrix = vec![0f64; input_channels * output_channels];
+    for value in matrix.iter_mut() {
+        *value = read_f32(io)? as f64;
+    }
+    let mut offset = vec![0f64; output_channels];
+    for value in offset.iter_mut() {
+        *value = read_f32(io)? as f64;
+    }
+
+    Stage::new_matrix(output_channels, input_channels, &matrix, Some(&offset)).ok_or_else(corrupted)
+}
+
+fn write_matrix(
+    io: &mut dyn IoHandler,
+    stage: &Stage,
+    matrix: &[f64],
+    offset: Option<&[f64]>,
+) -> Result<()> {
+    write_u16(io, stage.input_channels() as u16)?;
+    write_u16(io, stage.output_channels() as u16)?;
+
+    for value in matrix {
+        write_f32(io, *value as f32)?;
+    }
+    for i in 0..stage.output_channels() {
+        write_f32(io, offset.map_or(0.0, |o| o[i] as f32))?;
+    }
+    Ok(())
+}
+
+fn read_clut(io: &mut dyn IoHandler) -> Result<Stage> {
+    let input_channels = read_u16(io)? as usize;
+    let output_channels = read_u16(io)? as usize;
+    if input_channels == 0 || output_channels == 0 || input_channels > MAX_INPUT_DIMENSIONS {
+        return Err(corrupted());
+    }
+
+    let mut dimensions = [0u8; 16];
+    io.read_exact(&mut dimensions)?;
+
+    // Impossible value, 0 for no CLUT and then 2 at least
+    let grid_points = dimensions[..input_channels]
+        .iter()
+        .map(|v| *v as u32)
+        .collect::<Vec<_>>();
+    if grid_points.contains(&1) {
+        return Err(corrupted());
+    }
+
+    let size = grid_points
+        .iter()
+        .try_fold(output_channels, |size, n| size.checked_mul(*n as usize))
+        .filter(|size| *size > 0)
+        .ok_or_else(corrupted)?;
+    if size as u64 * 4 > io.reported_size().saturating_sub(io.tell()) as u64 {
+        return Err(corrupted());
+    }
+
+    let mut table = vec![0f32; size];
+    for value in table.iter_mut() {
+        *value = read_f32(io)?;
+    }
+
+    Stage::new_clut_float_granular(&grid_points, output_channels, Some(&table))
+        .ok_or_else(corrupted)
+}
+
+fn write_clut(io: &mut dyn IoHandler, stage: &Stage) -> Result<()> {
+    let (n_samples, table) = match stage.data() {
+        StageData::CLutFloat(params) => (&params.n_samples, params.table.clone()),
+        // 16 bit tables are promoted
+        StageData::CLut16(params) => (
+            &params.n_samples,
+            params.table.iter().map(|v| *v as f32 / 65535.0).collect(),
+        ),
+        _ => return Err(unsuitable("Not a CLUT")),
+    };
+
+    write_u16(io, stage.input_channels() as u16)?;
+    write_u16(io, stage.output_channels() as u16)?;
+
+    let mut dimensions = [0u8; 16];
+    for (i, n) in n_samples.iter().enumerate() {
+        dimensions[i] = *n as u8;
+    }
+    io.write_all(&dimensions)?;
+
+    for value in table {
+        write_f32(io, value)?;
+    }
+    Ok(())
+}
+
+/// Reads a multiProcessElementType into a pipeline, which evaluates in floating point. The `bACS` and `eACS`
+/// elements are placeholders, and are skipped.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Pipeline> {
+    read_with_context(&Context::new(), io, size_of_tag)
+}
+
+/// Reads a multiProcessElementType, whose curve segments may use types registered by plugins in `context`
+pub fn read_with_context(
+    context: &Context,
+    io: &mut dyn IoHandler,
+    _size_of_tag: u32,
+) -> Result<Pipeline> {
+    let input_channels = read_u16(io)? as usize;
+    let output_channels = read_u16(io)? as usize;
+    if input_channels == 0
+        || input_channels >= MAX_CHANNELS
+        || output_channels == 0
+        || output_channels >= MAX_CHANNELS
+    {
+        return Err(corrupted());
+    }
+
+    let mut result = Pipeline::new(input_channels, output_channels).ok_or_else(corrupted)?;
+
+    let count = read_u32(io)?;
+    // Element offsets are relative to the start of the tag, as `io` positions are
+    for (offset, _) in read_position_table(io, count, 0)? {
+        io.seek(offset)?;
+
+        let element_sig = Signature::from(read_u32(io)?);
+        read_u32(io)?; // Reserved
+
+        let stage = match element_sig {
+            stage::CURVE_SET_ELEM_TYPE => read_curve_set(context, io, offset)?,
+            stage::MATRIX_ELEM_TYPE => read_matrix(io)?,
+            stage::C_LUT_ELEM_TYPE => read_clut(io)?,
+            stage::B_ACS_ELEM_TYPE | stage::E_ACS_ELEM_TYPE => continue,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unknown MPE type '{}' found.", String::from(element_sig)),
+                ))
+            }
+        };
+
+        if !result.insert_stage(StageLoc::AtEnd, stage) {
+            return Err(corrupted());
+        }
+    }
+
+    // Check channel count
+    if input_channels != result.input_channels() || output_channels != result.output_channels() {
+        return Err(corrupted());
+    }
+
+    Ok(result)
+}
+
+/// Writes a pipeline as a multiProcessElementType. Stages must be curves, matrices or CLUTs.
+pub fn write(io: &mut dyn IoHandler, pipeline: &Pipeline) -> Result<()> {
+    let stages = pipeline.stages();
+
+    write_u16(io, pipeline.input_channels() as u16)?;
+    write_u16(io, pipeline.output_channels() as u16)?;
+    write_u32(io, stages.len() as u32)?;
+
+    write_position_table(io, stages.len(), 0, |io, i| {
+        let element = &stages[i];
+        let element_start = io.tell();
+
+        write_u32(io, u32::from(element.stage_type()))?;
+        write_u32(io, 0)?;
+
+        match element.data() {
+            StageData::Curves(curves) => write_curve_set(io, element_start, curves)?,
+            StageData::Matrix { matrix, offset } => {
+                write_matrix(io, element, matrix, offset.as_deref())?
+            }
+            StageData::CLut16(_) | StageData::CLutFloat(_) => write_clut(io, element)?,
+            _ => {
+                return Err(unsuitable(&format!(
+                    "Found unknown MPE type '{}'",
+                    String::from(element.stage_type())
+                )))
+            }
+        }
+        write_alignment(io)
+    })
+}
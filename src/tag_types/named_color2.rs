@@ -0,0 +1,68 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::text::ascii_to_string;
+use crate::plugin::{read_u16, read_u16_array, read_u32, write_u16_array, write_u32, IoHandler};
+use crate::{NamedColorList, MAX_CHANNELS};
+
+/// Size of the prefix, suffix and color names, NUL included if shorter
+const NAME_SIZE: usize = 32;
+
+pub(super) fn read_name(io: &mut dyn IoHandler) -> Result<String> {
+    let mut name = [0u8; NAME_SIZE];
+    io.read_exact(&mut name)?;
+
+    Ok(ascii_to_string(&name))
+}
+
+/// Writes ASCII text padded with NULs. Longer text is cut, and characters outside of ASCII are replaced by '?'.
+pub(super) fn write_name(io: &mut dyn IoHandler, value: &str) -> Result<()> {
+    let mut name = [0u8; NAME_SIZE];
+    for (dst, src) in name.iter_mut().zip(value.chars()) {
+        *dst = if src.is_ascii() { src as u8 } else { b'?' };
+    }
+
+    io.write_all(&name)
+}
+
+/// Reads a namedColor2Type
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<NamedColorList> {
+    read_u32(io)?; // Vendor flag
+    let count = read_u32(io)?;
+    let colorant_count = read_u32(io)? as usize;
+
+    let prefix = read_name(io)?;
+    let suffix = read_name(io)?;
+
+    let mut result = NamedColorList::new(colorant_count, &prefix, &suffix).ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Too many colorants '{}'", colorant_count),
+        )
+    })?;
+
+    let mut colorant = [0u16; MAX_CHANNELS];
+    for _ in 0..count {
+        let root = read_name(io)?;
+        let pcs = [read_u16(io)?, read_u16(io)?, read_u16(io)?];
+        read_u16_array(io, &mut colorant[..colorant_count])?;
+
+        result.append(&root, pcs, &colorant[..colorant_count]);
+    }
+    Ok(result)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &NamedColorList) -> Result<()> {
+    write_u32(io, 0)?; // Vendor flag
+    write_u32(io, value.len() as u32)?;
+    write_u32(io, value.colorant_count() as u32)?;
+
+    write_name(io, value.prefix())?;
+    write_name(io, value.suffix())?;
+
+    for color in value.colors() {
+        write_name(io, &color.name)?;
+        write_u16_array(io, &color.pcs)?;
+        write_u16_array(io, &color.device_colorant)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,63 @@
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use crate::plugin::{f64_to_s15f16, read_s15f16, read_u16, s15f16_to_f64, write_s15f16, write_u16};
+use crate::{Context, ToneCurve};
+
+/// Number of parameters of each function type, which are the curve types 1 to 5
+const PARAMS_BY_TYPE: [usize; 5] = [1, 3, 4, 5, 7];
+
+/// Reads a parametricCurveType
+pub fn read(reader: &mut dyn Read, size_of_tag: u32) -> Result<ToneCurve> {
+    read_with_context(&Context::new(), reader, size_of_tag)
+}
+
+/// Reads a parametricCurveType, whose curve types may be overridden by plugins registered in `context`
+pub fn read_with_context(
+    context: &Context,
+    reader: &mut dyn Read,
+    _size_of_tag: u32,
+) -> Result<ToneCurve> {
+    let function_type = read_u16(reader)? as usize;
+    read_u16(reader)?; // Reserved
+
+    if function_type >= PARAMS_BY_TYPE.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Unknown parametric curve type '{}'", function_type),
+        ));
+    }
+
+    let mut params = [0f64; 7];
+    for param in params.iter_mut().take(PARAMS_BY_TYPE[function_type]) {
+        *param = s15f16_to_f64(read_s15f16(reader)?);
+    }
+
+    ToneCurve::build_parametric_with_context(context, function_type as i32 + 1, &params)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Corrupted parametric curve"))
+}
+
+/// Whether the curve can be written as a parametricCurveType
+pub fn fits(curve: &ToneCurve) -> bool {
+    matches!(curve.parametric_type(), Some(1..=5))
+}
+
+/// Writes a parametricCurveType. Only single segment curves of types 1 to 5 can be written.
+pub fn write(writer: &mut dyn Write, curve: &ToneCurve) -> Result<()> {
+    let function_type = match curve.parametric_type() {
+        Some(t @ 1..=5) => t as usize,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Multisegment, inverted or unsupported parametric curves cannot be written",
+            ))
+        }
+    };
+
+    write_u16(writer, function_type as u16 - 1)?;
+    write_u16(writer, 0)?; // Reserved
+
+    for param in &curve.segments()[0].params[..PARAMS_BY_TYPE[function_type - 1]] {
+        write_s15f16(writer, f64_to_s15f16(*param))?;
+    }
+    Ok(())
+}
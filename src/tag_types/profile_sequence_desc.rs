@@ -0,0 +1,54 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::{read_embedded_text, write_embedded_text};
+use crate::plugin::{read_u32, read_u64, write_u32, write_u64, IoHandler};
+use crate::{ProfileSequenceDesc, Signature};
+
+/// Size of the fixed part of each description
+const DESC_HEADER_SIZE: u32 = 20;
+
+/// Reads a profileSequenceDescType. Profile IDs and descriptions are not part of it and are left empty.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Vec<ProfileSequenceDesc>> {
+    let count = read_u32(io)?;
+    if count as u64 * DESC_HEADER_SIZE as u64 > size_of_tag.saturating_sub(4) as u64 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Corrupted profileSequenceDescType",
+        ));
+    }
+
+    let mut result = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let device_mfg = Signature::from(read_u32(io)?);
+        let device_model = Signature::from(read_u32(io)?);
+        let attributes = read_u64(io)?;
+        let technology = Signature::from(read_u32(io)?);
+
+        result.push(ProfileSequenceDesc {
+            device_mfg,
+            device_model,
+            attributes,
+            technology,
+            manufacturer: read_embedded_text(io)?,
+            model: read_embedded_text(io)?,
+            ..Default::default()
+        });
+    }
+    Ok(result)
+}
+
+/// Writes a profileSequenceDescType. Texts are stored as textDescriptionType before v4.
+pub fn write(io: &mut dyn IoHandler, value: &[ProfileSequenceDesc], version: f64) -> Result<()> {
+    write_u32(io, value.len() as u32)?;
+
+    for desc in value {
+        write_u32(io, u32::from(desc.device_mfg))?;
+        write_u32(io, u32::from(desc.device_model))?;
+        write_u64(io, desc.attributes)?;
+        write_u32(io, u32::from(desc.technology))?;
+
+        write_embedded_text(io, &desc.manufacturer, version)?;
+        write_embedded_text(io, &desc.model, version)?;
+    }
+    Ok(())
+}
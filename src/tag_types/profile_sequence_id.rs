@@ -0,0 +1,37 @@
+use std::io::Result;
+
+use super::{read_embedded_text, read_position_table, write_embedded_text, write_position_table};
+use crate::plugin::{read_u32, write_u32, IoHandler};
+use crate::ProfileSequenceDesc;
+
+/// Reads a profileSequenceIdentifierType. Only profile IDs and descriptions are part of it, the rest is left
+/// empty.
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<Vec<ProfileSequenceDesc>> {
+    let count = read_u32(io)?;
+
+    let positions = read_position_table(io, count, 0)?;
+
+    let mut result = Vec::with_capacity(positions.len());
+    for (offset, _) in positions {
+        io.seek(offset)?;
+
+        let mut profile_id = [0u8; 16];
+        io.read_exact(&mut profile_id)?;
+
+        result.push(ProfileSequenceDesc {
+            profile_id,
+            description: read_embedded_text(io)?,
+            ..Default::default()
+        });
+    }
+    Ok(result)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &[ProfileSequenceDesc], version: f64) -> Result<()> {
+    write_u32(io, value.len() as u32)?;
+
+    write_position_table(io, value.len(), 0, |io, i| {
+        io.write_all(&value[i].profile_id)?;
+        write_embedded_text(io, &value[i].description, version)
+    })
+}
@@ -0,0 +1,130 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{
+    f64_to_s15f16, read_s15f16, read_u16, read_u32, read_xyz, s15f16_to_f64, write_s15f16,
+    write_u16, write_u32, write_xyz, IoHandler,
+};
+use crate::{Response16, ResponseCurve, ResponseCurveSet, Signature, MAX_CHANNELS};
+
+fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted response curve set")
+}
+
+fn read_curve(io: &mut dyn IoHandler, channels: usize) -> Result<ResponseCurve> {
+    let measurement_unit = Signature::from(read_u32(io)?);
+
+    let mut counts = Vec::with_capacity(channels);
+    for _ in 0..channels {
+        counts.push(read_u32(io)?);
+    }
+    // Each response takes 8 bytes
+    let total = counts.iter().map(|c| *c as u64).sum::<u64>();
+    if total * 8 > io.reported_size().saturating_sub(io.tell()) as u64 {
+        return Err(corrupted());
+    }
+
+    let mut max_colorant_pcs = Vec::with_capacity(channels);
+    for _ in 0..channels {
+        max_colorant_pcs.push(read_xyz(io)?);
+    }
+
+    let mut responses = Vec::with_capacity(channels);
+    for count in counts {
+        let mut channel = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let device = read_u16(io)?;
+            read_u16(io)?; // Reserved
+            channel.push(Response16 {
+                device,
+                measurement: s15f16_to_f64(read_s15f16(io)?),
+            });
+        }
+        responses.push(channel);
+    }
+
+    Ok(ResponseCurve {
+        measurement_unit,
+        max_colorant_pcs,
+        responses,
+    })
+}
+
+/// Reads a responseCurveSet16Type
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<ResponseCurveSet> {
+    let channels = read_u16(io)? as usize;
+    let count = read_u16(io)?;
+
+    if channels > MAX_CHANNELS {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Too many response curve channels '{}'", channels),
+        ));
+    }
+
+    let mut offsets = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        offsets.push(read_u32(io)?);
+    }
+
+    let mut curves = Vec::with_capacity(count as usize);
+    for offset in offsets {
+        io.seek(offset)?;
+        curves.push(read_curve(io, channels)?);
+    }
+    Ok(ResponseCurveSet { channels, curves })
+}
+
+fn write_curve(io: &mut dyn IoHandler, value: &ResponseCurve) -> Result<()> {
+    write_u32(io, u32::from(value.measurement_unit))?;
+
+    for channel in &value.responses {
+        write_u32(io, channel.len() as u32)?;
+    }
+    for pcs in &value.max_colorant_pcs {
+        write_xyz(io, *pcs)?;
+    }
+    for channel in &value.responses {
+        for response in channel {
+            write_u16(io, response.device)?;
+            write_u16(io, 0)?; // Reserved
+            write_s15f16(io, f64_to_s15f16(response.measurement))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &ResponseCurveSet) -> Result<()> {
+    let fits = value.channels <= u16::MAX as usize
+        && value.curves.len() <= u16::MAX as usize
+        && value.curves.iter().all(|c| {
+            c.max_colorant_pcs.len() == value.channels && c.responses.len() == value.channels
+        });
+    if !fits {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Response curves don't match the number of channels",
+        ));
+    }
+
+    write_u16(io, value.channels as u16)?;
+    write_u16(io, value.curves.len() as u16)?;
+
+    // Write a fake directory to be filled later on
+    let directory_pos = io.tell();
+    for _ in &value.curves {
+        write_u32(io, 0)?;
+    }
+
+    let mut offsets = Vec::with_capacity(value.curves.len());
+    for curve in &value.curves {
+        offsets.push(io.tell());
+        write_curve(io, curve)?;
+    }
+
+    let current_pos = io.tell();
+    io.seek(directory_pos)?;
+    for offset in offsets {
+        write_u32(io, offset)?;
+    }
+    io.seek(current_pos)
+}
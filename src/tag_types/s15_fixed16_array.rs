@@ -0,0 +1,17 @@
+use std::io::Result;
+
+use crate::plugin::{f64_to_s15f16, read_s15f16, s15f16_to_f64, write_s15f16, IoHandler};
+
+/// Reads a s15Fixed16ArrayType, as many numbers as the tag holds
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Vec<f64>> {
+    (0..size_of_tag / 4)
+        .map(|_| Ok(s15f16_to_f64(read_s15f16(io)?)))
+        .collect()
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &[f64]) -> Result<()> {
+    for v in value {
+        write_s15f16(io, f64_to_s15f16(*v))?;
+    }
+    Ok(())
+}
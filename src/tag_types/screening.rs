@@ -0,0 +1,46 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::plugin::{
+    f64_to_s15f16, read_s15f16, read_u32, s15f16_to_f64, write_s15f16, write_u32, IoHandler,
+};
+use crate::{Screening, ScreeningChannel, MAX_CHANNELS};
+
+fn too_many(count: usize, kind: ErrorKind) -> Error {
+    Error::new(kind, format!("Too many screening channels '{}'", count))
+}
+
+/// Reads a screeningType
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<Screening> {
+    let flag = read_u32(io)?;
+    let count = read_u32(io)? as usize;
+
+    if count > MAX_CHANNELS {
+        return Err(too_many(count, ErrorKind::InvalidData));
+    }
+
+    let mut channels = Vec::with_capacity(count);
+    for _ in 0..count {
+        channels.push(ScreeningChannel {
+            frequency: s15f16_to_f64(read_s15f16(io)?),
+            screen_angle: s15f16_to_f64(read_s15f16(io)?),
+            spot_shape: read_u32(io)?,
+        });
+    }
+    Ok(Screening { flag, channels })
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &Screening) -> Result<()> {
+    if value.channels.len() > MAX_CHANNELS {
+        return Err(too_many(value.channels.len(), ErrorKind::InvalidInput));
+    }
+
+    write_u32(io, value.flag)?;
+    write_u32(io, value.channels.len() as u32)?;
+
+    for channel in &value.channels {
+        write_s15f16(io, f64_to_s15f16(channel.frequency))?;
+        write_s15f16(io, f64_to_s15f16(channel.screen_angle))?;
+        write_u32(io, channel.spot_shape)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,13 @@
+use std::io::Result;
+
+use crate::plugin::{read_u32, write_u32, IoHandler};
+use crate::Signature;
+
+/// Reads a signatureType
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<Signature> {
+    Ok(Signature::from(read_u32(io)?))
+}
+
+pub fn write(io: &mut dyn IoHandler, value: Signature) -> Result<()> {
+    write_u32(io, u32::from(value))
+}
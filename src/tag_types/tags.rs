@@ -0,0 +1,305 @@
+use crate::signatures::{tag, tag_type};
+use crate::Signature;
+
+use super::TagData;
+
+/// Describes what a tag may hold
+#[derive(Copy, Clone)]
+pub struct TagDescriptor {
+    /// Minimum number of elements the tag holds, 1 for most tags
+    pub element_count: usize,
+    /// Types the tag may be stored as
+    pub supported_types: &'static [Signature],
+    /// Picks the type to write depending on the profile version. Uses the first supported type if not given.
+    pub decide_type: Option<fn(version: f64, data: &TagData) -> Signature>,
+}
+
+impl TagDescriptor {
+    /// Type to use when writing `data` to a profile of the given version
+    pub fn type_for(&self, version: f64, data: &TagData) -> Signature {
+        match self.decide_type {
+            Some(decide) => decide(version, data),
+            None => self.supported_types[0],
+        }
+    }
+}
+
+fn decide_xyz_type(_version: f64, _data: &TagData) -> Signature {
+    tag_type::XYZ
+}
+
+fn decide_text_type(version: f64, _data: &TagData) -> Signature {
+    if version >= 4.0 {
+        tag_type::MULTI_LOCALIZED_UNICODE
+    } else {
+        tag_type::TEXT
+    }
+}
+
+fn decide_text_desc_type(version: f64, _data: &TagData) -> Signature {
+    if version >= 4.0 {
+        tag_type::MULTI_LOCALIZED_UNICODE
+    } else {
+        tag_type::TEXT_DESCRIPTION
+    }
+}
+
+const XYZ_TYPES: &[Signature] = &[tag_type::XYZ];
+const TEXT_TYPES: &[Signature] = &[
+    tag_type::MULTI_LOCALIZED_UNICODE,
+    tag_type::TEXT,
+    tag_type::TEXT_DESCRIPTION,
+];
+const TEXT_DESC_TYPES: &[Signature] = &[
+    tag_type::MULTI_LOCALIZED_UNICODE,
+    tag_type::TEXT_DESCRIPTION,
+    tag_type::TEXT,
+];
+
+fn decide_curve_type(version: f64, data: &TagData) -> Signature {
+    match data {
+        TagData::ToneCurve(curve) if version >= 4.0 && super::parametric_curve::fits(curve) => {
+            tag_type::PARAMETRIC_CURVE
+        }
+        _ => tag_type::CURVE,
+    }
+}
+
+fn save_as_8_bits(data: &TagData) -> bool {
+    matches!(data, TagData::Pipeline(lut) if lut.save_as_8_bits())
+}
+
+fn decide_lut_type_a_to_b(version: f64, data: &TagData) -> Signature {
+    if version >= 4.0 {
+        tag_type::LUTA_TO_B
+    } else if save_as_8_bits(data) {
+        tag_type::LUT8
+    } else {
+        tag_type::LUT16
+    }
+}
+
+fn decide_lut_type_b_to_a(version: f64, data: &TagData) -> Signature {
+    if version >= 4.0 {
+        tag_type::LUTB_TO_A
+    } else if save_as_8_bits(data) {
+        tag_type::LUT8
+    } else {
+        tag_type::LUT16
+    }
+}
+
+const CURVE_TYPES: &[Signature] = &[tag_type::CURVE, tag_type::PARAMETRIC_CURVE];
+const A_TO_B_TYPES: &[Signature] = &[tag_type::LUTA_TO_B, tag_type::LUT16, tag_type::LUT8];
+const B_TO_A_TYPES: &[Signature] = &[tag_type::LUTB_TO_A, tag_type::LUT16, tag_type::LUT8];
+const MPE_TYPES: &[Signature] = &[tag_type::MULTI_PROCESS_ELEMENT];
+const NAMED_COLOR_TYPES: &[Signature] = &[tag_type::NAMED_COLOR2];
+const SIGNATURE_TYPES: &[Signature] = &[tag_type::SIGNATURE];
+
+const CURVE_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: CURVE_TYPES,
+    decide_type: Some(decide_curve_type),
+};
+
+const A_TO_B_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: A_TO_B_TYPES,
+    decide_type: Some(decide_lut_type_a_to_b),
+};
+
+const B_TO_A_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: B_TO_A_TYPES,
+    decide_type: Some(decide_lut_type_b_to_a),
+};
+
+const MPE_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: MPE_TYPES,
+    decide_type: None,
+};
+
+const NAMED_COLOR_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: NAMED_COLOR_TYPES,
+    decide_type: None,
+};
+
+const SIGNATURE_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: SIGNATURE_TYPES,
+    decide_type: None,
+};
+
+const XYZ_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: XYZ_TYPES,
+    decide_type: Some(decide_xyz_type),
+};
+
+const TEXT_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: TEXT_TYPES,
+    decide_type: Some(decide_text_type),
+};
+
+const TEXT_DESC_TAG: TagDescriptor = TagDescriptor {
+    element_count: 1,
+    supported_types: TEXT_DESC_TYPES,
+    decide_type: Some(decide_text_desc_type),
+};
+
+/// Known tags and the types they can hold. See `Context::tag_descriptor` for tags registered by plugins.
+pub fn tag_descriptor(signature: Signature) -> Option<TagDescriptor> {
+    match signature {
+        tag::MEDIA_WHITE_POINT
+        | tag::MEDIA_BLACK_POINT
+        | tag::LUMINANCE
+        | tag::RED_COLORANT
+        | tag::GREEN_COLORANT
+        | tag::BLUE_COLORANT => Some(XYZ_TAG),
+
+        tag::A_TO_B0 | tag::A_TO_B1 | tag::A_TO_B2 => Some(A_TO_B_TAG),
+
+        tag::B_TO_A0
+        | tag::B_TO_A1
+        | tag::B_TO_A2
+        | tag::GAMUT
+        | tag::PREVIEW0
+        | tag::PREVIEW1
+        | tag::PREVIEW2 => Some(B_TO_A_TAG),
+
+        tag::D_TO_B0
+        | tag::D_TO_B1
+        | tag::D_TO_B2
+        | tag::D_TO_B3
+        | tag::B_TO_D0
+        | tag::B_TO_D1
+        | tag::B_TO_D2
+        | tag::B_TO_D3 => Some(MPE_TAG),
+
+        tag::NAMED_COLOR2 => Some(NAMED_COLOR_TAG),
+
+        tag::PROFILE_SEQUENCE_DESC => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::PROFILE_SEQUENCE_DESC],
+            decide_type: None,
+        }),
+        tag::PROFILE_SEQUENCE_ID => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::PROFILE_SEQUENCE_ID],
+            decide_type: None,
+        }),
+
+        tag::TECHNOLOGY
+        | tag::COLORIMETRIC_INTENT_IMAGE_STATE
+        | tag::PERCEPTUAL_RENDERING_INTENT_GAMUT
+        | tag::SATURATION_RENDERING_INTENT_GAMUT => Some(SIGNATURE_TAG),
+
+        tag::CHROMATIC_ADAPTATION => Some(TagDescriptor {
+            element_count: 9,
+            supported_types: &[tag_type::S15_FIXED16_ARRAY],
+            decide_type: None,
+        }),
+        tag::CALIBRATION_DATE_TIME => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::DATE_TIME],
+            decide_type: None,
+        }),
+        tag::PS2_CRD0
+        | tag::PS2_CRD1
+        | tag::PS2_CRD2
+        | tag::PS2_CRD3
+        | tag::PS2_CSA
+        | tag::PS2_RENDERING_INTENT => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::DATA],
+            decide_type: None,
+        }),
+
+        tag::META => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::DICT],
+            decide_type: None,
+        }),
+        tag::CHROMATICITY => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::CHROMATICITY],
+            decide_type: None,
+        }),
+        tag::COLORANT_ORDER => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::COLORANT_ORDER],
+            decide_type: None,
+        }),
+        tag::COLORANT_TABLE | tag::COLORANT_TABLE_OUT => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::COLORANT_TABLE],
+            decide_type: None,
+        }),
+        tag::SCREENING => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::SCREENING],
+            decide_type: None,
+        }),
+        tag::UCR_BG => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::UCR_BG],
+            decide_type: None,
+        }),
+        tag::DEVICE_SETTINGS => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::DEVICE_SETTINGS],
+            decide_type: None,
+        }),
+        tag::CRD_INFO => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::CRD_INFO],
+            decide_type: None,
+        }),
+        tag::OUTPUT_RESPONSE => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::RESPONSE_CURVE_SET16],
+            decide_type: None,
+        }),
+        tag::MEASUREMENT => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::MEASUREMENT],
+            decide_type: None,
+        }),
+        tag::VIEWING_CONDITIONS => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::VIEWING_CONDITIONS],
+            decide_type: None,
+        }),
+        tag::VCGT => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::VCGT],
+            decide_type: None,
+        }),
+
+        tag::RED_TRC | tag::GREEN_TRC | tag::BLUE_TRC | tag::GRAY_TRC => Some(CURVE_TAG),
+
+        tag::COPYRIGHT => Some(TEXT_TAG),
+
+        tag::PROFILE_DESCRIPTION
+        | tag::DEVICE_MFG_DESC
+        | tag::DEVICE_MODEL_DESC
+        | tag::SCREENING_DESC
+        | tag::VIEWING_COND_DESC => Some(TEXT_DESC_TAG),
+
+        tag::CHAR_TARGET => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::TEXT],
+            decide_type: None,
+        }),
+        tag::PROFILE_DESCRIPTION_ML => Some(TagDescriptor {
+            element_count: 1,
+            supported_types: &[tag_type::MULTI_LOCALIZED_UNICODE],
+            decide_type: None,
+        }),
+
+        _ => None,
+    }
+}
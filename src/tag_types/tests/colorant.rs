@@ -0,0 +1,138 @@
+use crate::plugin::{MemoryIo, VecIo};
+use crate::signatures::{color_space, profile_class, tag, tag_type};
+use crate::tag_types::{chromaticity, colorant_order, colorant_table, TagData};
+use crate::{colorant_type, CIExyY, CIExyYTripple, Chromaticity, NamedColorList, Profile};
+
+fn xy(x: f64, y: f64) -> CIExyY {
+    CIExyY { x, y, Y: 1.0 }
+}
+
+#[test]
+fn test_chromaticity_round_trips() {
+    let value = Chromaticity {
+        colorant_type: colorant_type::ITU_R_BT709,
+        primaries: CIExyYTripple {
+            red: xy(0.64, 0.33),
+            green: xy(0.3, 0.6),
+            blue: xy(0.15, 0.06),
+        },
+    };
+    let mut io = VecIo::new();
+
+    chromaticity::write(&mut io, &value).unwrap();
+    let buf = io.data();
+    assert_eq!(4 + 3 * 8, buf.len());
+
+    let result = chromaticity::read(&mut MemoryIo::new(buf), buf.len() as u32).unwrap();
+    assert_eq!(colorant_type::ITU_R_BT709, result.colorant_type);
+    let pairs = [
+        (value.primaries.red, result.primaries.red),
+        (value.primaries.green, result.primaries.green),
+        (value.primaries.blue, result.primaries.blue),
+    ];
+    for (expected, actual) in pairs.iter() {
+        assert!((expected.x - actual.x).abs() < 1e-4);
+        assert!((expected.y - actual.y).abs() < 1e-4);
+        assert_eq!(1.0, actual.Y);
+    }
+}
+
+#[test]
+fn test_chromaticity_recovers_from_lcms1_bug() {
+    let mut data = vec![0, 0, 0, 0, 0, 3, 0, 0];
+    data.extend_from_slice(&[0, 1, 0, 0].repeat(6));
+
+    let result = chromaticity::read(&mut MemoryIo::new(&data), 32).unwrap();
+    assert_eq!(1.0, result.primaries.blue.y);
+
+    let data = [0, 4, 0, 0];
+    assert!(chromaticity::read(&mut MemoryIo::new(&data), 4).is_err());
+}
+
+#[test]
+fn test_colorant_order_round_trips() {
+    let value = vec![3u8, 0, 1, 2, 5, 4];
+    let mut io = VecIo::new();
+
+    colorant_order::write(&mut io, &value).unwrap();
+    let buf = io.data();
+    assert_eq!(&[0, 0, 0, 6, 3, 0, 1, 2, 5, 4], buf);
+    assert_eq!(
+        value,
+        colorant_order::read(&mut MemoryIo::new(buf), buf.len() as u32).unwrap()
+    );
+
+    assert!(colorant_order::write(&mut VecIo::new(), &[0; 17]).is_err());
+}
+
+fn hexachrome() -> NamedColorList {
+    let mut list = NamedColorList::new(0, "", "").unwrap();
+    list.append("Cyan", [0x9c00, 0x5800, 0x4a00], &[]);
+    list.append("Magenta", [0x7c00, 0xc900, 0x7d00], &[]);
+    list.append("Yellow", [0xe600, 0x7b00, 0xe100], &[]);
+    list.append("Black", [0x1000, 0x8000, 0x8000], &[]);
+    list.append("Orange", [0xa800, 0xb000, 0xd000], &[]);
+    list.append("Green", [0x8c00, 0x1c00, 0xa600], &[]);
+    list
+}
+
+#[test]
+fn test_colorant_table_round_trips() {
+    let mut io = VecIo::new();
+
+    colorant_table::write(&mut io, &hexachrome()).unwrap();
+    let buf = io.data();
+    assert_eq!(4 + 6 * 38, buf.len());
+
+    let result = colorant_table::read(&mut MemoryIo::new(buf), buf.len() as u32).unwrap();
+    assert_eq!(hexachrome(), result);
+    assert_eq!("Orange", result.colors()[4].name);
+}
+
+#[test]
+fn test_colorant_table_needs_list_without_device_values() {
+    let mut profile = Profile::new();
+    let list = NamedColorList::new(1, "", "").unwrap();
+
+    assert!(profile
+        .write_tag(tag::COLORANT_TABLE, TagData::NamedColorList(list))
+        .is_err());
+}
+
+#[test]
+fn test_colorant_tags_in_profile() {
+    let mut profile = Profile::new();
+    profile.device_class = profile_class::OUTPUT;
+    profile.color_space = color_space::MCH6;
+    profile.pcs = color_space::LAB;
+    profile
+        .write_tag(tag::COLORANT_TABLE, TagData::NamedColorList(hexachrome()))
+        .unwrap();
+    profile
+        .write_tag(
+            tag::COLORANT_TABLE_OUT,
+            TagData::NamedColorList(hexachrome()),
+        )
+        .unwrap();
+    profile
+        .write_tag(
+            tag::COLORANT_ORDER,
+            TagData::ColorantOrder(vec![0, 1, 2, 3, 4, 5]),
+        )
+        .unwrap();
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    assert_eq!(
+        Some(tag_type::COLORANT_TABLE),
+        result.tag_type(tag::COLORANT_TABLE_OUT)
+    );
+    assert_eq!(
+        TagData::NamedColorList(hexachrome()),
+        result.read_tag(tag::COLORANT_TABLE).unwrap()
+    );
+    assert_eq!(
+        TagData::ColorantOrder(vec![0, 1, 2, 3, 4, 5]),
+        result.read_tag(tag::COLORANT_ORDER).unwrap()
+    );
+}
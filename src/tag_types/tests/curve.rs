@@ -0,0 +1,69 @@
+use crate::plugin::VecIo;
+use crate::tag_types::{curve, parametric_curve};
+use crate::ToneCurve;
+
+#[test]
+fn test_curve_type_keeps_single_gamma() {
+    let mut buf = Vec::new();
+
+    curve::write(&mut buf, &ToneCurve::build_gamma(2.2).unwrap()).unwrap();
+    assert_eq!(vec![0, 0, 0, 1, 0x02, 0x33], buf);
+
+    let result = curve::read(&mut buf.as_slice(), buf.len() as u32).unwrap();
+    assert_eq!(Some(1), result.parametric_type());
+    assert!((result.params().unwrap()[0] - 2.2).abs() < 1.0 / 256.0);
+}
+
+#[test]
+fn test_curve_type_without_entries_is_linear() {
+    let buf = [0u8; 4];
+
+    let result = curve::read(&mut &buf[..], 4).unwrap();
+
+    assert!(result.is_linear());
+}
+
+#[test]
+fn test_curve_type_round_trips_table() {
+    let table = [0u16, 0x1000, 0x4000, 0xffff];
+    let mut buf = Vec::new();
+
+    curve::write(&mut buf, &ToneCurve::from_table16(&table).unwrap()).unwrap();
+    let result = curve::read(&mut buf.as_slice(), buf.len() as u32).unwrap();
+
+    assert_eq!(&table[..], result.table16());
+}
+
+#[test]
+fn test_parametric_curve_type_round_trips() {
+    let params = [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045];
+    let srgb = ToneCurve::build_parametric(4, &params).unwrap();
+    let mut io = VecIo::new();
+
+    parametric_curve::write(&mut io, &srgb).unwrap();
+    // Function type, reserved and five parameters
+    assert_eq!(4 + 5 * 4, io.data().len());
+    assert_eq!(&[0, 3], &io.data()[..2]);
+
+    let result = parametric_curve::read(&mut io.data(), 24).unwrap();
+    assert_eq!(Some(4), result.parametric_type());
+    for (expected, actual) in params.iter().zip(result.params().unwrap()) {
+        assert!((expected - actual).abs() < 1.0 / 65536.0);
+    }
+}
+
+#[test]
+fn test_parametric_curve_type_rejects_inverses_and_tables() {
+    let inverse = ToneCurve::build_parametric(-1, &[2.2]).unwrap();
+
+    assert!(!parametric_curve::fits(&inverse));
+    assert!(parametric_curve::write(&mut Vec::new(), &inverse).is_err());
+    assert!(parametric_curve::write(&mut Vec::new(), &ToneCurve::linear()).is_err());
+}
+
+#[test]
+fn test_parametric_curve_type_rejects_unknown_function() {
+    let buf = [0u8, 5, 0, 0];
+
+    assert!(parametric_curve::read(&mut &buf[..], 4).is_err());
+}
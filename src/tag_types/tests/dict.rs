@@ -0,0 +1,150 @@
+use std::io::Write;
+
+use crate::plugin::{IoHandler, MemoryIo, TagBase, VecIo};
+use crate::signatures::{tag, tag_type};
+use crate::tag_types::{dict, mluc, TagData};
+use crate::{Dict, Mlu, Profile};
+
+fn write_dict(value: &Dict) -> VecIo {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    dict::write(&mut io, value).unwrap();
+    io
+}
+
+fn read_dict(data: &[u8]) -> std::io::Result<Dict> {
+    let mut io = MemoryIo::new(data);
+    io.seek(8).unwrap();
+    dict::read(&mut io, data.len() as u32 - 8)
+}
+
+#[test]
+fn test_dict_round_trips() {
+    let mut display_name = Mlu::new();
+    display_name.set("en", "US", "Press condition");
+    display_name.set("de", "DE", "Druckbedingung");
+
+    let mut value = Dict::new();
+    value.add("press", Some("FOGRA51"), Some(display_name), None);
+    value.add(
+        "batch",
+        Some("2024-117"),
+        None,
+        Some(Mlu::from("Batch 117")),
+    );
+    value.add("empty", None, None, None);
+
+    let io = write_dict(&value);
+    // Display values need the longest records
+    assert_eq!(&[0, 0, 0, 32], &io.data()[12..16]);
+
+    let result = read_dict(io.data()).unwrap();
+    assert_eq!(value, result);
+    assert_eq!(Some("FOGRA51"), result.value("press"));
+    assert_eq!(None, result.value("empty"));
+    assert_eq!(
+        Some("Druckbedingung".to_string()),
+        result
+            .get("press")
+            .unwrap()
+            .display_name
+            .as_ref()
+            .unwrap()
+            .get("de", "DE")
+    );
+}
+
+#[test]
+fn test_dict_uses_short_records_without_display_strings() {
+    let mut value = Dict::new();
+    value.add("a", Some("b"), None, None);
+
+    let io = write_dict(&value);
+    assert_eq!(&[0, 0, 0, 16], &io.data()[12..16]);
+    // Header, one record and both strings
+    assert_eq!(8 + 8 + 16 + 4, io.data().len());
+    assert_eq!(value, read_dict(io.data()).unwrap());
+}
+
+#[test]
+fn test_dict_reads_display_strings_with_type_base() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    io.write_all(&[0, 0, 0, 1, 0, 0, 0, 24]).unwrap();
+    // Name at 40, display name at 42
+    io.write_all(&[0, 0, 0, 40, 0, 0, 0, 2]).unwrap();
+    io.write_all(&[0u8; 8]).unwrap();
+    io.write_all(&[0, 0, 0, 42]).unwrap();
+    io.write_all(&[0, 0, 0, 0]).unwrap();
+    io.write_all(&[0, b'x']).unwrap();
+    TagBase::from(u32::from(tag_type::MULTI_LOCALIZED_UNICODE))
+        .write(&mut io)
+        .unwrap();
+    mluc::write(&mut io, &Mlu::from("Ex")).unwrap();
+    let size = (io.data().len() - 42) as u8;
+    let mut data = io.data().to_vec();
+    data[39] = size;
+
+    let result = read_dict(&data).unwrap();
+    assert_eq!(
+        Some(&Mlu::from("Ex")),
+        result.get("x").unwrap().display_name.as_ref()
+    );
+}
+
+#[test]
+fn test_dict_rejects_display_strings_larger_than_the_tag() {
+    // Display name without type base at 42, claiming 4 GB and then just past the end
+    for size in [[0xff; 4], [0, 0, 0, 7]] {
+        let mut io = VecIo::new();
+        io.write_all(&[0u8; 8]).unwrap();
+        io.write_all(&[0, 0, 0, 1, 0, 0, 0, 24]).unwrap();
+        io.write_all(&[0, 0, 0, 40, 0, 0, 0, 2]).unwrap();
+        io.write_all(&[0u8; 8]).unwrap();
+        io.write_all(&[0, 0, 0, 42]).unwrap();
+        io.write_all(&size).unwrap();
+        io.write_all(&[0, b'x', 0, 0, 0, 0]).unwrap();
+
+        let error = read_dict(io.data()).unwrap_err();
+        assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+    }
+}
+
+#[test]
+fn test_dict_rejects_unknown_record_length() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    io.write_all(&[0, 0, 0, 0, 0, 0, 0, 20]).unwrap();
+
+    assert!(read_dict(io.data()).is_err());
+}
+
+#[test]
+fn test_dict_rejects_strings_larger_than_the_tag() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    io.write_all(&[0, 0, 0, 1, 0, 0, 0, 16]).unwrap();
+    // Name at the end of the tag, claiming almost 4 GB
+    io.write_all(&[0, 0, 0, 32, 0xff, 0xff, 0xff, 0xf0])
+        .unwrap();
+    io.write_all(&[0u8; 8]).unwrap();
+    io.write_all(&[0, b'a']).unwrap();
+
+    let error = read_dict(io.data()).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+}
+
+#[test]
+fn test_meta_tag_holds_dict() {
+    let mut value = Dict::new();
+    value.add("measurement", Some("batch 7"), None, None);
+
+    let mut profile = Profile::new();
+    profile
+        .write_tag(tag::META, TagData::Dict(value.clone()))
+        .unwrap();
+    let profile = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    assert_eq!(Some(tag_type::DICT), profile.tag_type(tag::META));
+    assert_eq!(TagData::Dict(value), profile.read_tag(tag::META).unwrap());
+}
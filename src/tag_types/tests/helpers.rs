@@ -0,0 +1,18 @@
+use std::io::Write;
+
+use crate::plugin::VecIo;
+
+/// Bytes written by `write`
+pub(super) fn encode<F: FnOnce(&mut VecIo)>(write: F) -> Vec<u8> {
+    let mut io = VecIo::new();
+    write(&mut io);
+    io.data().to_vec()
+}
+
+/// Bytes written by `write` after room for the type base, which offsets inside tags count from
+pub(super) fn encode_after_base<F: FnOnce(&mut VecIo)>(write: F) -> Vec<u8> {
+    encode(|io| {
+        io.write_all(&[0u8; 8]).unwrap();
+        write(io);
+    })
+}
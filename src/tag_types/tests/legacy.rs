@@ -0,0 +1,225 @@
+use std::io::ErrorKind;
+
+use super::helpers::encode;
+use crate::plugin::{MemoryIo, VecIo};
+use crate::signatures::{self, tag, tag_type};
+use crate::tag_types::*;
+use crate::{
+    screening_flags, spot_shape, DevicePlatform, DeviceSetting, DeviceSettings, Mlu, Profile,
+    Response16, ResponseCurve, ResponseCurveSet, Screening, ScreeningChannel, Signature, ToneCurve,
+    UcrBg, CIEXYZ,
+};
+
+#[test]
+fn test_screening_round_trips() {
+    let value = Screening {
+        flag: screening_flags::FREQUENCY_UNITS_LPCM,
+        channels: vec![
+            ScreeningChannel {
+                frequency: 60.0,
+                screen_angle: 15.0,
+                spot_shape: spot_shape::ROUND,
+            },
+            ScreeningChannel {
+                frequency: 60.0,
+                screen_angle: 75.5,
+                spot_shape: spot_shape::ELLIPSE,
+            },
+        ],
+    };
+
+    let data = encode(|io| screening::write(io, &value).unwrap());
+    assert_eq!(8 + 2 * 12, data.len());
+    let result = screening::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    assert_eq!(value, result);
+}
+
+#[test]
+fn test_ucr_bg_round_trips() {
+    let value = UcrBg {
+        ucr: ToneCurve::from_table16(&[0, 0x4000, 0x8000]).unwrap(),
+        bg: ToneCurve::from_table16(&[50]).unwrap(),
+        desc: Mlu::from("GCR medium"),
+    };
+
+    let data = encode(|io| ucr_bg::write(io, &value).unwrap());
+    assert_eq!(4 + 6 + 4 + 2 + 11, data.len());
+    let result = ucr_bg::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    assert_eq!(value, result);
+
+    // The curves can't take more than the tag holds
+    assert!(ucr_bg::read(&mut MemoryIo::new(&data), 12).is_err());
+}
+
+#[test]
+fn test_crd_info_round_trips() {
+    let mut value = Mlu::new();
+    value.set("PS", "nm", "Printer");
+    value.set("PS", "#0", "Perceptual CRD");
+    value.set("PS", "#1", "Colorimetric CRD");
+    value.set("PS", "#2", "Saturation CRD");
+    value.set("PS", "#3", "Absolute CRD");
+
+    let data = encode(|io| crd_info::write(io, &value).unwrap());
+    assert_eq!(&[0, 0, 0, 8], &data[..4]);
+    assert_eq!(b"Printer\0", &data[4..12]);
+
+    let result = crd_info::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    assert_eq!(Some("Saturation CRD".to_string()), result.get("PS", "#2"));
+    assert_eq!(value, result);
+}
+
+#[test]
+fn test_device_settings_round_trip() {
+    let resolution = DeviceSetting {
+        id: Signature::new(b"rsln"),
+        value_size: 8,
+        data: vec![0, 0, 2, 0xd0, 0, 0, 2, 0xd0, 0, 0, 5, 0xa0, 0, 0, 5, 0xa0],
+    };
+    let media = DeviceSetting {
+        id: Signature::new(b"mdia"),
+        value_size: 4,
+        data: vec![0, 0, 0, 1],
+    };
+    let value = DeviceSettings {
+        platforms: vec![DevicePlatform {
+            platform: signatures::platform::MICROSOFT,
+            combinations: vec![vec![resolution.clone(), media], vec![resolution]],
+        }],
+    };
+
+    let data = encode(|io| device_settings::write(io, &value).unwrap());
+    // The platform entry and its combinations have their whole size
+    assert_eq!(&[0, 0, 0, 100], &data[8..12]);
+    assert_eq!(&[0, 0, 0, 52], &data[16..20]);
+    assert_eq!(4 + 100, data.len());
+
+    let result = device_settings::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    assert_eq!(value, result);
+    let combination = &result.platforms[0].combinations[0];
+    assert_eq!(2, combination[0].values().count());
+}
+
+fn response_curves() -> ResponseCurveSet {
+    let curve = |unit: Signature, scale: f64| ResponseCurve {
+        measurement_unit: unit,
+        max_colorant_pcs: vec![
+            CIEXYZ {
+                X: 0.15,
+                Y: 0.2,
+                Z: 0.5,
+            },
+            CIEXYZ {
+                X: 0.4,
+                Y: 0.2,
+                Z: 0.2,
+            },
+        ],
+        responses: vec![
+            vec![
+                Response16 {
+                    device: 0,
+                    measurement: 0.0,
+                },
+                Response16 {
+                    device: 0xffff,
+                    measurement: 1.5 * scale,
+                },
+            ],
+            vec![Response16 {
+                device: 0x8000,
+                measurement: 0.75 * scale,
+            }],
+        ],
+    };
+
+    ResponseCurveSet {
+        channels: 2,
+        curves: vec![
+            curve(signatures::STATUS_T, 1.0),
+            curve(signatures::DNN, 0.5),
+        ],
+    }
+}
+
+#[test]
+fn test_response_curve_set_round_trips() {
+    let value = response_curves();
+
+    let data = encode(|io| response_curve_set16::write(io, &value).unwrap());
+    // Channels, count and offsets, then the curves
+    assert_eq!(&[0, 0, 0, 12], &data[4..8]);
+    let result = response_curve_set16::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    assert_eq!(value.channels, result.channels);
+
+    let status_t = result.curve(signatures::STATUS_T).unwrap();
+    assert_eq!(1.5, status_t.responses[0][1].measurement);
+    assert_eq!(
+        0.375,
+        result.curve(signatures::DNN).unwrap().responses[1][0].measurement
+    );
+    assert!(result.curve(signatures::STATUS_A).is_none());
+    for (expected, actual) in value.curves.iter().zip(result.curves.iter()) {
+        assert_eq!(expected.responses, actual.responses);
+        for (e, a) in expected
+            .max_colorant_pcs
+            .iter()
+            .zip(actual.max_colorant_pcs.iter())
+        {
+            assert!(
+                (e.X - a.X).abs() < 1e-4 && (e.Y - a.Y).abs() < 1e-4 && (e.Z - a.Z).abs() < 1e-4
+            );
+        }
+    }
+}
+
+#[test]
+fn test_response_curve_set_needs_curves_for_each_channel() {
+    let mut value = response_curves();
+    value.curves[1].responses.pop();
+
+    assert!(response_curve_set16::write(&mut VecIo::new(), &value).is_err());
+}
+
+#[test]
+fn test_response_curve_set_rejects_too_many_channels() {
+    let data = [0u8, 17, 0, 0];
+
+    let err = response_curve_set16::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidData, err.kind());
+}
+
+#[test]
+fn test_legacy_tags_in_profile() {
+    let mut profile = Profile::new();
+    profile.set_version(2.1);
+    profile
+        .write_tag(
+            tag::OUTPUT_RESPONSE,
+            TagData::ResponseCurveSet(response_curves()),
+        )
+        .unwrap();
+    profile
+        .write_tag(tag::SCREENING, TagData::Screening(Screening::default()))
+        .unwrap();
+    profile
+        .write_tag(tag::CRD_INFO, TagData::Mlu(Mlu::new()))
+        .unwrap();
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    assert_eq!(
+        Some(tag_type::RESPONSE_CURVE_SET16),
+        result.tag_type(tag::OUTPUT_RESPONSE)
+    );
+    match result.read_tag(tag::OUTPUT_RESPONSE).unwrap() {
+        TagData::ResponseCurveSet(value) => assert_eq!(2, value.curves.len()),
+        other => panic!("Unexpected tag data {:?}", other),
+    }
+    assert_eq!(
+        TagData::Screening(Screening::default()),
+        result.read_tag(tag::SCREENING).unwrap()
+    );
+    assert_eq!(Some(tag_type::CRD_INFO), result.tag_type(tag::CRD_INFO));
+}
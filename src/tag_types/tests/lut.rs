@@ -0,0 +1,269 @@
+use std::io::{ErrorKind, Write};
+
+use crate::pipeline::{Pipeline, Stage, StageData, StageLoc};
+use crate::plugin::{IoHandler, MemoryIo, VecIo};
+use crate::tag_types::{lut16, lut8, lut_a_to_b, lut_b_to_a};
+use crate::ToneCurve;
+
+fn sample_pipeline() -> Pipeline {
+    let mut result = Pipeline::new(3, 3).unwrap();
+    let table = (0..2 * 2 * 2 * 3)
+        .map(|i| (i * 2719) as u16)
+        .collect::<Vec<_>>();
+    let curve =
+        ToneCurve::from_table16(&(0..256).map(|i| (i * 257) as u16).collect::<Vec<_>>()).unwrap();
+
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_matrix(3, 3, &[0.5, 0.25, 0.25, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0], None).unwrap(),
+    );
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_tone_curves(vec![curve; 3]).unwrap(),
+    );
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_clut16(2, 3, 3, Some(&table)).unwrap(),
+    );
+    result
+}
+
+#[test]
+fn test_lut16_round_trips() {
+    let pipeline = sample_pipeline();
+    let mut buf = Vec::new();
+
+    lut16::write(&mut buf, &pipeline).unwrap();
+    // Header, matrix, entry counts, 256 entry input tables, CLUT and linear output tables
+    assert_eq!(4 + 36 + 4 + 3 * 256 * 2 + 24 * 2 + 3 * 2 * 2, buf.len());
+
+    let result = lut16::read(&mut buf.as_slice(), buf.len() as u32).unwrap();
+    assert_eq!(4, result.stage_count());
+    assert_eq!(&pipeline.stages()[..3], &result.stages()[..3]);
+    assert!(matches!(result.stages()[3].data(), StageData::Curves(c) if c[0].is_linear()));
+}
+
+#[test]
+fn test_lut16_identity_matrix_is_skipped() {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap());
+    let mut buf = Vec::new();
+
+    lut16::write(&mut buf, &pipeline).unwrap();
+    let result = lut16::read(&mut buf.as_slice(), buf.len() as u32).unwrap();
+
+    // Input and output curves only
+    assert_eq!(2, result.stage_count());
+}
+
+#[test]
+fn test_lut8_round_trips_8_bit_values() {
+    let pipeline = sample_pipeline();
+    let mut buf = Vec::new();
+
+    lut8::write(&mut buf, &pipeline).unwrap();
+    assert_eq!(4 + 36 + 3 * 256 + 24 + 3 * 256, buf.len());
+
+    let result = lut8::read(&mut buf.as_slice(), buf.len() as u32).unwrap();
+    let mut expected = [0u16; 3];
+    let mut actual = [0u16; 3];
+    for input in [
+        [0u16, 0, 0],
+        [0x4000, 0x8000, 0xc000],
+        [0xffff, 0xffff, 0xffff],
+    ]
+    .iter()
+    {
+        pipeline.eval_16(input, &mut expected);
+        result.eval_16(input, &mut actual);
+        for i in 0..3 {
+            assert!((expected[i] as i32 - actual[i] as i32).abs() <= 0x100);
+        }
+    }
+}
+
+#[test]
+fn test_lut8_needs_256_entries() {
+    let mut pipeline = Pipeline::new(1, 1).unwrap();
+    let curve = ToneCurve::from_table16(&[0, 0x4000, 0xffff]).unwrap();
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_tone_curves(vec![curve]).unwrap(),
+    );
+
+    let err = lut8::write(&mut Vec::new(), &pipeline).unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidInput, err.kind());
+}
+
+#[test]
+fn test_lut16_rejects_unsuitable_pipelines() {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_clut_float(2, 3, 3, None).unwrap(),
+    );
+
+    assert!(lut16::write(&mut Vec::new(), &pipeline).is_err());
+
+    let mut pipeline = sample_pipeline();
+    pipeline.insert_stage(StageLoc::AtEnd, Stage::new_lab_v2_to_v4());
+    assert!(lut16::write(&mut Vec::new(), &pipeline).is_err());
+}
+
+#[test]
+fn test_lut16_rejects_single_grid_point() {
+    let buf = [3u8, 3, 1, 0];
+
+    let err = lut16::read(&mut &buf[..], 4).unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidData, err.kind());
+}
+
+fn a_to_b_pipeline() -> Pipeline {
+    let srgb =
+        ToneCurve::build_parametric(4, &[2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045])
+            .unwrap();
+    let table = (0..3 * 3 * 2 * 3)
+        .map(|i| (i * 1213) as u16)
+        .collect::<Vec<_>>();
+    let mut result = Pipeline::new(3, 3).unwrap();
+
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_tone_curves(vec![srgb; 3]).unwrap(),
+    );
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_clut16_granular(&[3, 3, 2], 3, Some(&table)).unwrap(),
+    );
+    result.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap());
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_matrix(
+            3,
+            3,
+            &[0.5, 0.25, 0.25, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+            Some(&[0.125, 0.0, -0.5]),
+        )
+        .unwrap(),
+    );
+    result.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_tone_curves(vec![ToneCurve::build_gamma(1.8).unwrap(); 3]).unwrap(),
+    );
+    result
+}
+
+#[test]
+fn test_lut_a_to_b_round_trips_all_elements() {
+    let pipeline = a_to_b_pipeline();
+    let mut io = VecIo::new();
+    // Positions are relative to the start of the tag, type base included
+    io.write_all(&[0u8; 8]).unwrap();
+
+    lut_a_to_b::write(&mut io, &pipeline).unwrap();
+    assert_eq!(0, io.data().len() % 4);
+
+    let mut io = MemoryIo::new(io.data());
+    io.seek(8).unwrap();
+    let result = lut_a_to_b::read(&mut io, 0).unwrap();
+
+    assert_eq!(5, result.stage_count());
+    assert_eq!(pipeline.stages()[1], result.stages()[1]);
+    assert!(
+        matches!(result.stages()[0].data(), StageData::Curves(c) if c[0].parametric_type() == Some(4))
+    );
+    assert!(
+        matches!(result.stages()[4].data(), StageData::Curves(c) if c[0].parametric_type() == Some(1))
+    );
+    match result.stages()[3].data() {
+        StageData::Matrix { offset, .. } => assert_eq!(&Some(vec![0.125, 0.0, -0.5]), offset),
+        _ => panic!("not a matrix"),
+    }
+}
+
+#[test]
+fn test_lut_b_to_a_round_trips_8_bit_clut() {
+    let table = (0..2 * 2 * 2 * 4)
+        .map(|i| (i * 2000) as u16)
+        .collect::<Vec<_>>();
+    let mut pipeline = Pipeline::new(3, 4).unwrap();
+    pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap());
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_clut16(2, 3, 4, Some(&table)).unwrap(),
+    );
+    pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(4).unwrap());
+    pipeline.set_save_as_8_bits(true);
+
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    lut_b_to_a::write(&mut io, &pipeline).unwrap();
+
+    let mut io = MemoryIo::new(io.data());
+    io.seek(8).unwrap();
+    let result = lut_b_to_a::read(&mut io, 0).unwrap();
+
+    assert_eq!(3, result.input_channels());
+    assert_eq!(4, result.output_channels());
+    match result.stages()[1].data() {
+        StageData::CLut16(params) => {
+            for (expected, actual) in table.iter().zip(params.table.iter()) {
+                assert!((*expected as i32 - *actual as i32).abs() <= 0x80);
+            }
+        }
+        _ => panic!("not a CLUT"),
+    }
+}
+
+#[test]
+fn test_lut_a_to_b_rejects_unsuitable_pipelines() {
+    // lut16 layout: matrix first
+    let mut io = VecIo::new();
+
+    assert_eq!(
+        ErrorKind::InvalidInput,
+        lut_a_to_b::write(&mut io, &sample_pipeline())
+            .unwrap_err()
+            .kind()
+    );
+    assert!(lut_b_to_a::write(&mut io, &a_to_b_pipeline()).is_err());
+}
+
+#[test]
+fn test_lut_cluts_larger_than_the_tag_are_rejected() {
+    // 7 inputs, 16 outputs and 255 grid points, with an identity matrix
+    let mut buf = vec![7u8, 16, 255, 0];
+    for i in 0..9 {
+        let one = if i % 4 == 0 { 1u8 } else { 0 };
+        buf.extend([0, one, 0, 0]);
+    }
+
+    let mut lut16_buf = buf.clone();
+    lut16_buf.extend([0, 2, 0, 2]);
+    lut16_buf.extend([0, 0, 0xff, 0xff].repeat(7));
+    let err = lut16::read(&mut lut16_buf.as_slice(), lut16_buf.len() as u32).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, err.kind());
+
+    let mut lut8_buf = buf;
+    lut8_buf.extend((0..=255u8).cycle().take(7 * 256));
+    let err = lut8::read(&mut lut8_buf.as_slice(), lut8_buf.len() as u32).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, err.kind());
+}
+
+#[test]
+fn test_lut_a_to_b_clut_larger_than_the_tag_is_rejected() {
+    // Type base, 7 inputs, 15 outputs and only a CLUT right after the header
+    let mut data = vec![0u8; 8];
+    data.extend([7, 15, 0, 0]);
+    data.extend([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 32, 0, 0, 0, 0]);
+    data.extend([255; 7]);
+    data.extend([0; 9]);
+    data.extend([2, 0, 0, 0]);
+
+    let mut io = MemoryIo::new(&data);
+    io.seek(8).unwrap();
+    let err = lut_a_to_b::read(&mut io, data.len() as u32 - 8).unwrap_err();
+    assert_eq!(ErrorKind::InvalidData, err.kind());
+}
@@ -0,0 +1,93 @@
+use crate::plugin::{MemoryIo, VecIo};
+use crate::tag_types::{measurement, viewing_conditions};
+use crate::{
+    illuminant_type, ICCMeasurementConditions, ICCViewingConditions, IlluminantType,
+    MeasurementGeometry, StandardObserver, CIEXYZ,
+};
+
+#[test]
+fn test_measurement_round_trips() {
+    let value = ICCMeasurementConditions {
+        observer: StandardObserver::Cie1931,
+        backing: CIEXYZ {
+            X: 0.5,
+            Y: 0.25,
+            Z: 0.125,
+        },
+        geometry: MeasurementGeometry::ZeroFortyFive,
+        flare: 0.01,
+        illuminant_type: IlluminantType::D50,
+    };
+    let mut io = VecIo::new();
+
+    measurement::write(&mut io, &value).unwrap();
+    let buf = io.data();
+    assert_eq!(28, buf.len());
+    assert_eq!(&[0, 0, 0, 1], &buf[..4]);
+    assert_eq!(&[0, 0, 0, 1], &buf[24..]);
+
+    let result = measurement::read(&mut MemoryIo::new(buf), buf.len() as u32).unwrap();
+    assert_eq!(value.observer, result.observer);
+    assert_eq!(value.backing, result.backing);
+    assert_eq!(value.geometry, result.geometry);
+    assert!((value.flare - result.flare).abs() < 1e-4);
+    assert_eq!(value.illuminant_type, result.illuminant_type);
+}
+
+#[test]
+fn test_measurement_keeps_unknown_values() {
+    let mut buf = [0u8; 28];
+    buf[19] = 3;
+    buf[27] = 9;
+
+    let result = measurement::read(&mut MemoryIo::new(&buf), 28).unwrap();
+    assert_eq!(MeasurementGeometry::Other(3), result.geometry);
+    assert_eq!(IlluminantType::Other(9), result.illuminant_type);
+    assert_eq!(StandardObserver::Unknown, result.observer);
+
+    let mut io = VecIo::new();
+    measurement::write(&mut io, &result).unwrap();
+    assert_eq!(&buf[..], io.data());
+
+    buf[19] = 2;
+    let result = measurement::read(&mut MemoryIo::new(&buf), 28).unwrap();
+    assert_eq!(MeasurementGeometry::ZeroDiffuse, result.geometry);
+}
+
+#[test]
+fn test_illuminant_types_match_their_constants() {
+    assert_eq!(
+        IlluminantType::D65,
+        IlluminantType::from(illuminant_type::D65)
+    );
+    assert_eq!(illuminant_type::F8, u32::from(IlluminantType::F8));
+    assert_eq!(
+        illuminant_type::UNKNOWN,
+        u32::from(IlluminantType::default())
+    );
+}
+
+#[test]
+fn test_viewing_conditions_round_trips() {
+    let value = ICCViewingConditions {
+        illuminant_xyz: CIEXYZ {
+            X: 76.0,
+            Y: 80.0,
+            Z: 62.5,
+        },
+        surround_xyz: CIEXYZ {
+            X: 15.25,
+            Y: 16.0,
+            Z: 13.5,
+        },
+        illuminant_type: IlluminantType::F8,
+    };
+    let mut io = VecIo::new();
+
+    viewing_conditions::write(&mut io, &value).unwrap();
+    let buf = io.data();
+    assert_eq!(28, buf.len());
+
+    let result = viewing_conditions::read(&mut MemoryIo::new(buf), buf.len() as u32).unwrap();
+    assert_eq!(value, result);
+}
@@ -0,0 +1,118 @@
+use super::helpers::encode_after_base;
+use crate::plugin::{IoHandler, MemoryIo};
+use crate::tag_types::{mluc, text, text_description};
+use crate::{Mlu, NO_COUNTRY, NO_LANGUAGE};
+
+#[test]
+fn test_mlu_lookup_falls_back() {
+    let mut mlu = Mlu::new();
+    mlu.set("en", "US", "Color");
+    mlu.set("en", "GB", "Colour");
+    mlu.set("fr", "FR", "Couleur");
+
+    assert_eq!(Some("Colour".to_string()), mlu.get("en", "GB"));
+    assert_eq!(Some("Color".to_string()), mlu.get("en", "AU"));
+    assert_eq!(Some("Color".to_string()), mlu.get("ja", "JP"));
+    assert_eq!(
+        Some(("fr".to_string(), "FR".to_string())),
+        mlu.translation_codes("fr", "CA")
+    );
+    assert_eq!(3, mlu.translations().count());
+    assert_eq!(None, Mlu::new().get("en", "US"));
+}
+
+#[test]
+fn test_mlu_set_replaces() {
+    let mut mlu = Mlu::from("first");
+    mlu.set(NO_LANGUAGE, NO_COUNTRY, "second");
+
+    assert_eq!(1, mlu.len());
+    assert_eq!(Some("second".to_string()), mlu.get("en", "US"));
+}
+
+#[test]
+fn test_mlu_ascii_replaces_non_ascii() {
+    let mlu = Mlu::from("Größe");
+
+    assert_eq!(Some("Gr??e".to_string()), mlu.get_ascii("de", "DE"));
+}
+
+#[test]
+fn test_mluc_round_trip() {
+    let mut mlu = Mlu::new();
+    mlu.set("en", "US", "Hello");
+    mlu.set("ja", "JP", "こんにちは");
+    mlu.set("de", "DE", "");
+
+    let data = encode_after_base(|io| mluc::write(io, &mlu).unwrap());
+    assert_eq!(&[0x65, 0x6E, 0x55, 0x53], &data[16..20]);
+
+    let mut io = MemoryIo::new(&data);
+    io.seek(8).unwrap();
+    let result = mluc::read(&mut io, data.len() as u32 - 8).unwrap();
+
+    assert_eq!(mlu, result);
+}
+
+#[test]
+fn test_mluc_rejects_offsets_outside_tag() {
+    let mlu = Mlu::from("Hello");
+    let mut data = encode_after_base(|io| mluc::write(io, &mlu).unwrap());
+    data[27] = 0xF0;
+
+    let mut io = MemoryIo::new(&data);
+    io.seek(8).unwrap();
+    assert!(mluc::read(&mut io, data.len() as u32 - 8).is_err());
+}
+
+#[test]
+fn test_text_description_round_trip_is_aligned() {
+    let mlu = Mlu::from("sRGB IEC61966-2.1");
+
+    let data = encode_after_base(|io| text_description::write(io, &mlu).unwrap());
+    assert_eq!(0, data.len() % 4);
+
+    let mut io = MemoryIo::new(&data);
+    io.seek(8).unwrap();
+    let result = text_description::read(&mut io, data.len() as u32 - 8).unwrap();
+    assert_eq!(mlu, result);
+}
+
+#[test]
+fn test_text_description_keeps_unicode() {
+    let mlu = Mlu::from("Caf\u{e9} \u{20ac}");
+
+    let data = encode_after_base(|io| text_description::write(io, &mlu).unwrap());
+
+    assert_eq!(b"Caf? ?\0", &data[12..19]);
+    assert_eq!([0, 0, 0, 7], data[23..27]);
+    let wide: Vec<u16> = data[27..41]
+        .chunks_exact(2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(
+        "Caf\u{e9} \u{20ac}\0".encode_utf16().collect::<Vec<_>>(),
+        wide
+    );
+}
+
+#[test]
+fn test_text_description_reads_unicode_back() {
+    let mlu = Mlu::from("Caf\u{e9} \u{20ac}");
+
+    let data = encode_after_base(|io| text_description::write(io, &mlu).unwrap());
+
+    let mut io = MemoryIo::new(&data);
+    io.seek(8).unwrap();
+    let result = text_description::read(&mut io, data.len() as u32 - 8).unwrap();
+    assert_eq!(mlu, result);
+}
+
+#[test]
+fn test_text_stops_at_nul() {
+    let data = b"Copyright\0\0\0";
+
+    let result = text::read(&mut MemoryIo::new(data), data.len() as u32).unwrap();
+
+    assert_eq!(Some("Copyright".to_string()), result.get("en", "US"));
+}
@@ -0,0 +1,14 @@
+mod colorant;
+mod curve;
+mod dict;
+mod helpers;
+mod legacy;
+mod lut;
+mod measurement;
+mod mlu;
+mod multi_process_element;
+mod named_color;
+mod numeric;
+mod profile_sequence;
+mod vcgt;
+mod xyz;
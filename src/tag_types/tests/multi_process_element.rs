@@ -0,0 +1,141 @@
+use std::io::{ErrorKind, Write};
+
+use crate::pipeline::{Pipeline, Stage, StageData, StageLoc};
+use crate::plugin::{IoHandler, MemoryIo, VecIo};
+use crate::tag_types::multi_process_element;
+use crate::{CurveSegment, ToneCurve, MINUS_INF, PLUS_INF};
+
+fn segment(x0: f32, x1: f32, r#type: i32, params: &[f64]) -> CurveSegment {
+    let mut result = CurveSegment {
+        x0,
+        x1,
+        r#type,
+        params: [0.0; 10],
+        n_grid_points: 0,
+        sampled_points: Vec::new(),
+    };
+    result.params[..params.len()].copy_from_slice(params);
+    result
+}
+
+/// Linear below 0, sampled from 0 to 1 and logarithmic above
+fn segmented_curve() -> ToneCurve {
+    let mut sampled = segment(0.0, 1.0, 0, &[]);
+    sampled.n_grid_points = 5;
+    sampled.sampled_points = vec![0.0, 0.1, 0.3, 0.6, 1.0];
+
+    ToneCurve::from_segments(&[
+        segment(MINUS_INF, 0.0, 6, &[1.0, 1.0, 1.0, -1.0]),
+        sampled,
+        segment(1.0, PLUS_INF, 7, &[1.0, 1.0, 10.0, 0.0, 1.0]),
+    ])
+    .unwrap()
+}
+
+fn write(pipeline: &Pipeline) -> Vec<u8> {
+    let mut io = VecIo::new();
+    // Positions are relative to the start of the tag, type base included
+    io.write_all(&[0u8; 8]).unwrap();
+    multi_process_element::write(&mut io, pipeline).unwrap();
+    io.data().to_vec()
+}
+
+fn read(data: &[u8]) -> std::io::Result<Pipeline> {
+    let mut io = MemoryIo::new(data);
+    io.seek(8).unwrap();
+    multi_process_element::read(&mut io, data.len() as u32 - 8)
+}
+
+#[test]
+fn test_mpe_round_trips_all_elements() {
+    let table = (0..2 * 2 * 2 * 3)
+        .map(|i| i as f32 / 23.0)
+        .collect::<Vec<_>>();
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_tone_curves(vec![segmented_curve(); 3]).unwrap(),
+    );
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_matrix(
+            3,
+            3,
+            &[0.5, 0.25, 0.25, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+            Some(&[0.125, 0.0, -0.5]),
+        )
+        .unwrap(),
+    );
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_clut_float(2, 3, 3, Some(&table)).unwrap(),
+    );
+
+    let data = write(&pipeline);
+    assert_eq!(0, data.len() % 4);
+
+    let result = read(&data).unwrap();
+    assert_eq!(pipeline, result);
+
+    // Values out of 0..1 go through the formula segments
+    let mut out = [0f32; 3];
+    result.stages()[0].eval(&[-0.5, 0.5, 2.0], &mut out);
+    for (expected, actual) in [-0.5, 0.3, 20f32.log10() + 1.0].iter().zip(out.iter()) {
+        assert!(
+            (expected - actual).abs() < 1e-6,
+            "{} != {}",
+            expected,
+            actual
+        );
+    }
+}
+
+#[test]
+fn test_mpe_writes_tabulated_curves_as_sampled() {
+    let curve = ToneCurve::from_table16(&[0, 0x4000, 0xffff]).unwrap();
+    let mut pipeline = Pipeline::new(1, 1).unwrap();
+    pipeline.insert_stage(
+        StageLoc::AtEnd,
+        Stage::new_tone_curves(vec![curve]).unwrap(),
+    );
+
+    let result = read(&write(&pipeline)).unwrap();
+
+    let mut out = [0f32];
+    result.eval_float(&[0.25], &mut out);
+    assert!((out[0] - 0x2000 as f32 / 65535.0).abs() < 1e-6);
+    match result.stages()[0].data() {
+        StageData::Curves(curves) => assert_eq!(3, curves[0].segments().len()),
+        _ => panic!("not curves"),
+    }
+}
+
+#[test]
+fn test_mpe_skips_acs_elements() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    for v in &[0x00010001u32, 1, 24, 8] {
+        io.write_all(&v.to_be_bytes()).unwrap();
+    }
+    io.write_all(b"bACS\0\0\0\0").unwrap();
+
+    let result = read(io.data()).unwrap();
+
+    assert_eq!(0, result.stage_count());
+    assert_eq!(1, result.input_channels());
+}
+
+#[test]
+fn test_mpe_rejects_unknown_elements() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    for v in &[0x00010001u32, 1, 24, 8] {
+        io.write_all(&v.to_be_bytes()).unwrap();
+    }
+    io.write_all(b"abcd\0\0\0\0").unwrap();
+
+    let err = read(io.data()).unwrap_err();
+
+    assert_eq!(ErrorKind::InvalidData, err.kind());
+    assert!(err.to_string().contains("abcd"));
+}
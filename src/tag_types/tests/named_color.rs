@@ -0,0 +1,49 @@
+use crate::plugin::{MemoryIo, VecIo};
+use crate::tag_types::named_color2;
+use crate::NamedColorList;
+
+#[test]
+fn test_named_color2_round_trips() {
+    let mut list = NamedColorList::new(4, "ACME ", " C").unwrap();
+    list.append(
+        "Warm Red",
+        [0x8000, 0xc000, 0xa000],
+        &[0, 0xffff, 0xe000, 0],
+    );
+    list.append("Process Blue", [0x5000, 0x7000, 0x2000], &[0xffff, 0x4000]);
+    let mut io = VecIo::new();
+
+    named_color2::write(&mut io, &list).unwrap();
+    let buf = io.data();
+    assert_eq!(12 + 32 + 32 + 2 * (32 + 6 + 8), buf.len());
+
+    let result = named_color2::read(&mut MemoryIo::new(buf), buf.len() as u32).unwrap();
+    assert_eq!(list, result);
+    assert_eq!(
+        &[0xffff, 0x4000, 0, 0],
+        &result.colors()[1].device_colorant[..]
+    );
+    assert_eq!(Some(1), result.index_of("process BLUE"));
+    assert_eq!(None, result.index_of("Black"));
+}
+
+#[test]
+fn test_named_color2_cuts_long_names() {
+    let mut list = NamedColorList::new(0, "", "").unwrap();
+    list.append("A name much longer than thirty two characters", [0; 3], &[]);
+    let mut io = VecIo::new();
+
+    named_color2::write(&mut io, &list).unwrap();
+    let buf = io.data();
+    let result = named_color2::read(&mut MemoryIo::new(buf), buf.len() as u32).unwrap();
+
+    assert_eq!("A name much longer than thirty t", result.colors()[0].name);
+}
+
+#[test]
+fn test_named_color2_rejects_too_many_colorants() {
+    let mut buf = vec![0u8; 12 + 64];
+    buf[11] = 17;
+
+    assert!(named_color2::read(&mut MemoryIo::new(&buf), buf.len() as u32).is_err());
+}
@@ -0,0 +1,153 @@
+use super::helpers::encode;
+use crate::plugin::MemoryIo;
+use crate::signatures::{tag, tag_type};
+use crate::tag_types::*;
+use crate::{DateTimeNumber, ICCData, Profile, Signature};
+
+#[test]
+fn test_fixed_arrays_round_trip() {
+    let values = [1.0, -0.5, 0.25, 32767.0];
+    let data = encode(|io| s15_fixed16_array::write(io, &values).unwrap());
+    assert_eq!(&[0xff, 0xff, 0x80, 0], &data[4..8]);
+    let result = s15_fixed16_array::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    assert_eq!(&values[..], &result[..]);
+
+    let values = [1.5, 65535.0, 0.0];
+    let data = encode(|io| u16_fixed16_array::write(io, &values).unwrap());
+    assert_eq!(&[0, 1, 0x80, 0], &data[..4]);
+    let result = u16_fixed16_array::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    assert_eq!(&values[..], &result[..]);
+}
+
+#[test]
+fn test_integer_arrays_round_trip() {
+    let data = encode(|io| uint8_array::write(io, &[1, 2, 3]).unwrap());
+    assert_eq!(
+        vec![1, 2, 3],
+        uint8_array::read(&mut MemoryIo::new(&data), 3).unwrap()
+    );
+
+    let data = encode(|io| uint16_array::write(io, &[0x1234, 0xffff]).unwrap());
+    assert_eq!(&[0x12, 0x34, 0xff, 0xff], &data[..]);
+    assert_eq!(
+        vec![0x1234, 0xffff],
+        uint16_array::read(&mut MemoryIo::new(&data), 4).unwrap()
+    );
+
+    let data = encode(|io| uint32_array::write(io, &[7, 0xdeadbeef]).unwrap());
+    assert_eq!(
+        vec![7, 0xdeadbeef],
+        uint32_array::read(&mut MemoryIo::new(&data), 8).unwrap()
+    );
+
+    let data = encode(|io| uint64_array::write(io, &[1 << 40]).unwrap());
+    assert_eq!(&[0, 0, 1, 0, 0, 0, 0, 0], &data[..]);
+    assert_eq!(
+        vec![1 << 40],
+        uint64_array::read(&mut MemoryIo::new(&data), 8).unwrap()
+    );
+}
+
+#[test]
+fn test_arrays_ignore_trailing_bytes() {
+    let data = [0, 0, 0, 1, 0, 0];
+    assert_eq!(
+        vec![1],
+        uint32_array::read(&mut MemoryIo::new(&data), 6).unwrap()
+    );
+}
+
+#[test]
+fn test_data_round_trips() {
+    let value = ICCData {
+        flag: ICCData::ASCII,
+        data: b"%!PS".to_vec(),
+    };
+    let data = encode(|io| data::write(io, &value).unwrap());
+    assert_eq!(8, data.len());
+    assert_eq!(value, data::read(&mut MemoryIo::new(&data), 8).unwrap());
+
+    assert!(data::read(&mut MemoryIo::new(&data), 3).is_err());
+}
+
+#[test]
+fn test_date_time_round_trips() {
+    let value = DateTimeNumber {
+        year: 2021,
+        month: 3,
+        day: 14,
+        hours: 15,
+        minutes: 9,
+        seconds: 26,
+    };
+    let data = encode(|io| date_time::write(io, value).unwrap());
+    assert_eq!(12, data.len());
+    assert_eq!(
+        value,
+        date_time::read(&mut MemoryIo::new(&data), 12).unwrap()
+    );
+}
+
+#[test]
+fn test_simple_tags_in_profile() {
+    let chad = vec![
+        1.0479, 0.0229, -0.0502, 0.0296, 0.9904, -0.0171, -0.0092, 0.0151, 0.7519,
+    ];
+    let calibrated = DateTimeNumber {
+        year: 2020,
+        month: 1,
+        day: 2,
+        ..Default::default()
+    };
+    let crd = ICCData {
+        flag: ICCData::BINARY,
+        data: vec![0, 1, 2],
+    };
+
+    let mut profile = Profile::new();
+    profile
+        .write_tag(
+            tag::CHROMATIC_ADAPTATION,
+            TagData::S15Fixed16Array(chad.clone()),
+        )
+        .unwrap();
+    profile
+        .write_tag(tag::CALIBRATION_DATE_TIME, TagData::DateTime(calibrated))
+        .unwrap();
+    profile
+        .write_tag(tag::PS2_CRD0, TagData::Data(crd.clone()))
+        .unwrap();
+    profile
+        .write_tag(
+            tag::COLORIMETRIC_INTENT_IMAGE_STATE,
+            TagData::Signature(Signature::new(b"scoe")),
+        )
+        .unwrap();
+    // The chromatic adaptation matrix needs 9 numbers
+    assert!(profile
+        .write_tag(
+            tag::CHROMATIC_ADAPTATION,
+            TagData::S15Fixed16Array(vec![1.0])
+        )
+        .is_err());
+
+    let result = Profile::open_mem(&profile.save_to_mem().unwrap()).unwrap();
+
+    match result.read_tag(tag::CHROMATIC_ADAPTATION).unwrap() {
+        TagData::S15Fixed16Array(values) => {
+            for (expected, actual) in chad.iter().zip(values.iter()) {
+                assert!((expected - actual).abs() < 1e-4);
+            }
+        }
+        other => panic!("Unexpected tag data {:?}", other),
+    }
+    assert_eq!(
+        TagData::DateTime(calibrated),
+        result.read_tag(tag::CALIBRATION_DATE_TIME).unwrap()
+    );
+    assert_eq!(TagData::Data(crd), result.read_tag(tag::PS2_CRD0).unwrap());
+    assert_eq!(
+        Some(tag_type::SIGNATURE),
+        result.tag_type(tag::COLORIMETRIC_INTENT_IMAGE_STATE)
+    );
+}
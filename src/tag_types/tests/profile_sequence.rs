@@ -0,0 +1,104 @@
+use std::io::Write;
+
+use crate::plugin::{IoHandler, MemoryIo, VecIo};
+use crate::tag_types::{profile_sequence_desc, profile_sequence_id};
+use crate::{Mlu, ProfileSequenceDesc, Signature};
+
+fn sequence() -> Vec<ProfileSequenceDesc> {
+    let mut model = Mlu::new();
+    model.set("en", "US", "Printer");
+    model.set("es", "ES", "Impresora");
+
+    vec![
+        ProfileSequenceDesc {
+            device_mfg: Signature::new(b"ACME"),
+            device_model: Signature::new(b"M100"),
+            attributes: 2,
+            technology: Signature::new(b"dcam"),
+            profile_id: [7; 16],
+            manufacturer: Mlu::from("Acme"),
+            model,
+            description: Mlu::from("Camera"),
+        },
+        ProfileSequenceDesc {
+            device_mfg: Signature::new(b"WXYZ"),
+            description: Mlu::from("Odd length"),
+            ..Default::default()
+        },
+    ]
+}
+
+fn read_back(
+    data: &[u8],
+    read: fn(&mut dyn IoHandler, u32) -> std::io::Result<Vec<ProfileSequenceDesc>>,
+) -> Vec<ProfileSequenceDesc> {
+    let mut io = MemoryIo::new(data);
+    io.seek(8).unwrap();
+    read(&mut io, data.len() as u32 - 8).unwrap()
+}
+
+#[test]
+fn test_pseq_round_trips_v4() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    profile_sequence_desc::write(&mut io, &sequence(), 4.3).unwrap();
+
+    let result = read_back(io.data(), profile_sequence_desc::read);
+
+    assert_eq!(2, result.len());
+    for (expected, actual) in sequence().iter().zip(result.iter()) {
+        assert_eq!(expected.device_mfg, actual.device_mfg);
+        assert_eq!(expected.device_model, actual.device_model);
+        assert_eq!(expected.attributes, actual.attributes);
+        assert_eq!(expected.technology, actual.technology);
+        assert_eq!(expected.manufacturer, actual.manufacturer);
+        assert_eq!(expected.model, actual.model);
+        // Not part of the tag
+        assert_eq!([0; 16], actual.profile_id);
+        assert!(actual.description.is_empty());
+    }
+}
+
+#[test]
+fn test_pseq_uses_text_description_before_v4() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    profile_sequence_desc::write(&mut io, &sequence(), 2.1).unwrap();
+
+    // Count, then the fixed part of the first description followed by the embedded type
+    assert_eq!(b"desc", &io.data()[8 + 4 + 20..8 + 4 + 24]);
+
+    let result = read_back(io.data(), profile_sequence_desc::read);
+    assert_eq!(Some("Printer".to_string()), result[0].model.get("es", "ES"));
+    assert_eq!(
+        Some("Acme".to_string()),
+        result[0].manufacturer.get("en", "US")
+    );
+    assert_eq!(Signature::new(b"WXYZ"), result[1].device_mfg);
+}
+
+#[test]
+fn test_psid_round_trips() {
+    let mut io = VecIo::new();
+    io.write_all(&[0u8; 8]).unwrap();
+    profile_sequence_id::write(&mut io, &sequence(), 4.3).unwrap();
+
+    let result = read_back(io.data(), profile_sequence_id::read);
+
+    assert_eq!(2, result.len());
+    assert_eq!([7; 16], result[0].profile_id);
+    assert_eq!(Mlu::from("Camera"), result[0].description);
+    assert_eq!(Mlu::from("Odd length"), result[1].description);
+    assert_eq!(Signature::default(), result[0].device_mfg);
+}
+
+#[test]
+fn test_psid_with_a_huge_count_is_rejected() {
+    let data = [0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff];
+
+    let mut io = MemoryIo::new(&data);
+    io.seek(8).unwrap();
+
+    let error = profile_sequence_id::read(&mut io, 4).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, error.kind());
+}
@@ -0,0 +1,129 @@
+use crate::plugin::{MemoryIo, VecIo};
+use crate::signatures::{tag, tag_type};
+use crate::tag_types::{vcgt, TagData};
+use crate::{Profile, ToneCurve};
+
+fn round_trip(curves: &[ToneCurve; 3]) -> (Vec<u8>, [ToneCurve; 3]) {
+    let mut io = VecIo::new();
+    vcgt::write(&mut io, curves).unwrap();
+    let data = io.data().to_vec();
+
+    let result = vcgt::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+    (data, result)
+}
+
+#[test]
+fn test_vcgt_formula_round_trips() {
+    let curve = |gamma: f64, min: f64, max: f64| {
+        let params = [
+            gamma,
+            (max - min).powf(1.0 / gamma),
+            0.0,
+            0.0,
+            0.0,
+            min,
+            0.0,
+        ];
+        ToneCurve::build_parametric(5, &params).unwrap()
+    };
+    let curves = [
+        curve(1.0, 0.0, 1.0),
+        curve(2.2, 0.0, 0.9),
+        curve(1.8, 0.1, 0.8),
+    ];
+
+    let (data, result) = round_trip(&curves);
+
+    // Formula, then gamma, min and max of each channel
+    assert_eq!(4 + 3 * 12, data.len());
+    assert_eq!(&[0, 0, 0, 1], &data[..4]);
+    for (expected, actual) in curves.iter().zip(result.iter()) {
+        assert_eq!(Some(5), actual.parametric_type());
+        for i in 0..=10 {
+            let x = i as f32 / 10.0;
+            assert!((expected.eval_f32(x) - actual.eval_f32(x)).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn test_vcgt_table_round_trips() {
+    let curves = [
+        ToneCurve::linear(),
+        ToneCurve::build_gamma(2.2).unwrap(),
+        ToneCurve::from_table16(&[0x1000, 0x8000, 0xf000]).unwrap(),
+    ];
+
+    let (data, result) = round_trip(&curves);
+
+    assert_eq!(4 + 6 + 3 * 256 * 2, data.len());
+    assert_eq!(&[0, 0, 0, 0, 0, 3, 1, 0, 0, 2], &data[..10]);
+    for (expected, actual) in curves.iter().zip(result.iter()) {
+        assert_eq!(256, actual.n_entries());
+        for i in 0..=255u16 {
+            let x = i * 257;
+            assert!((expected.eval_u16(x) as i32 - actual.eval_u16(x) as i32).abs() <= 1);
+        }
+    }
+}
+
+#[test]
+fn test_vcgt_keeps_tables_of_the_same_size() {
+    let table = |offset: u16| -> Vec<u16> { (0..1024u16).map(|i| i * 64 + offset).collect() };
+    let curves = [
+        ToneCurve::from_table16(&table(0)).unwrap(),
+        ToneCurve::from_table16(&table(1)).unwrap(),
+        ToneCurve::from_table16(&table(2)).unwrap(),
+    ];
+
+    let (data, result) = round_trip(&curves);
+
+    assert_eq!(4 + 6 + 3 * 1024 * 2, data.len());
+    for (expected, actual) in curves.iter().zip(result.iter()) {
+        assert_eq!(expected.table16(), actual.table16());
+    }
+}
+
+#[test]
+fn test_vcgt_reads_8_bit_tables() {
+    let mut data = vec![0, 0, 0, 0, 0, 3, 0, 2, 0, 1];
+    data.extend_from_slice(&[0, 0xff, 0x10, 0x20, 0xff, 0]);
+
+    let result = vcgt::read(&mut MemoryIo::new(&data), data.len() as u32).unwrap();
+
+    assert_eq!(&[0, 0xffff], result[0].table16());
+    assert_eq!(&[0x1010, 0x2020], result[1].table16());
+    assert_eq!(&[0xffff, 0], result[2].table16());
+}
+
+#[test]
+fn test_vcgt_rejects_wrong_channel_count() {
+    let data = [0, 0, 0, 0, 0, 1, 0, 2, 0, 2, 0, 0, 0xff, 0xff];
+    assert!(vcgt::read(&mut MemoryIo::new(&data), data.len() as u32).is_err());
+
+    let data = [0, 0, 0, 2];
+    assert!(vcgt::read(&mut MemoryIo::new(&data), data.len() as u32).is_err());
+}
+
+#[test]
+fn test_vcgt_tag_in_profile() {
+    let curves = [
+        ToneCurve::build_gamma(1.0).unwrap(),
+        ToneCurve::build_gamma(1.0).unwrap(),
+        ToneCurve::build_gamma(1.0).unwrap(),
+    ];
+    let mut profile = Profile::new();
+    profile.write_tag(tag::VCGT, TagData::Vcgt(curves)).unwrap();
+
+    let mut io = VecIo::new();
+    profile.save_to_io(&mut io).unwrap();
+    let profile = Profile::open_io(&mut MemoryIo::new(io.data())).unwrap();
+
+    assert_eq!(Some(tag_type::VCGT), profile.tag_type(tag::VCGT));
+    match profile.read_tag(tag::VCGT).unwrap() {
+        TagData::Vcgt(result) => {
+            assert!(result.iter().all(|c| c.eval_u16(0x8000) == 0x8000));
+        }
+        other => panic!("Unexpected tag data {:?}", other),
+    }
+}
@@ -0,0 +1,115 @@
+use crate::plugin::{read_encoded_xyz, write_encoded_xyz};
+use crate::tag_types::xyz as xyz_type;
+use crate::{EncodedXYZNumber, CIEXYZ};
+
+#[test]
+fn test_encoded_xyz_from_ciexyz_rounds_to_s15f16() {
+    let value = CIEXYZ {
+        X: crate::d50::X,
+        Y: crate::d50::Y,
+        Z: crate::d50::Z,
+    };
+
+    let encoded = EncodedXYZNumber::from(value);
+
+    assert_eq!(
+        EncodedXYZNumber {
+            x: 0xF6D6,
+            y: 0x10000,
+            z: 0xD32D
+        },
+        encoded
+    );
+}
+
+#[test]
+fn test_ciexyz_from_encoded_xyz_handles_negatives() {
+    let encoded = EncodedXYZNumber {
+        x: -0x8000,
+        y: 0x18000,
+        z: 0,
+    };
+
+    let value = CIEXYZ::from(encoded);
+
+    assert_eq!(
+        CIEXYZ {
+            X: -0.5,
+            Y: 1.5,
+            Z: 0.0
+        },
+        value
+    );
+}
+
+#[test]
+fn test_ciexyz_from_encoded_xyz_handles_the_extremes() {
+    let encoded = EncodedXYZNumber {
+        x: i32::MIN,
+        y: i32::MAX,
+        z: -1,
+    };
+
+    let value = CIEXYZ::from(encoded);
+
+    assert_eq!(-32768.0, value.X);
+    assert_eq!(32768.0 - 1.0 / 65536.0, value.Y);
+    assert_eq!(-1.0 / 65536.0, value.Z);
+}
+
+#[test]
+fn test_encoded_xyz_is_stored_as_big_endian_s15f16() {
+    let mut buf = Vec::new();
+
+    write_encoded_xyz(
+        &mut buf,
+        EncodedXYZNumber {
+            x: 0x0001_8000,
+            y: -1,
+            z: 0x7FFF_FFFF,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        vec![0x00, 0x01, 0x80, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0xFF, 0xFF, 0xFF],
+        buf
+    );
+    let value = read_encoded_xyz(&mut buf.as_slice()).unwrap();
+    assert_eq!(-1, value.y);
+}
+
+#[test]
+fn test_xyz_type_round_trips_array() {
+    let values = [
+        CIEXYZ {
+            X: 0.4361,
+            Y: 0.2225,
+            Z: 0.0139,
+        },
+        CIEXYZ {
+            X: 0.3851,
+            Y: 0.7169,
+            Z: 0.0971,
+        },
+    ];
+    let mut buf = Vec::new();
+
+    xyz_type::write(&mut buf, &values).unwrap();
+    assert_eq!(2 * xyz_type::XYZ_NUMBER_SIZE as usize, buf.len());
+
+    let result = xyz_type::read(&mut buf.as_slice(), buf.len() as u32).unwrap();
+    assert_eq!(2, result.len());
+    for (expected, actual) in values.iter().zip(result.iter()) {
+        assert!((expected.X - actual.X).abs() < 1.0 / 65536.0);
+        assert!((expected.Y - actual.Y).abs() < 1.0 / 65536.0);
+        assert!((expected.Z - actual.Z).abs() < 1.0 / 65536.0);
+    }
+}
+
+#[test]
+fn test_xyz_type_fails_on_truncated_data() {
+    let buf = [0u8; 8];
+
+    assert!(xyz_type::read(&mut &buf[..], 12).is_err());
+}
@@ -0,0 +1,31 @@
+use std::io::Result;
+
+use crate::mlu::{NO_COUNTRY, NO_LANGUAGE};
+use crate::plugin::IoHandler;
+use crate::Mlu;
+
+/// Reads a textType. The text is stored as a language independent entry.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Mlu> {
+    let mut text = vec![0u8; size_of_tag as usize];
+    io.read_exact(&mut text)?;
+
+    Ok(Mlu::from(ascii_to_string(&text).as_str()))
+}
+
+/// Writes a textType, which only holds ASCII. Characters outside of it are replaced by '?'.
+pub fn write(io: &mut dyn IoHandler, value: &Mlu) -> Result<()> {
+    let text = value.get_ascii(NO_LANGUAGE, NO_COUNTRY).unwrap_or_default();
+
+    io.write_all(text.as_bytes())?;
+    io.write_all(&[0])
+}
+
+/// Converts ASCII data up to the first NUL
+pub(crate) fn ascii_to_string(data: &[u8]) -> String {
+    let len = data.iter().position(|c| *c == 0).unwrap_or(data.len());
+
+    data[..len]
+        .iter()
+        .map(|c| if c.is_ascii() { *c as char } else { '?' })
+        .collect()
+}
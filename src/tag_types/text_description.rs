@@ -0,0 +1,101 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::text::ascii_to_string;
+use crate::mlu::{NO_COUNTRY, NO_LANGUAGE};
+use crate::plugin::{
+    align_32, read_u16_array, read_u32, write_u16, write_u16_array, write_u32, write_u8, IoHandler,
+};
+use crate::Mlu;
+
+/// Reads a v2 textDescriptionType. The Unicode part is used if there is one, the ASCII part otherwise. The
+/// ScriptCode part is skipped.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Mlu> {
+    if size_of_tag < 4 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Corrupted textDescriptionType",
+        ));
+    }
+
+    let ascii_count = read_u32(io)?;
+    let mut size_of_tag = size_of_tag - 4;
+    if size_of_tag < ascii_count {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Corrupted textDescriptionType",
+        ));
+    }
+
+    let mut text = vec![0u8; ascii_count as usize];
+    io.read_exact(&mut text)?;
+    size_of_tag -= ascii_count;
+    let ascii = Mlu::from(ascii_to_string(&text).as_str());
+
+    // Skip the other parts if present, so the tag can be followed by others when embedded
+    if size_of_tag < 8 {
+        return Ok(ascii);
+    }
+    read_u32(io)?; // Unicode language code
+    let unicode_count = read_u32(io)?;
+    size_of_tag -= 8;
+
+    let unicode_size = unicode_count.saturating_mul(2);
+    if size_of_tag < unicode_size {
+        return Ok(ascii);
+    }
+    let mut wide = vec![0u16; unicode_count as usize];
+    read_u16_array(io, &mut wide)?;
+    size_of_tag -= unicode_size;
+
+    let result = match wide.iter().position(|c| *c == 0).unwrap_or(wide.len()) {
+        0 => ascii,
+        len => Mlu::from(String::from_utf16_lossy(&wide[..len]).as_str()),
+    };
+
+    if size_of_tag >= 2 + 1 + 67 {
+        io.read_exact(&mut [0u8; 2 + 1 + 67])?;
+    }
+    Ok(result)
+}
+
+/// Writes a v2 textDescriptionType. The Unicode part keeps the text as is, the ASCII one replaces other
+/// characters with '?'.
+pub fn write(io: &mut dyn IoHandler, value: &Mlu) -> Result<()> {
+    let len_tag_requirement = 8 + write_unpadded(io, value)?;
+    let len_aligned = align_32(len_tag_requirement);
+
+    // The ASCII count may leave the rest misaligned, so the padding goes at the end of the tag
+    io.write_all(&vec![0u8; (len_aligned - len_tag_requirement) as usize])
+}
+
+/// Writes a textDescriptionType without the padding at the end, as embedded in other tags. Returns the number
+/// of bytes written.
+pub(super) fn write_unpadded(io: &mut dyn IoHandler, value: &Mlu) -> Result<u32> {
+    let text = value.get_ascii(NO_LANGUAGE, NO_COUNTRY).unwrap_or_default();
+    let wide: Vec<u16> = value
+        .get(NO_LANGUAGE, NO_COUNTRY)
+        .unwrap_or_default()
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    // Text lengths including the null terminator
+    let len_text = text.len() as u32 + 1;
+    let len_wide = wide.len() as u32;
+
+    write_u32(io, len_text)?;
+    io.write_all(text.as_bytes())?;
+    io.write_all(&[0])?;
+
+    // Unicode language code and description
+    write_u32(io, 0)?;
+    write_u32(io, len_wide)?;
+    write_u16_array(io, &wide)?;
+
+    // ScriptCode code, count and description, unused
+    write_u16(io, 0)?;
+    write_u8(io, 0)?;
+    io.write_all(&[0u8; 67])?;
+
+    Ok(4 + len_text + 4 + 4 + 2 * len_wide + 2 + 1 + 67)
+}
@@ -0,0 +1,45 @@
+use std::io::{Error, ErrorKind, Result};
+
+use super::text;
+use crate::plugin::{read_u16_array, read_u32, write_u16_array, write_u32, IoHandler};
+use crate::{ToneCurve, UcrBg};
+
+fn corrupted() -> Error {
+    Error::new(ErrorKind::InvalidData, "Corrupted ucrbg tag")
+}
+
+/// Reads a curve as its count followed by its entries, taking its size out of `size_of_tag`
+fn read_curve(io: &mut dyn IoHandler, size_of_tag: &mut u32) -> Result<ToneCurve> {
+    let count = read_u32(io)?;
+    *size_of_tag = count
+        .checked_mul(2)
+        .and_then(|size| size.checked_add(4))
+        .and_then(|size| size_of_tag.checked_sub(size))
+        .ok_or_else(corrupted)?;
+
+    let mut table = vec![0u16; count as usize];
+    read_u16_array(io, &mut table)?;
+    ToneCurve::from_table16(&table).ok_or_else(corrupted)
+}
+
+fn write_curve(io: &mut dyn IoHandler, value: &ToneCurve) -> Result<()> {
+    write_u32(io, value.table16().len() as u32)?;
+    write_u16_array(io, value.table16())
+}
+
+/// Reads a ucrbgType. The description takes the rest of the tag.
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<UcrBg> {
+    let mut size_of_tag = size_of_tag;
+
+    let ucr = read_curve(io, &mut size_of_tag)?;
+    let bg = read_curve(io, &mut size_of_tag)?;
+    let desc = text::read(io, size_of_tag)?;
+
+    Ok(UcrBg { ucr, bg, desc })
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &UcrBg) -> Result<()> {
+    write_curve(io, &value.ucr)?;
+    write_curve(io, &value.bg)?;
+    text::write(io, &value.desc)
+}
@@ -0,0 +1,15 @@
+use std::io::Result;
+
+use crate::plugin::{read_u16, write_u16, IoHandler};
+
+/// Reads a uInt16ArrayType, as many numbers as the tag holds
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Vec<u16>> {
+    (0..size_of_tag / 2).map(|_| read_u16(io)).collect()
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &[u16]) -> Result<()> {
+    for v in value {
+        write_u16(io, *v)?;
+    }
+    Ok(())
+}
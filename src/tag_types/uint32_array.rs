@@ -0,0 +1,15 @@
+use std::io::Result;
+
+use crate::plugin::{read_u32, write_u32, IoHandler};
+
+/// Reads a uInt32ArrayType, as many numbers as the tag holds
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Vec<u32>> {
+    (0..size_of_tag / 4).map(|_| read_u32(io)).collect()
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &[u32]) -> Result<()> {
+    for v in value {
+        write_u32(io, *v)?;
+    }
+    Ok(())
+}
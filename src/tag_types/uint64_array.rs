@@ -0,0 +1,15 @@
+use std::io::Result;
+
+use crate::plugin::{read_u64, write_u64, IoHandler};
+
+/// Reads a uInt64ArrayType, as many numbers as the tag holds
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Vec<u64>> {
+    (0..size_of_tag / 8).map(|_| read_u64(io)).collect()
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &[u64]) -> Result<()> {
+    for v in value {
+        write_u64(io, *v)?;
+    }
+    Ok(())
+}
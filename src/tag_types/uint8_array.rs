@@ -0,0 +1,14 @@
+use std::io::Result;
+
+use crate::plugin::IoHandler;
+
+/// Reads a uInt8ArrayType, as many numbers as the tag holds
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<Vec<u8>> {
+    let mut result = vec![0u8; size_of_tag as usize];
+    io.read_exact(&mut result)?;
+    Ok(result)
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &[u8]) -> Result<()> {
+    io.write_all(value)
+}
@@ -0,0 +1,147 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::internal::{from_8_to_16, quick_saturate_word};
+use crate::plugin::{
+    f64_to_s15f16, read_s15f16, read_u16, read_u16_array, read_u32, read_u8, s15f16_to_f64,
+    write_s15f16, write_u16, write_u16_array, write_u32, IoHandler,
+};
+use crate::ToneCurve;
+
+/// The curves are given as tables
+const TABLE: u32 = 0;
+/// The curves are given as gamma, minimum and maximum
+const FORMULA: u32 = 1;
+
+/// Number of entries of the tables written when the curves are not tables of one size
+const TABLE_ENTRIES: u16 = 256;
+
+fn unsupported(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn invalid_curve() -> Error {
+    Error::new(ErrorKind::InvalidData, "Invalid vcgt curve")
+}
+
+/// Reads the Apple video card gamma type, red, green and blue curves in that order
+pub fn read(io: &mut dyn IoHandler, size_of_tag: u32) -> Result<[ToneCurve; 3]> {
+    let curves = match read_u32(io)? {
+        TABLE => {
+            let channels = read_u16(io)?;
+            let entries = read_u16(io)?;
+            let mut bytes = read_u16(io)?;
+
+            if channels != 3 {
+                return Err(unsupported(format!(
+                    "Unsupported number of channels for VCGT '{}'",
+                    channels
+                )));
+            }
+
+            // Adobe's quirk fixup. Fixing broken profiles...
+            if entries == 256 && bytes == 1 && size_of_tag == 1576 {
+                bytes = 2;
+            }
+
+            let mut curves = Vec::with_capacity(3);
+            for _ in 0..3 {
+                let mut table = vec![0u16; entries as usize];
+                match bytes {
+                    1 => {
+                        for value in table.iter_mut() {
+                            *value = from_8_to_16(read_u8(io)?);
+                        }
+                    }
+                    2 => read_u16_array(io, &mut table)?,
+                    _ => {
+                        return Err(unsupported(format!(
+                            "Unsupported bit depth for VCGT '{}'",
+                            bytes * 8
+                        )))
+                    }
+                }
+                curves.push(ToneCurve::from_table16(&table).ok_or_else(invalid_curve)?);
+            }
+            curves
+        }
+        FORMULA => {
+            let mut curves = Vec::with_capacity(3);
+            for _ in 0..3 {
+                let gamma = s15f16_to_f64(read_s15f16(io)?);
+                let min = s15f16_to_f64(read_s15f16(io)?);
+                let max = s15f16_to_f64(read_s15f16(io)?);
+
+                // Y = (Max - Min) * (X ^ Gamma) + Min, as a type 5 parametric curve
+                let params = [
+                    gamma,
+                    (max - min).powf(1.0 / gamma),
+                    0.0,
+                    0.0,
+                    0.0,
+                    min,
+                    0.0,
+                ];
+                curves.push(ToneCurve::build_parametric(5, &params).ok_or_else(invalid_curve)?);
+            }
+            curves
+        }
+        other => {
+            return Err(unsupported(format!(
+                "Unsupported tag type for VCGT '{}'",
+                other
+            )))
+        }
+    };
+
+    let mut curves = curves.into_iter();
+    Ok([
+        curves.next().unwrap(),
+        curves.next().unwrap(),
+        curves.next().unwrap(),
+    ])
+}
+
+/// Writes the formula variant if all curves are type 5 parametric curves, 16 bit tables otherwise
+pub fn write(io: &mut dyn IoHandler, value: &[ToneCurve; 3]) -> Result<()> {
+    if value.iter().all(|c| c.parametric_type() == Some(5)) {
+        write_u32(io, FORMULA)?;
+
+        for curve in value {
+            let params = curve.params().ok_or_else(invalid_curve)?;
+            let gamma = params[0];
+            let min = params[5];
+            let max = params[1].powf(gamma) + min;
+
+            write_s15f16(io, f64_to_s15f16(gamma))?;
+            write_s15f16(io, f64_to_s15f16(min))?;
+            write_s15f16(io, f64_to_s15f16(max))?;
+        }
+    } else {
+        // Tables sharing a size are kept as they are, anything else is resampled to that size
+        let mut sizes = value
+            .iter()
+            .filter(|c| c.segments().is_empty())
+            .map(ToneCurve::n_entries);
+        let entries = match sizes.next() {
+            Some(n) if (2..=u16::MAX as usize).contains(&n) && sizes.all(|m| m == n) => n as u16,
+            _ => TABLE_ENTRIES,
+        };
+
+        write_u32(io, TABLE)?;
+        write_u16(io, 3)?;
+        write_u16(io, entries)?;
+        write_u16(io, 2)?;
+
+        for curve in value {
+            if curve.segments().is_empty() && curve.n_entries() == entries as usize {
+                write_u16_array(io, curve.table16())?;
+                continue;
+            }
+            for i in 0..entries {
+                let v = curve.eval_f32(i as f32 / (entries - 1) as f32);
+                write_u16(io, quick_saturate_word(v as f64 * 65535.0))?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::io::Result;
+
+use crate::plugin::{read_u32, read_xyz, write_u32, write_xyz, IoHandler};
+use crate::ICCViewingConditions;
+
+/// Reads a viewingConditionsType
+pub fn read(io: &mut dyn IoHandler, _size_of_tag: u32) -> Result<ICCViewingConditions> {
+    Ok(ICCViewingConditions {
+        illuminant_xyz: read_xyz(io)?,
+        surround_xyz: read_xyz(io)?,
+        illuminant_type: read_u32(io)?.into(),
+    })
+}
+
+pub fn write(io: &mut dyn IoHandler, value: &ICCViewingConditions) -> Result<()> {
+    write_xyz(io, value.illuminant_xyz)?;
+    write_xyz(io, value.surround_xyz)?;
+    write_u32(io, value.illuminant_type.into())
+}
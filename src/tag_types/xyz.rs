@@ -0,0 +1,26 @@
+use std::io::{Read, Result, Write};
+
+use crate::plugin::{read_xyz, write_xyz};
+use crate::CIEXYZ;
+
+/// Size of a single encoded XYZ number
+pub const XYZ_NUMBER_SIZE: u32 = 12;
+
+/// Reads an XYZ type. The tag may hold any number of XYZ values.
+pub fn read(reader: &mut dyn Read, size_of_tag: u32) -> Result<Vec<CIEXYZ>> {
+    let count = size_of_tag / XYZ_NUMBER_SIZE;
+    let mut result = Vec::with_capacity(count as usize);
+
+    for _ in 0..count {
+        result.push(read_xyz(reader)?);
+    }
+    Ok(result)
+}
+
+/// Writes an XYZ type holding all the given values
+pub fn write(writer: &mut dyn Write, values: &[CIEXYZ]) -> Result<()> {
+    for value in values {
+        write_xyz(writer, *value)?;
+    }
+    Ok(())
+}
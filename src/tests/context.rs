@@ -0,0 +1,133 @@
+use std::io::ErrorKind;
+
+use crate::plugin::{MemoryIo, ParametricCurves, Plugin, PluginBase};
+use crate::signatures::{color_space, profile_class, tag, tag_type};
+use crate::tag_types::TagData;
+use crate::{Context, NamedColorList, Profile, ToneCurve, Transform, LCMS_VERSION};
+
+// Y = a * X, with X = Y / a as the inverse
+fn eval_scale(r#type: i32, params: &[f64], r: f64) -> f64 {
+    if r#type > 0 {
+        params[0] * r
+    } else {
+        r / params[0]
+    }
+}
+
+fn scale_plugin() -> PluginBase<'static> {
+    PluginBase::new(Plugin::ParametricCurves(ParametricCurves {
+        functions: &[(500, 1)],
+        evaluator: eval_scale,
+    }))
+}
+
+#[test]
+fn test_plugins_stay_in_their_context() {
+    let context = Context::with_plugins(&scale_plugin()).unwrap();
+
+    let curve = ToneCurve::build_parametric_with_context(&context, 500, &[0.5]).unwrap();
+    assert_eq!(Some(&[0.5][..]), curve.params());
+    assert!((curve.eval_f32(0.5) - 0.25).abs() < 1e-6);
+
+    let inverse = ToneCurve::build_parametric_with_context(&context, -500, &[0.5]).unwrap();
+    assert!((inverse.eval_f32(0.25) - 0.5).abs() < 1e-6);
+
+    assert!(ToneCurve::build_parametric(500, &[0.5]).is_none());
+    assert!(ToneCurve::build_parametric_with_context(&Context::new(), 500, &[0.5]).is_none());
+}
+
+#[test]
+fn test_registering_on_a_clone_leaves_original_untouched() {
+    let original = Context::new();
+    let mut clone = original.clone();
+    assert_eq!(original, clone);
+
+    clone.register_plugins(&scale_plugin()).unwrap();
+    assert_ne!(original, clone);
+    assert!(ToneCurve::build_parametric_with_context(&original, 500, &[1.0]).is_none());
+    assert!(ToneCurve::build_parametric_with_context(&clone, 500, &[1.0]).is_some());
+
+    clone.unregister_plugins();
+    assert_eq!(Context::new(), clone);
+    assert!(ToneCurve::build_parametric_with_context(&clone, 500, &[1.0]).is_none());
+}
+
+#[test]
+fn test_plugins_needing_newer_version_are_rejected() {
+    let mut plugin = scale_plugin();
+    plugin.expected_version = LCMS_VERSION as u32 + 10;
+
+    let result = Context::with_plugins(&plugin);
+    assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+
+    plugin.expected_version = 2000;
+    assert!(Context::with_plugins(&plugin).is_ok());
+}
+
+#[test]
+fn test_plugins_with_too_many_types_are_rejected() {
+    let mut plugin = scale_plugin();
+    plugin.plugin = Plugin::ParametricCurves(ParametricCurves {
+        functions: &[(500, 11)],
+        evaluator: eval_scale,
+    });
+    assert!(Context::with_plugins(&plugin).is_err());
+
+    const MANY: &[(i32, usize)] = &[(500, 1); 21];
+    plugin.plugin = Plugin::ParametricCurves(ParametricCurves {
+        functions: MANY,
+        evaluator: eval_scale,
+    });
+    assert!(Context::with_plugins(&plugin).is_err());
+}
+
+#[test]
+fn test_transforms_keep_profile_context() {
+    let context = Context::with_plugins(&scale_plugin()).unwrap();
+
+    let mut list = NamedColorList::new(3, "", "").unwrap();
+    list.append("White", [0xff00, 0x8000, 0x8000], &[0xffff, 0xffff, 0xffff]);
+
+    let mut profile = Profile::new_with_context(&context);
+    profile.device_class = profile_class::NAMED_COLOR;
+    profile.color_space = color_space::RGB;
+    profile.pcs = color_space::LAB;
+    profile
+        .write_tag(tag::NAMED_COLOR2, TagData::NamedColorList(list))
+        .unwrap();
+
+    let data = profile.save_to_mem().unwrap();
+    let profile = Profile::open_mem_with_context(&context, &data).unwrap();
+    assert_eq!(&context, profile.context());
+
+    let transform = Transform::new_named_color(&profile, false).unwrap();
+    assert_eq!(&context, transform.context());
+    assert_eq!(&context, transform.to_device_link(4.3).unwrap().context());
+}
+
+#[test]
+fn test_tag_types_build_curves_with_the_context() {
+    let plugin = PluginBase::new(Plugin::ParametricCurves(ParametricCurves {
+        functions: &[(1, 1)],
+        evaluator: eval_scale,
+    }));
+    let context = Context::with_plugins(&plugin).unwrap();
+
+    // Type 1 with a parameter of 0.5, as parametricCurveType and as curveType
+    let parametric = [0, 0, 0, 0, 0, 0, 0x80, 0];
+    let curve = [0, 0, 0, 1, 0, 0x80];
+    for (type_sig, data) in [
+        (tag_type::PARAMETRIC_CURVE, &parametric[..]),
+        (tag_type::CURVE, &curve[..]),
+    ] {
+        let read = |context: &Context| {
+            let mut io = MemoryIo::new(data);
+            match context.read_tag_type(type_sig, &mut io, data.len() as u32) {
+                Ok(TagData::ToneCurve(curve)) => curve.eval_f32(0.5),
+                other => panic!("Unexpected {:?}", other),
+            }
+        };
+        assert!((read(&context) - 0.25).abs() < 1e-6);
+        assert!((read(&Context::new()) - 0.5f32.sqrt()).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,223 @@
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+use super::helpers::identity_link;
+use crate::plugin::{FormatterFactory, PackFn, Plugin, PluginBase, Stride, UnrollFn};
+use crate::{ColorSpace, Context, PixelType, Transform};
+
+fn identity_transform(input: PixelType, output: PixelType) -> Transform {
+    Transform::new_multiprofile(&[&identity_link(&Context::new())], 0)
+        .unwrap()
+        .with_formats(input, output)
+        .unwrap()
+}
+
+#[test]
+fn test_8_bit_to_16_bit() {
+    let transform = identity_transform(PixelType::RGB_8, PixelType::RGB_16);
+    assert_eq!(Some(PixelType::RGB_8), transform.input_format());
+    assert_eq!(Some(PixelType::RGB_16), transform.output_format());
+
+    let mut out = [0u8; 12];
+    transform
+        .transform_buffer(&[0, 0x80, 0xff, 0x12, 0x34, 0x56], &mut out, 2)
+        .unwrap();
+
+    let words: Vec<u16> = out
+        .chunks_exact(2)
+        .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+        .collect();
+    assert_eq!(vec![0, 0x8080, 0xffff, 0x1212, 0x3434, 0x5656], words);
+}
+
+#[test]
+fn test_swapped_and_extra_channels() {
+    let transform = identity_transform(PixelType::BGR_8, PixelType::RGB_8);
+    let mut out = [0u8; 3];
+    transform.transform_buffer(&[1, 2, 3], &mut out, 1).unwrap();
+    assert_eq!([3, 2, 1], out);
+
+    // Alpha is skipped on input and left untouched on output
+    let transform = identity_transform(PixelType::ARGB_8, PixelType::ARGB_8);
+    let mut out = [0xAAu8; 8];
+    transform
+        .transform_buffer(&[9, 1, 2, 3, 9, 4, 5, 6], &mut out, 2)
+        .unwrap();
+    assert_eq!([0xAA, 1, 2, 3, 0xAA, 4, 5, 6], out);
+}
+
+#[test]
+fn test_planar_lines() {
+    let transform = identity_transform(PixelType::RGB_8_PLANAR, PixelType::RGB_8);
+    let mut out = [0u8; 6];
+    transform
+        .transform_buffer(&[1, 4, 2, 5, 3, 6], &mut out, 2)
+        .unwrap();
+    assert_eq!([1, 2, 3, 4, 5, 6], out);
+
+    // Two lines of one pixel, with padding after each line and plane
+    let stride = Stride {
+        bytes_per_line_in: 1,
+        bytes_per_line_out: 4,
+        bytes_per_plane_in: 3,
+        bytes_per_plane_out: 0,
+    };
+    let mut out = [0u8; 8];
+    transform
+        .transform_lines(&[1, 4, 0, 2, 5, 0, 3, 6, 0], &mut out, 1, 2, &stride)
+        .unwrap();
+    assert_eq!([1, 2, 3, 0, 4, 5, 6, 0], out);
+}
+
+#[test]
+fn test_float_to_8_bit() {
+    let transform = identity_transform(PixelType::RGB_FLT, PixelType::RGB_8);
+    let input: Vec<u8> = [0.0f32, 0.5, 1.0]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+
+    let mut out = [0u8; 3];
+    transform.transform_buffer(&input, &mut out, 1).unwrap();
+    assert_eq!([0, 128, 255], out);
+}
+
+#[test]
+fn test_transform_needs_matching_formats() {
+    let transform = Transform::new_multiprofile(&[&identity_link(&Context::new())], 0).unwrap();
+    let mut out = [0u8; 3];
+    assert!(transform.transform_buffer(&[0; 3], &mut out, 1).is_err());
+
+    let result = transform.with_formats(PixelType::CMYK_8, PixelType::RGB_8);
+    assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+}
+
+#[test]
+fn test_buffers_too_small_are_rejected() {
+    let transform = identity_transform(PixelType::RGB_16, PixelType::RGB_8);
+    let mut out = [0u8; 6];
+    let error = transform
+        .transform_buffer(&[0; 10], &mut out, 2)
+        .unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, error.kind());
+
+    let mut out = [0u8; 5];
+    let error = transform
+        .transform_buffer(&[0; 12], &mut out, 2)
+        .unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, error.kind());
+
+    // The last line starts past the end of the input
+    let stride = Stride {
+        bytes_per_line_in: 8,
+        bytes_per_line_out: 3,
+        ..Stride::default()
+    };
+    let mut out = [0u8; 6];
+    let error = transform
+        .transform_lines(&[0; 12], &mut out, 1, 2, &stride)
+        .unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, error.kind());
+
+    // Planes are too far apart for the input
+    let transform = identity_transform(PixelType::RGB_8_PLANAR, PixelType::RGB_8);
+    let stride = Stride {
+        bytes_per_plane_in: 3,
+        ..Stride::default()
+    };
+    let mut out = [0u8; 3];
+    let error = transform
+        .transform_lines(&[0; 6], &mut out, 1, 1, &stride)
+        .unwrap_err();
+    assert_eq!(ErrorKind::InvalidInput, error.kind());
+}
+
+#[test]
+fn test_float_formats_out_of_the_unit_range_are_rejected() {
+    let transform = Transform::new_multiprofile(&[&identity_link(&Context::new())], 0).unwrap();
+    for format in [PixelType::LAB_FLT, PixelType::XYZ_FLT] {
+        let result = transform.clone().with_formats(format, PixelType::RGB_8);
+        assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+    }
+
+    let mut cmy = PixelType::RGB_FLT;
+    cmy.set_color_space(ColorSpace::Cmy);
+    let result = transform.with_formats(PixelType::RGB_8, cmy);
+    assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+}
+
+/// 5-6-5 packed RGB, flagged by the optimized bit
+fn rgb_565() -> PixelType {
+    let mut format = PixelType::RGB_16;
+    format.set_optimized(true);
+    format
+}
+
+fn unroll_565(_: PixelType, values: &mut [u16], input: &[u8], _: usize) -> usize {
+    let packed = u16::from_ne_bytes([input[0], input[1]]);
+    values[0] = ((packed >> 11) & 0x1f) * (0xffff / 0x1f);
+    values[1] = ((packed >> 5) & 0x3f) * (0xffff / 0x3f);
+    values[2] = (packed & 0x1f) * (0xffff / 0x1f);
+    2
+}
+
+fn pack_565(_: PixelType, values: &[u16], output: &mut [u8], _: usize) -> usize {
+    let r = (values[0] as u32 * 0x1f + 0x7fff) / 0xffff;
+    let g = (values[1] as u32 * 0x3f + 0x7fff) / 0xffff;
+    let b = (values[2] as u32 * 0x1f + 0x7fff) / 0xffff;
+    let packed = ((r << 11) | (g << 5) | b) as u16;
+    output[..2].copy_from_slice(&packed.to_ne_bytes());
+    2
+}
+
+struct Rgb565;
+
+impl FormatterFactory for Rgb565 {
+    fn unroll_16(&self, format: PixelType) -> Option<UnrollFn<u16>> {
+        if format == rgb_565() {
+            Some(unroll_565)
+        } else {
+            None
+        }
+    }
+
+    fn pack_16(&self, format: PixelType) -> Option<PackFn<u16>> {
+        if format == rgb_565() {
+            Some(pack_565)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn test_plugin_formatters_for_packed_formats() {
+    let link = identity_link(&Context::new());
+    let transform = Transform::new_multiprofile(&[&link], 0).unwrap();
+    let result = transform.with_formats(rgb_565(), PixelType::RGB_8);
+    assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+
+    let plugin = PluginBase::new(Plugin::Formatters(Arc::new(Rgb565)));
+    let context = Context::with_plugins(&plugin).unwrap();
+    let transform = Transform::new_multiprofile(&[&identity_link(&context)], 0)
+        .unwrap()
+        .with_formats(rgb_565(), PixelType::RGB_8)
+        .unwrap();
+
+    let pixels: Vec<u8> = [0xf800u16, 0x07e0, 0x001f]
+        .iter()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+    let mut out = [0u8; 9];
+    transform.transform_buffer(&pixels, &mut out, 3).unwrap();
+    assert_eq!([255, 0, 0, 0, 255, 0, 0, 0, 255], out);
+
+    // Built-in formats still work alongside the plugin
+    let back = transform
+        .clone()
+        .with_formats(PixelType::RGB_8, rgb_565())
+        .unwrap();
+    let mut packed = [0u8; 6];
+    back.transform_buffer(&out, &mut packed, 3).unwrap();
+    assert_eq!(&pixels[..], &packed[..]);
+}
@@ -0,0 +1,19 @@
+use crate::pipeline::{Stage, StageLoc};
+use crate::signatures::{color_space, profile_class, tag};
+use crate::tag_types::TagData;
+use crate::{Context, Pipeline, Profile};
+
+/// RGB to RGB device link doing nothing
+pub(super) fn identity_link(context: &Context) -> Profile {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    assert!(pipeline.insert_stage(StageLoc::AtEnd, Stage::new_identity_curves(3).unwrap()));
+
+    let mut profile = Profile::new_with_context(context);
+    profile.device_class = profile_class::LINK;
+    profile.color_space = color_space::RGB;
+    profile.pcs = color_space::RGB;
+    profile
+        .write_tag(tag::A_TO_B0, TagData::Pipeline(pipeline))
+        .unwrap();
+    profile
+}
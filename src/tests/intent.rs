@@ -0,0 +1,90 @@
+use std::io::{ErrorKind, Result};
+
+use super::helpers::identity_link;
+use crate::pipeline::{Stage, StageLoc};
+use crate::plugin::{default_icc_intents, Plugin, PluginBase, RenderingIntent};
+use crate::{Context, Intent, Pipeline, Profile, Transform};
+
+const PRESERVE_NEUTRALS: u32 = 0x100;
+
+/// Relative colorimetric, then every pixel turned to the gray of its average
+fn link_neutrals(
+    context: &Context,
+    intents: &[u32],
+    profiles: &[&Profile],
+    bpc: &[bool],
+    adaptation_states: &[f64],
+) -> Result<Pipeline> {
+    assert_eq!(&[PRESERVE_NEUTRALS], intents);
+    assert_eq!(&[true], bpc);
+    assert_eq!(&[0.5], adaptation_states);
+
+    let mut result = default_icc_intents(context, &[1], profiles, bpc, adaptation_states)?;
+    let third = 1.0 / 3.0;
+    let average = Stage::new_matrix(3, 3, &[third; 9], None).unwrap();
+    assert!(result.insert_stage(StageLoc::AtEnd, average));
+    Ok(result)
+}
+
+fn neutrals_plugin() -> PluginBase<'static> {
+    PluginBase::new(Plugin::RenderingIntent(RenderingIntent {
+        intent: PRESERVE_NEUTRALS,
+        description: "Preserve neutrals",
+        link: link_neutrals,
+    }))
+}
+
+#[test]
+fn test_plugin_intent_links_profiles() {
+    let context = Context::with_plugins(&neutrals_plugin()).unwrap();
+    let link = identity_link(&context);
+
+    let transform = Transform::new_extended_with_context(
+        &context,
+        &[&link],
+        &[PRESERVE_NEUTRALS],
+        &[true],
+        &[0.5],
+    )
+    .unwrap();
+    assert_eq!(PRESERVE_NEUTRALS, transform.rendering_intent());
+
+    let mut out = [0u16; 3];
+    transform.transform_16(&[0x3000, 0x6000, 0x9000], &mut out);
+    assert_eq!([0x6000; 3], out);
+}
+
+#[test]
+fn test_unknown_intents_are_rejected() {
+    let link = identity_link(&Context::new());
+    let result = Transform::new_multiprofile(&[&link], PRESERVE_NEUTRALS);
+    assert_eq!(ErrorKind::InvalidInput, result.unwrap_err().kind());
+
+    assert!(Transform::new_multiprofile(&[&link], Intent::Saturation.into()).is_ok());
+}
+
+#[test]
+fn test_supported_intents_list_plugins() {
+    assert_eq!(
+        vec![
+            (Intent::Perceptual, "Perceptual"),
+            (Intent::RelativeColorimetric, "Relative colorimetric"),
+            (Intent::Saturation, "Saturation"),
+            (Intent::AbsoluteColorimetric, "Absolute colorimetric"),
+        ],
+        Intent::supported(&Context::new())
+    );
+
+    let context = Context::with_plugins(&neutrals_plugin()).unwrap();
+    let intents = Intent::supported(&context);
+    assert_eq!(5, intents.len());
+    assert_eq!(
+        (Intent::Custom(PRESERVE_NEUTRALS), "Preserve neutrals"),
+        intents[4]
+    );
+
+    let intent = Intent::from(PRESERVE_NEUTRALS);
+    assert_eq!(Some("Preserve neutrals"), intent.description(&context));
+    assert_eq!(None, intent.description(&Context::new()));
+    assert_eq!(Some("Perceptual"), Intent::Perceptual.description(&context));
+}
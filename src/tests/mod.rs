@@ -0,0 +1,7 @@
+mod context;
+mod formatter;
+mod helpers;
+mod intent;
+mod tone_curve;
+mod transform;
+mod white_point;
@@ -0,0 +1,82 @@
+use crate::tone_curve::MINUS_INF;
+use crate::{CurveSegment, ToneCurve};
+
+const SRGB_PARAMS: [f64; 5] = [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045];
+
+#[test]
+fn test_gamma_one_is_linear_with_two_entries() {
+    let curve = ToneCurve::build_gamma(1.0).unwrap();
+
+    assert_eq!(2, curve.n_entries());
+    assert!(curve.is_linear());
+    assert_eq!(Some(1), curve.parametric_type());
+}
+
+#[test]
+fn test_gamma_curve_evaluates_power() {
+    let curve = ToneCurve::build_gamma(2.2).unwrap();
+
+    assert_eq!(4096, curve.n_entries());
+    assert!((curve.eval_f32(0.5) - 0.5f32.powf(2.2)).abs() < 1e-6);
+    let expected = (0.5f64.powf(2.2) * 65535.0) as i32;
+    assert!((curve.eval_u16(0x8000) as i32 - expected).abs() <= 8);
+}
+
+#[test]
+fn test_srgb_parametric_curve_and_its_inverse() {
+    let curve = ToneCurve::build_parametric(4, &SRGB_PARAMS).unwrap();
+    let inverse = ToneCurve::build_parametric(-4, &SRGB_PARAMS).unwrap();
+
+    for x in [0.0f32, 0.02, 0.04045, 0.2, 0.5, 1.0].iter() {
+        let y = curve.eval_f32(*x);
+        assert!((inverse.eval_f32(y) - x).abs() < 1e-5, "{} gave {}", x, y);
+    }
+    assert!((curve.eval_f32(0.02) - 0.02 / 12.92).abs() < 1e-7);
+}
+
+#[test]
+fn test_parametric_curve_needs_known_type_and_params() {
+    assert!(ToneCurve::build_parametric(9, &[1.0; 10]).is_none());
+    assert!(ToneCurve::build_parametric(4, &[1.0; 3]).is_none());
+}
+
+#[test]
+fn test_float_table_keeps_values_outside_of_range() {
+    let curve = ToneCurve::from_float_table(&[0.25, 0.5, 2.0]).unwrap();
+
+    assert!((curve.eval_f32(0.75) - 1.25).abs() < 1e-6);
+    assert_eq!(2.0, curve.eval_f32(3.0));
+    assert_eq!(0.25, curve.eval_f32(-1.0));
+    // The 16 bit table saturates
+    assert_eq!(0xffff, curve.eval_u16(0xffff));
+}
+
+#[test]
+fn test_segmented_curve_picks_segment_by_domain() {
+    let segments = [
+        CurveSegment {
+            x0: MINUS_INF,
+            x1: 0.5,
+            r#type: 6,
+            params: [1.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            n_grid_points: 0,
+            sampled_points: Vec::new(),
+        },
+        CurveSegment {
+            x0: 0.5,
+            x1: 1.0,
+            r#type: 0,
+            params: [0.0; 10],
+            n_grid_points: 2,
+            sampled_points: vec![1.0, 0.0],
+        },
+    ];
+
+    let curve = ToneCurve::from_segments(&segments).unwrap();
+
+    assert!((curve.eval_f32(0.25) - 0.5).abs() < 1e-6);
+    assert_eq!(1.0, curve.eval_f32(0.5));
+    assert!((curve.eval_f32(0.75) - 0.5).abs() < 1e-6);
+    // Nothing covers values past the last segment
+    assert!(curve.eval_f32(2.0) < -1e20);
+}
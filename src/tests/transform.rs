@@ -0,0 +1,205 @@
+use crate::pipeline::{Stage, StageLoc};
+use crate::signatures::{color_space, profile_class, tag};
+use crate::tag_types::TagData;
+use crate::{Mlu, NamedColorList, Pipeline, Profile, Signature, Transform};
+
+fn named_color_profile() -> Profile {
+    let mut list = NamedColorList::new(3, "", "").unwrap();
+    list.append("White", [0xff00, 0x8000, 0x8000], &[0xffff, 0xffff, 0xffff]);
+    list.append("Black", [0, 0x8000, 0x8000], &[0, 0, 0]);
+    list.append("Blue", [0x3000, 0x9000, 0x2000], &[0, 0, 0xffff]);
+
+    let mut profile = Profile::new();
+    profile.device_class = profile_class::NAMED_COLOR;
+    profile.color_space = color_space::RGB;
+    profile.pcs = color_space::LAB;
+    profile
+        .write_tag(tag::NAMED_COLOR2, TagData::NamedColorList(list))
+        .unwrap();
+    profile
+}
+
+#[test]
+fn test_named_color_transform_gives_device_values() {
+    let profile = Profile::open_mem(&named_color_profile().save_to_mem().unwrap()).unwrap();
+    let transform = Transform::new_named_color(&profile, false).unwrap();
+
+    let mut out = [0u16; 9];
+    transform.transform_16(&[2, 0, 7], &mut out);
+
+    assert_eq!([0, 0, 0xffff, 0xffff, 0xffff, 0xffff, 0, 0, 0], out);
+}
+
+#[test]
+fn test_named_color_transform_gives_v4_lab() {
+    let transform = Transform::new_named_color(&named_color_profile(), true).unwrap();
+    assert_eq!(1, transform.input_channels());
+    assert_eq!(3, transform.output_channels());
+
+    let mut out = [0u16; 6];
+    transform.transform_16(&[0, 1], &mut out);
+
+    assert_eq!([0xffff, 0x8080, 0x8080, 0, 0x8080, 0x8080], out);
+}
+
+#[test]
+fn test_named_color_transform_needs_named_color_profile() {
+    let mut profile = named_color_profile();
+    profile.device_class = profile_class::DISPLAY;
+
+    assert!(Transform::new_named_color(&profile, true).is_err());
+}
+
+fn matrix_pipeline(matrix: &[f64]) -> Pipeline {
+    let mut pipeline = Pipeline::new(3, 3).unwrap();
+    let stages = vec![
+        Stage::new_identity_curves(3).unwrap(),
+        Stage::new_matrix(3, 3, matrix, None).unwrap(),
+        Stage::new_identity_curves(3).unwrap(),
+    ];
+    for stage in stages {
+        assert!(pipeline.insert_stage(StageLoc::AtEnd, stage));
+    }
+    pipeline
+}
+
+/// RGB profile going to XYZ through a matrix
+fn input_profile() -> Profile {
+    let mut profile = Profile::new();
+    profile.device_class = profile_class::INPUT;
+    profile.pcs = color_space::XYZ;
+    profile.manufacturer = Signature::new(b"ACME");
+    profile.model = 42;
+    profile
+        .write_tag(tag::DEVICE_MFG_DESC, TagData::Mlu(Mlu::from("Acme")))
+        .unwrap();
+    profile
+        .write_tag(tag::PROFILE_DESCRIPTION, TagData::Mlu(Mlu::from("Scanner")))
+        .unwrap();
+    // Pipelines hold XYZ scaled by 1 + 32767/32768, so this is about twice the written values
+    let to_xyz = [0.2, 0.15, 0.1, 0.1, 0.3, 0.1, 0.0, 0.05, 0.35];
+    profile
+        .write_pipeline(tag::A_TO_B0, &matrix_pipeline(&to_xyz))
+        .unwrap();
+    profile
+}
+
+/// RGB profile coming from Lab through a matrix, so linking needs an XYZ to Lab conversion
+fn output_profile() -> Profile {
+    let mut profile = Profile::new();
+    profile.pcs = color_space::LAB;
+    profile
+        .write_tag(tag::DEVICE_MODEL_DESC, TagData::Mlu(Mlu::from("Monitor")))
+        .unwrap();
+    profile
+        .write_tag(tag::PROFILE_DESCRIPTION, TagData::Mlu(Mlu::from("Display")))
+        .unwrap();
+    let from_lab = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+    profile
+        .write_pipeline(tag::B_TO_A0, &matrix_pipeline(&from_lab))
+        .unwrap();
+    profile
+}
+
+#[test]
+fn test_transform_links_through_pcs_conversion() {
+    let transform = Transform::new(&input_profile(), &output_profile(), 0).unwrap();
+    assert_eq!(color_space::RGB, transform.entry_color_space());
+    assert_eq!(color_space::RGB, transform.exit_color_space());
+
+    // White maps to the XYZ of about (0.9, 1.0, 0.8)
+    const SCALE: f64 = 1.0 + 32767.0 / 32768.0;
+    let mut out = [0f32; 3];
+    transform.transform_float(&[1.0, 1.0, 1.0], &mut out);
+    let lab = crate::xyz_to_lab(
+        None,
+        crate::CIEXYZ {
+            X: 0.45 * SCALE,
+            Y: 0.5 * SCALE,
+            Z: 0.4 * SCALE,
+        },
+    );
+    assert!((out[0] - (lab.L / 100.0) as f32).abs() < 0.001);
+    assert!((out[1] - ((lab.a + 128.0) / 255.0) as f32).abs() < 0.001);
+    assert!((out[2] - ((lab.b + 128.0) / 255.0) as f32).abs() < 0.001);
+}
+
+#[test]
+fn test_device_link_records_profile_sequence() {
+    let transform = Transform::new(&input_profile(), &output_profile(), 0).unwrap();
+
+    for &version in &[2.1, 4.3] {
+        let link = transform.to_device_link(version).unwrap();
+        let link = Profile::open_mem(&link.save_to_mem().unwrap()).unwrap();
+        assert_eq!(profile_class::LINK, link.device_class);
+        assert_eq!(version >= 4.0, link.has_tag(tag::PROFILE_SEQUENCE_ID));
+
+        let seq = link.read_profile_sequence().unwrap();
+        assert_eq!(2, seq.len());
+        assert_eq!(Signature::new(b"ACME"), seq[0].device_mfg);
+        assert_eq!(Signature::from(42), seq[0].device_model);
+        assert_eq!(
+            Some("Acme".to_string()),
+            seq[0].manufacturer.get("en", "US")
+        );
+        assert_eq!(Some("Monitor".to_string()), seq[1].model.get("en", "US"));
+        if version >= 4.0 {
+            assert_eq!(
+                Some("Scanner".to_string()),
+                seq[0].description.get("en", "US")
+            );
+            assert_eq!(
+                Some("Display".to_string()),
+                seq[1].description.get("en", "US")
+            );
+        }
+
+        // The conversion is sampled into a CLUT
+        let relinked = Transform::new_multiprofile(&[&link], 0).unwrap();
+        let input = [0x8000, 0x4000, 0xc000];
+        let mut expected = [0u16; 3];
+        let mut actual = [0u16; 3];
+        transform.transform_16(&input, &mut expected);
+        relinked.transform_16(&input, &mut actual);
+        for (e, a) in expected.iter().zip(actual.iter()) {
+            assert!(
+                (*e as i32 - *a as i32).abs() < 0x100,
+                "{:?} {:?}",
+                expected,
+                actual
+            );
+        }
+    }
+}
+
+#[test]
+fn test_matrix_shaper_profiles_are_rejected() {
+    let mut profile = output_profile();
+    profile
+        .write_tag(tag::RED_TRC, TagData::ToneCurve(crate::ToneCurve::linear()))
+        .unwrap();
+    assert!(profile.remove_tag(tag::B_TO_A0));
+
+    let error = Transform::new(&input_profile(), &profile, 0).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidInput, error.kind());
+    assert!(error.to_string().contains("matrix-shaper"));
+}
+
+#[test]
+fn test_formats_keep_the_original_pipeline() {
+    let transform = Transform::new(&input_profile(), &output_profile(), 0).unwrap();
+    let original = transform.pipeline().clone();
+    let mut expected = [0u16; 3];
+    transform.transform_16(&[0x1234, 0x8000, 0xff00], &mut expected);
+
+    let transform = transform
+        .with_formats(crate::PixelType::RGB_8, crate::PixelType::RGB_8)
+        .unwrap()
+        .with_formats(crate::PixelType::RGB_16, crate::PixelType::RGB_16)
+        .unwrap();
+    assert_eq!(&original, transform.pipeline());
+
+    let mut out = [0u16; 3];
+    transform.transform_16(&[0x1234, 0x8000, 0xff00], &mut out);
+    assert_eq!(expected, out);
+}
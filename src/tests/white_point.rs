@@ -0,0 +1,213 @@
+use crate::white_point::*;
+use crate::{d50, illuminant_type, CIExyY, CIExyYTripple, CIEXYZ};
+
+const D65: CIExyY = CIExyY {
+    x: 0.3127,
+    y: 0.3290,
+    Y: 1.0,
+};
+
+fn xy(x: f64, y: f64) -> CIExyY {
+    CIExyY { x, y, Y: 1.0 }
+}
+
+fn assert_xyz(expected: CIEXYZ, actual: CIEXYZ, tolerance: f64) {
+    assert!(
+        (expected.X - actual.X).abs() < tolerance
+            && (expected.Y - actual.Y).abs() < tolerance
+            && (expected.Z - actual.Z).abs() < tolerance,
+        "Expected {:?} but was {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn test_build_colorants_for_srgb() {
+    let primaries = CIExyYTripple {
+        red: xy(0.64, 0.33),
+        green: xy(0.30, 0.60),
+        blue: xy(0.15, 0.06),
+    };
+
+    let colorants = build_colorants(D65, &primaries).unwrap();
+
+    assert_xyz(
+        CIEXYZ {
+            X: 0.4361,
+            Y: 0.2225,
+            Z: 0.0139,
+        },
+        colorants.red,
+        1e-4,
+    );
+    assert_xyz(
+        CIEXYZ {
+            X: 0.3851,
+            Y: 0.7169,
+            Z: 0.0971,
+        },
+        colorants.green,
+        1e-4,
+    );
+    assert_xyz(
+        CIEXYZ {
+            X: 0.1430,
+            Y: 0.0606,
+            Z: 0.7139,
+        },
+        colorants.blue,
+        1e-4,
+    );
+}
+
+#[test]
+fn test_colorants_add_up_to_d50() {
+    // Display P3
+    let primaries = CIExyYTripple {
+        red: xy(0.680, 0.320),
+        green: xy(0.265, 0.690),
+        blue: xy(0.150, 0.060),
+    };
+
+    let c = build_colorants(D65, &primaries).unwrap();
+
+    assert_xyz(
+        d50::XYZ,
+        CIEXYZ {
+            X: c.red.X + c.green.X + c.blue.X,
+            Y: c.red.Y + c.green.Y + c.blue.Y,
+            Z: c.red.Z + c.green.Z + c.blue.Z,
+        },
+        1e-10,
+    );
+}
+
+#[test]
+fn test_build_colorants_fails_on_degenerate_primaries() {
+    let primaries = CIExyYTripple {
+        red: xy(0.3, 0.3),
+        green: xy(0.3, 0.3),
+        blue: xy(0.15, 0.06),
+    };
+
+    assert!(build_colorants(D65, &primaries).is_none());
+}
+
+#[test]
+fn test_adapt_to_illuminant_maps_white_to_illuminant() {
+    let source = CIEXYZ::from(D65);
+
+    let result = adapt_to_illuminant(source, d50::XYZ, source).unwrap();
+
+    assert_xyz(d50::XYZ, result, 1e-10);
+}
+
+#[test]
+fn test_adaptation_matrix_to_same_white_is_identity() {
+    let matrix = adaptation_matrix(None, d50::XYZ, d50::XYZ).unwrap();
+
+    assert!(matrix.is_identity());
+}
+
+#[test]
+fn test_white_point_from_temp_on_daylight_locus() {
+    let white = white_point_from_temp(6504.0).unwrap();
+
+    assert!((white.x - 0.3127).abs() < 1e-4);
+    assert!((white.y - 0.3291).abs() < 1e-4);
+    assert_eq!(1.0, white.Y);
+}
+
+#[test]
+fn test_white_point_from_temp_out_of_range() {
+    assert!(white_point_from_temp(3999.0).is_none());
+    assert!(white_point_from_temp(25001.0).is_none());
+    assert!(planckian_white_point_from_temp(1000.0).is_none());
+}
+
+#[test]
+fn test_planckian_white_point_matches_illuminant_a() {
+    let expected = illuminant_white_point(illuminant_type::A).unwrap();
+
+    let white = planckian_white_point_from_temp(2856.0).unwrap();
+
+    assert!((white.x - expected.x).abs() < 1e-3);
+    assert!((white.y - expected.y).abs() < 1e-3);
+}
+
+#[test]
+fn test_temp_from_white_point_round_trips() {
+    for temp in &[4000.0, 5003.0, 6504.0, 9300.0, 15000.0] {
+        let white = white_point_from_temp(*temp).unwrap();
+
+        let result = temp_from_white_point(white).unwrap();
+
+        assert!(
+            (result - temp).abs() / temp < 0.005,
+            "Expected {} but was {}",
+            temp,
+            result
+        );
+    }
+}
+
+#[test]
+fn test_temp_from_planckian_white_points() {
+    for temp in &[2856.0, 3000.0, 3077.0, 3200.0] {
+        let white = planckian_white_point_from_temp(*temp).unwrap();
+
+        let result = temp_from_white_point(white).unwrap();
+
+        assert!(
+            (result - temp).abs() / temp < 0.005,
+            "Expected {} but was {}",
+            temp,
+            result
+        );
+    }
+}
+
+#[test]
+fn test_temp_from_illuminant_white_points() {
+    let d65 = illuminant_white_point(illuminant_type::D65).unwrap();
+    let d50 = illuminant_white_point(illuminant_type::D50).unwrap();
+
+    assert!((temp_from_white_point(d65).unwrap() - 6504.0).abs() < 10.0);
+    assert!((temp_from_white_point(d50).unwrap() - 5003.0).abs() < 10.0);
+    assert!(illuminant_white_point(illuminant_type::UNKNOWN).is_none());
+}
+
+fn srgb_primaries() -> CIExyYTripple {
+    CIExyYTripple {
+        red: xy(0.64, 0.33),
+        green: xy(0.30, 0.60),
+        blue: xy(0.15, 0.06),
+    }
+}
+
+#[test]
+fn test_build_vcgt_for_native_white_is_gamma() {
+    let curves = build_vcgt(&srgb_primaries(), D65, D65, 1.0).unwrap();
+
+    for curve in curves.iter() {
+        assert!((curve.eval_f32(1.0) - 1.0).abs() < 1e-4);
+        assert!((curve.eval_f32(0.5) - 0.5).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn test_build_vcgt_lowers_blue_for_warmer_white() {
+    let target = white_point_from_temp(5000.0).unwrap();
+    let curves = build_vcgt(&srgb_primaries(), D65, target, 2.2).unwrap();
+
+    let gains: Vec<f32> = curves.iter().map(|c| c.eval_f32(1.0)).collect();
+    assert!((gains[0] - 1.0).abs() < 1e-4);
+    assert!(gains[1] < 1.0 && gains[2] < gains[1]);
+    assert!((curves[0].eval_f32(0.5) - 0.5f32.powf(2.2)).abs() < 1e-4);
+}
+
+#[test]
+fn test_build_vcgt_fails_out_of_gamut() {
+    assert!(build_vcgt(&srgb_primaries(), D65, xy(0.1, 0.8), 1.0).is_none());
+}
@@ -0,0 +1,536 @@
+use crate::internal::{quantize_val, quick_saturate_word, MATRIX_DET_TOLERANCE};
+use crate::plugin::interpolation::{lerp_table_16, lerp_table_float};
+use crate::plugin::ParametricCurveEvaluator;
+use crate::{Context, CurveSegment};
+
+/// Maximum number of entries of a tabulated curve
+pub const MAX_TABLE_ENTRIES: usize = 65530;
+
+/// Lower end of the domain of curves
+pub const MINUS_INF: f32 = -1e22;
+
+/// Upper end of the domain of curves
+pub const PLUS_INF: f32 = 1e22;
+
+/// Number of parameters of a built-in parametric curve type, the negative types being the inverses
+pub fn parametric_param_count(r#type: i32) -> Option<usize> {
+    match r#type.abs() {
+        1 => Some(1),
+        2 => Some(3),
+        3 => Some(4),
+        4 => Some(5),
+        5 => Some(7),
+        6 => Some(4),
+        7 => Some(5),
+        8 => Some(5),
+        108 => Some(1),
+        _ => None,
+    }
+}
+
+/// Evaluates a built-in parametric curve
+pub fn eval_parametric(r#type: i32, params: &[f64], r: f64) -> f64 {
+    let p = |i: usize| params.get(i).copied().unwrap_or(0.0);
+    let near_zero = |v: f64| v.abs() < MATRIX_DET_TOLERANCE;
+
+    match r#type {
+        // X = Y ^ Gamma
+        1 => {
+            if r < 0.0 {
+                if (p(0) - 1.0).abs() < MATRIX_DET_TOLERANCE {
+                    r
+                } else {
+                    0.0
+                }
+            } else {
+                r.powf(p(0))
+            }
+        }
+        // Type 1 reversed: X = Y ^ 1/gamma
+        -1 => {
+            if r < 0.0 {
+                if (p(0) - 1.0).abs() < MATRIX_DET_TOLERANCE {
+                    r
+                } else {
+                    0.0
+                }
+            } else if near_zero(p(0)) {
+                PLUS_INF as f64
+            } else {
+                r.powf(1.0 / p(0))
+            }
+        }
+        // CIE 122-1966
+        // Y = (aX + b)^Gamma  | X >= -b/a
+        // Y = 0               | else
+        2 => {
+            if near_zero(p(1)) {
+                return 0.0;
+            }
+            let disc = -p(2) / p(1);
+            if r >= disc {
+                let e = p(1) * r + p(2);
+                if e > 0.0 {
+                    e.powf(p(0))
+                } else {
+                    0.0
+                }
+            } else {
+                0.0
+            }
+        }
+        // Type 2 reversed
+        // X = (Y ^1/g  - b) / a
+        -2 => {
+            if near_zero(p(0)) || near_zero(p(1)) || r < 0.0 {
+                0.0
+            } else {
+                ((r.powf(1.0 / p(0)) - p(2)) / p(1)).max(0.0)
+            }
+        }
+        // IEC 61966-3
+        // Y = (aX + b)^Gamma + c | X <= -b/a
+        // Y = c                  | else
+        3 => {
+            if near_zero(p(1)) {
+                return 0.0;
+            }
+            let disc = (-p(2) / p(1)).max(0.0);
+            if r >= disc {
+                let e = p(1) * r + p(2);
+                if e > 0.0 {
+                    e.powf(p(0)) + p(3)
+                } else {
+                    0.0
+                }
+            } else {
+                p(3)
+            }
+        }
+        // Type 3 reversed
+        // X = ((Y-c)^1/g - b)/a    | (Y>=c)
+        // X = -b/a                 | (Y<c)
+        -3 => {
+            if near_zero(p(1)) {
+                return 0.0;
+            }
+            if r >= p(3) {
+                let e = r - p(3);
+                if e > 0.0 {
+                    (e.powf(1.0 / p(0)) - p(2)) / p(1)
+                } else {
+                    0.0
+                }
+            } else {
+                -p(2) / p(1)
+            }
+        }
+        // IEC 61966-2.1 (sRGB)
+        // Y = (aX + b)^Gamma | X >= d
+        // Y = cX             | X < d
+        4 => {
+            if r >= p(4) {
+                let e = p(1) * r + p(2);
+                if e > 0.0 {
+                    e.powf(p(0))
+                } else {
+                    0.0
+                }
+            } else {
+                r * p(3)
+            }
+        }
+        // Type 4 reversed
+        // X = ((Y^1/g-b)/a)    | Y >= (ad+b)^g
+        // X = Y/c              | Y < (ad+b)^g
+        -4 => {
+            let e = p(1) * p(4) + p(2);
+            let disc = if e < 0.0 { 0.0 } else { e.powf(p(0)) };
+
+            if r >= disc {
+                if near_zero(p(0)) || near_zero(p(1)) {
+                    0.0
+                } else {
+                    (r.powf(1.0 / p(0)) - p(2)) / p(1)
+                }
+            } else if near_zero(p(3)) {
+                0.0
+            } else {
+                r / p(3)
+            }
+        }
+        // Y = (aX + b)^Gamma + e | X >= d
+        // Y = cX + f             | X < d
+        5 => {
+            if r >= p(4) {
+                let e = p(1) * r + p(2);
+                if e > 0.0 {
+                    e.powf(p(0)) + p(5)
+                } else {
+                    p(5)
+                }
+            } else {
+                r * p(3) + p(6)
+            }
+        }
+        // Type 5 reversed
+        // X = ((Y-e)1/g-b)/a   | Y >= (ad+b)^g+e), cd+f
+        // X = (Y-f)/c          | else
+        -5 => {
+            let disc = p(3) * p(4) + p(6);
+            if r >= disc {
+                let e = r - p(5);
+                if e < 0.0 || near_zero(p(0)) || near_zero(p(1)) {
+                    0.0
+                } else {
+                    (e.powf(1.0 / p(0)) - p(2)) / p(1)
+                }
+            } else if near_zero(p(3)) {
+                0.0
+            } else {
+                (r - p(6)) / p(3)
+            }
+        }
+        // Types 6, 7 and 8 come from segmented curves
+        // Y = (a * X + b) ^ Gamma + c
+        6 => {
+            let e = p(1) * r + p(2);
+            if e < 0.0 {
+                p(3)
+            } else {
+                e.powf(p(0)) + p(3)
+            }
+        }
+        // ((Y - c) ^1/Gamma - b) / a
+        -6 => {
+            if near_zero(p(0)) || near_zero(p(1)) {
+                return 0.0;
+            }
+            let e = r - p(3);
+            if e < 0.0 {
+                0.0
+            } else {
+                (e.powf(1.0 / p(0)) - p(2)) / p(1)
+            }
+        }
+        // Y = a * log (b * X^Gamma + c) + d
+        7 => {
+            let e = p(2) * r.powf(p(0)) + p(3);
+            if e <= 0.0 {
+                p(4)
+            } else {
+                p(1) * e.log10() + p(4)
+            }
+        }
+        // pow((pow(10, (Y-d) / a) - c) / b, 1/g) = X
+        -7 => {
+            if near_zero(p(0)) || near_zero(p(1)) || near_zero(p(2)) {
+                0.0
+            } else {
+                ((10f64.powf((r - p(4)) / p(1)) - p(3)) / p(2)).powf(1.0 / p(0))
+            }
+        }
+        // Y = a * b^(c*X+d) + e
+        8 => p(0) * p(1).powf(p(2) * r + p(3)) + p(4),
+        // Y = (log((y-e) / a) / log(b) - d ) / c
+        -8 => {
+            let disc = r - p(4);
+            if disc < 0.0 || near_zero(p(0)) || near_zero(p(2)) {
+                0.0
+            } else {
+                ((disc / p(0)).ln() / p(1).ln() - p(3)) / p(2)
+            }
+        }
+        // S-Shaped: (1 - (1-x)^1/g)^1/g
+        108 => {
+            if near_zero(p(0)) {
+                0.0
+            } else {
+                (1.0 - (1.0 - r).powf(1.0 / p(0))).powf(1.0 / p(0))
+            }
+        }
+        // 1 - (1 - y^g)^g
+        -108 => 1.0 - (1.0 - r.powf(p(0))).powf(p(0)),
+        _ => 0.0,
+    }
+}
+
+/// How a segment is evaluated, the built-in way or by a plugin
+#[derive(Copy, Clone, Debug)]
+struct SegmentEval {
+    param_count: usize,
+    eval: ParametricCurveEvaluator,
+}
+
+impl SegmentEval {
+    const SAMPLED: Self = Self {
+        param_count: 0,
+        eval: eval_parametric,
+    };
+
+    fn find(context: &Context, r#type: i32) -> Option<Self> {
+        if r#type == 0 {
+            return Some(Self::SAMPLED);
+        }
+        if let Some((param_count, eval)) = context.plugins().parametric_curve(r#type) {
+            return Some(Self { param_count, eval });
+        }
+        Some(Self {
+            param_count: parametric_param_count(r#type)?,
+            eval: eval_parametric,
+        })
+    }
+}
+
+/// A tone curve. Curves built from segments keep them for floating point evaluation, and all of them have a 16 bit
+/// table used by 16 bit evaluation.
+#[derive(Clone, Debug)]
+pub struct ToneCurve {
+    segments: Vec<CurveSegment>,
+    /// How each segment is evaluated
+    evals: Vec<SegmentEval>,
+    table16: Vec<u16>,
+}
+
+impl PartialEq for ToneCurve {
+    fn eq(&self, other: &Self) -> bool {
+        self.segments == other.segments && self.table16 == other.table16
+    }
+}
+
+impl ToneCurve {
+    /// Builds a curve from its 16 bit samples. Returns `None` if there are no samples or too many.
+    pub fn from_table16(values: &[u16]) -> Option<Self> {
+        if values.is_empty() || values.len() > MAX_TABLE_ENTRIES {
+            return None;
+        }
+
+        Some(Self {
+            segments: Vec::new(),
+            evals: Vec::new(),
+            table16: values.to_vec(),
+        })
+    }
+
+    /// Builds a curve from floating point samples spread over [0, 1]. The samples are kept as a single sampled
+    /// segment.
+    pub fn from_float_table(values: &[f32]) -> Option<Self> {
+        if values.len() < 2 || values.len() > MAX_TABLE_ENTRIES {
+            return None;
+        }
+
+        Self::from_segments(&[
+            CurveSegment {
+                x0: MINUS_INF,
+                x1: 0.0,
+                r#type: 6,
+                params: [
+                    1.0,
+                    0.0,
+                    0.0,
+                    values[0] as f64,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                ],
+                n_grid_points: 0,
+                sampled_points: Vec::new(),
+            },
+            CurveSegment {
+                x0: 0.0,
+                x1: 1.0,
+                r#type: 0,
+                params: [0.0; 10],
+                n_grid_points: values.len() as u32,
+                sampled_points: values.to_vec(),
+            },
+            CurveSegment {
+                x0: 1.0,
+                x1: PLUS_INF,
+                r#type: 6,
+                params: [
+                    1.0,
+                    0.0,
+                    0.0,
+                    values[values.len() - 1] as f64,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                ],
+                n_grid_points: 0,
+                sampled_points: Vec::new(),
+            },
+        ])
+    }
+
+    /// Builds a curve from segments covering consecutive parts of the domain. Returns `None` if a segment has
+    /// an unknown type or too few sampled points.
+    pub fn from_segments(segments: &[CurveSegment]) -> Option<Self> {
+        Self::from_segments_with_context(&Context::new(), segments)
+    }
+
+    /// Builds a curve from segments, which may use the parametric curve types registered in `context`
+    pub fn from_segments_with_context(
+        context: &Context,
+        segments: &[CurveSegment],
+    ) -> Option<Self> {
+        if segments.is_empty() {
+            return None;
+        }
+        let mut evals = Vec::with_capacity(segments.len());
+        for segment in segments {
+            if segment.r#type == 0
+                && (segment.sampled_points.len() < 2
+                    || segment.sampled_points.len() != segment.n_grid_points as usize)
+            {
+                return None;
+            }
+            evals.push(SegmentEval::find(context, segment.r#type)?);
+        }
+
+        // Identity curves only need two entries
+        let n_grid_points = match segments {
+            [single] if single.r#type == 1 && (single.params[0] - 1.0).abs() < 0.001 => 2,
+            _ => 4096,
+        };
+
+        let mut result = Self {
+            segments: segments.to_vec(),
+            evals,
+            table16: Vec::new(),
+        };
+        result.table16 = (0..n_grid_points)
+            .map(|i| {
+                let r = i as f64 / (n_grid_points - 1) as f64;
+                quick_saturate_word(result.eval_segments(r) * 65535.0)
+            })
+            .collect();
+
+        Some(result)
+    }
+
+    /// Builds one of the built-in parametric curves, see `eval_parametric`
+    pub fn build_parametric(r#type: i32, params: &[f64]) -> Option<Self> {
+        Self::build_parametric_with_context(&Context::new(), r#type, params)
+    }
+
+    /// Builds a parametric curve of a built-in type or one registered in `context`
+    pub fn build_parametric_with_context(
+        context: &Context,
+        r#type: i32,
+        params: &[f64],
+    ) -> Option<Self> {
+        let count = SegmentEval::find(context, r#type)?.param_count;
+        if params.len() < count {
+            return None;
+        }
+
+        let mut segment = CurveSegment {
+            x0: MINUS_INF,
+            x1: PLUS_INF,
+            r#type,
+            params: [0.0; 10],
+            n_grid_points: 0,
+            sampled_points: Vec::new(),
+        };
+        segment.params[..count].copy_from_slice(&params[..count]);
+
+        Self::from_segments_with_context(context, &[segment])
+    }
+
+    /// Builds the curve Y = X ^ gamma
+    pub fn build_gamma(gamma: f64) -> Option<Self> {
+        Self::build_parametric(1, &[gamma])
+    }
+
+    /// The identity curve
+    pub fn linear() -> Self {
+        Self {
+            segments: Vec::new(),
+            evals: Vec::new(),
+            table16: vec![0, 0xffff],
+        }
+    }
+
+    pub fn table16(&self) -> &[u16] {
+        &self.table16
+    }
+
+    pub fn n_entries(&self) -> usize {
+        self.table16.len()
+    }
+
+    /// Segments the curve was built from, empty for tabulated curves
+    pub fn segments(&self) -> &[CurveSegment] {
+        &self.segments
+    }
+
+    /// Type of the curve if it is a single parametric segment
+    pub fn parametric_type(&self) -> Option<i32> {
+        match self.segments.as_slice() {
+            [single] if single.r#type != 0 => Some(single.r#type),
+            _ => None,
+        }
+    }
+
+    /// Parameters of the curve if it is a single parametric segment
+    pub fn params(&self) -> Option<&[f64]> {
+        self.parametric_type()?;
+        Some(&self.segments[0].params[..self.evals[0].param_count])
+    }
+
+    fn eval_segments(&self, r: f64) -> f64 {
+        for (segment, eval) in self.segments.iter().zip(&self.evals).rev() {
+            // Check for domain
+            if r > segment.x0 as f64 && r <= segment.x1 as f64 {
+                let out = if segment.r#type == 0 {
+                    let r1 = (r - segment.x0 as f64) / (segment.x1 - segment.x0) as f64;
+                    lerp_table_float(&segment.sampled_points, r1 as f32) as f64
+                } else {
+                    (eval.eval)(segment.r#type, &segment.params, r)
+                };
+
+                return if out.is_infinite() && out > 0.0 {
+                    PLUS_INF as f64
+                } else if out.is_infinite() {
+                    MINUS_INF as f64
+                } else {
+                    out
+                };
+            }
+        }
+
+        MINUS_INF as f64
+    }
+
+    pub fn eval_u16(&self, v: u16) -> u16 {
+        lerp_table_16(&self.table16, v)
+    }
+
+    /// Evaluates in floating point, using the segments if the curve has them
+    pub fn eval_f32(&self, v: f32) -> f32 {
+        if self.segments.is_empty() {
+            return self.eval_u16(quick_saturate_word(v as f64 * 65535.0)) as f32 / 65535.0;
+        }
+        self.eval_segments(v as f64) as f32
+    }
+
+    /// Whether the curve is close enough to the identity
+    pub fn is_linear(&self) -> bool {
+        let n = self.table16.len() as u32;
+        if n < 2 {
+            return false;
+        }
+
+        self.table16.iter().enumerate().all(|(i, &value)| {
+            let diff = (value as i32 - quantize_val(i as f64, n) as i32).abs();
+            diff <= 0x0f
+        })
+    }
+}
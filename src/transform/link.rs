@@ -0,0 +1,169 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::pipeline::{Stage, StageLoc};
+use crate::signatures::{color_space, profile_class};
+use crate::{Context, Intent, Pipeline, Profile, Signature};
+
+/// Whether values in space `a` can be taken where space `b` is expected
+fn is_compatible_space(a: Signature, b: Signature) -> bool {
+    if a == b {
+        return true;
+    }
+
+    // Four channels
+    let is_4_channels = |s| s == color_space::MCH4 || s == color_space::COLOR4;
+    if (is_4_channels(a) && b == color_space::CMYK) || (a == color_space::CMYK && is_4_channels(b))
+    {
+        return true;
+    }
+
+    // The PCS can be converted
+    (a == color_space::XYZ && b == color_space::LAB)
+        || (a == color_space::LAB && b == color_space::XYZ)
+}
+
+fn mismatch() -> Error {
+    Error::new(ErrorKind::InvalidInput, "ColorSpace mismatch")
+}
+
+/// Converts between the PCS a pipeline gives and the one the next takes
+fn add_conversion(result: &mut Pipeline, in_pcs: Signature, out_pcs: Signature) -> Result<()> {
+    let stage = match (in_pcs, out_pcs) {
+        (color_space::XYZ, color_space::LAB) => Stage::new_xyz_to_lab(),
+        (color_space::LAB, color_space::XYZ) => Stage::new_lab_to_xyz(),
+        (a, b) if a == b || is_compatible_space(a, b) => return Ok(()),
+        _ => return Err(mismatch()),
+    };
+
+    if result.insert_stage(StageLoc::AtEnd, stage) {
+        Ok(())
+    } else {
+        Err(mismatch())
+    }
+}
+
+/// Links the profiles into a single pipeline, using the intent given for each of them. Profiles in the chain
+/// work as input profiles until the PCS is reached, and as output profiles from there. Device links and
+/// abstract profiles are used as they are.
+pub(crate) fn link_profiles(profiles: &[&Profile], intents: &[u32]) -> Result<Pipeline> {
+    if profiles.is_empty() || profiles.len() != intents.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Wrong number of profiles or intents",
+        ));
+    }
+
+    let mut result = Pipeline::new(0, 0).unwrap();
+    let mut current_space = profiles[0].color_space;
+
+    for (profile, intent) in profiles.iter().zip(intents) {
+        let class = profile.device_class;
+        let is_device_link = class == profile_class::LINK || class == profile_class::ABSTRACT;
+        let is_input = current_space != color_space::XYZ && current_space != color_space::LAB;
+
+        let (space_in, space_out) = if is_input || is_device_link {
+            (profile.color_space, profile.pcs)
+        } else {
+            (profile.pcs, profile.color_space)
+        };
+        if !is_compatible_space(space_in, current_space) {
+            return Err(mismatch());
+        }
+
+        let lut = if is_device_link || (class == profile_class::NAMED_COLOR && profiles.len() == 1)
+        {
+            add_conversion(&mut result, current_space, space_in)?;
+            profile.read_devicelink_pipeline(*intent)?
+        } else if is_input {
+            profile.read_input_pipeline(*intent)?
+        } else {
+            add_conversion(&mut result, current_space, space_in)?;
+            profile.read_output_pipeline(*intent)?
+        };
+
+        if !result.cat(&lut) {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Couldn't link the profiles",
+            ));
+        }
+        current_space = space_out;
+    }
+
+    Ok(result)
+}
+
+/// Links the profiles with the ICC intents, the way transforms do when no plugin handles the intent. Meant for
+/// intent plugins building on the standard behavior. Black point compensation and partial adaptation aren't
+/// supported yet, so `bpc` and `adaptation_states` are only checked for length.
+pub fn default_icc_intents(
+    _context: &Context,
+    intents: &[u32],
+    profiles: &[&Profile],
+    bpc: &[bool],
+    adaptation_states: &[f64],
+) -> Result<Pipeline> {
+    if bpc.len() != profiles.len() || adaptation_states.len() != profiles.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Wrong number of black point compensation flags or adaptation states",
+        ));
+    }
+    link_profiles(profiles, intents)
+}
+
+/// Links the profiles with the intent plugin of `context` handling the first intent, or the ICC intents
+pub(crate) fn link(
+    context: &Context,
+    intents: &[u32],
+    profiles: &[&Profile],
+    bpc: &[bool],
+    adaptation_states: &[f64],
+) -> Result<Pipeline> {
+    let intent = *intents.first().ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidInput,
+            "Wrong number of profiles or intents",
+        )
+    })?;
+
+    match (context.plugins().intent(intent), Intent::from(intent)) {
+        (Some(plugin), _) => (plugin.link)(context, intents, profiles, bpc, adaptation_states),
+        (None, Intent::Custom(_)) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unsupported intent '{}'", intent),
+        )),
+        (None, _) => default_icc_intents(context, intents, profiles, bpc, adaptation_states),
+    }
+}
+
+/// Color spaces a chain of profiles takes and gives
+pub(crate) fn chain_color_spaces(profiles: &[&Profile]) -> (Signature, Signature) {
+    let mut entry = profiles[0].color_space;
+    let mut post_space = entry;
+
+    for (i, profile) in profiles.iter().enumerate() {
+        let class = profile.device_class;
+        let is_input = post_space != color_space::XYZ && post_space != color_space::LAB;
+
+        let (space_in, space_out) = if class == profile_class::NAMED_COLOR {
+            let space_out = if profiles.len() > 1 {
+                profile.pcs
+            } else {
+                profile.color_space
+            };
+            (color_space::COLOR1, space_out)
+        } else if is_input || class == profile_class::LINK {
+            (profile.color_space, profile.pcs)
+        } else {
+            (profile.pcs, profile.color_space)
+        };
+
+        if i == 0 {
+            entry = space_in;
+        }
+        post_space = space_out;
+    }
+
+    (entry, post_space)
+}
@@ -0,0 +1,495 @@
+use std::fmt;
+use std::io::{Error, ErrorKind, Result};
+use std::sync::Arc;
+
+use crate::pipeline::{Stage, StageLoc};
+use crate::plugin::{
+    find_pack, find_unroll, FormatSample, PackFn, Stride, TransformWorker, UnrollFn,
+    MAX_STAGE_CHANNELS,
+};
+use crate::signatures::{profile_class, tag};
+use crate::tag_types::TagData;
+use crate::{Context, Mlu, Pipeline, PixelType, Profile, ProfileSequenceDesc, Signature};
+
+mod link;
+pub use link::default_icc_intents;
+
+/// Routines moving pixels between the buffers and the pipeline, or a plugin doing the whole transform
+#[derive(Clone)]
+enum Formatters {
+    Words {
+        unroll: UnrollFn<u16>,
+        pack: PackFn<u16>,
+    },
+    Float {
+        unroll: UnrollFn<f32>,
+        pack: PackFn<f32>,
+    },
+    Worker(Arc<dyn TransformWorker>),
+}
+
+/// Pixel formats of the buffers a transform works on, with the pipeline optimized for them
+#[derive(Clone)]
+struct Formats {
+    input: PixelType,
+    output: PixelType,
+    formatters: Formatters,
+    pipeline: Pipeline,
+}
+
+/// Formatters and the optimized pipeline are picked from the formats, so comparing the formats is enough
+impl PartialEq for Formats {
+    fn eq(&self, other: &Self) -> bool {
+        self.input == other.input && self.output == other.output
+    }
+}
+
+impl fmt::Debug for Formats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Formats")
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Color transform, running a pipeline on each pixel of a buffer. Pixels are interleaved, with the channels of
+/// each pixel next to each other.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Transform {
+    pipeline: Pipeline,
+    entry_color_space: Signature,
+    exit_color_space: Signature,
+    rendering_intent: u32,
+    /// Profiles the transform was built from
+    sequence: Vec<ProfileSequenceDesc>,
+    context: Context,
+    formats: Option<Formats>,
+}
+
+impl Transform {
+    /// Transform from the device values of `input` to the ones of `output`, using the context of `input`
+    pub fn new(input: &Profile, output: &Profile, intent: u32) -> Result<Self> {
+        Self::new_with_context(input.context(), input, output, intent)
+    }
+
+    /// Transform from the device values of `input` to the ones of `output`, using the plugins of `context`
+    pub fn new_with_context(
+        context: &Context,
+        input: &Profile,
+        output: &Profile,
+        intent: u32,
+    ) -> Result<Self> {
+        Self::new_multiprofile_with_context(context, &[input, output], intent)
+    }
+
+    /// Transform going through a chain of profiles, all of them using the same intent. Profiles need lookup
+    /// tables for the intent or the perceptual one. The context of the first profile is used.
+    pub fn new_multiprofile(profiles: &[&Profile], intent: u32) -> Result<Self> {
+        let context = profiles
+            .first()
+            .map(|p| p.context().clone())
+            .unwrap_or_default();
+        Self::new_multiprofile_with_context(&context, profiles, intent)
+    }
+
+    /// Transform going through a chain of profiles, using the plugins of `context`
+    pub fn new_multiprofile_with_context(
+        context: &Context,
+        profiles: &[&Profile],
+        intent: u32,
+    ) -> Result<Self> {
+        let intents = vec![intent; profiles.len()];
+        let bpc = vec![false; profiles.len()];
+        let adaptation_states = vec![1.0; profiles.len()];
+        Self::new_extended_with_context(context, profiles, &intents, &bpc, &adaptation_states)
+    }
+
+    /// Transform going through a chain of profiles, each of them with its own intent, black point compensation
+    /// and adaptation state. The first intent picks how profiles are linked: intents registered by plugins of
+    /// `context` come first, then the ICC ones. Other intents are rejected.
+    pub fn new_extended_with_context(
+        context: &Context,
+        profiles: &[&Profile],
+        intents: &[u32],
+        bpc: &[bool],
+        adaptation_states: &[f64],
+    ) -> Result<Self> {
+        let mut pipeline = link::link(context, intents, profiles, bpc, adaptation_states)?;
+        pipeline.use_interpolators(context);
+        let (entry_color_space, exit_color_space) = link::chain_color_spaces(profiles);
+
+        Ok(Self {
+            pipeline,
+            entry_color_space,
+            exit_color_space,
+            rendering_intent: intents[0],
+            sequence: profiles.iter().map(|p| p.sequence_desc()).collect(),
+            context: context.clone(),
+            formats: None,
+        })
+    }
+
+    /// Sets the layout of the buffers given to `transform_buffer` and `transform_lines`. Formatters registered
+    /// by plugins of the transform context come first. Float formats on either side make the transform work in
+    /// floating point, 16 bits are used otherwise. Those buffers go through a copy of the pipeline optimized for
+    /// the formats, `transform_16`, `transform_float` and `to_device_link` keep using the original one. Workers
+    /// registered by transform plugins take over the whole transform, formatters and optimization included.
+    pub fn with_formats(mut self, input: PixelType, output: PixelType) -> Result<Self> {
+        if input.channels() as usize != self.input_channels()
+            || output.channels() as usize != self.output_channels()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Format channels don't match the transform",
+            ));
+        }
+
+        let mut pipeline = self.pipeline.clone();
+        let worker = self
+            .context
+            .plugins()
+            .transform_worker(&mut pipeline, input, output);
+        if let Some(worker) = worker {
+            self.formats = Some(Formats {
+                input,
+                output,
+                formatters: Formatters::Worker(worker),
+                pipeline,
+            });
+            return Ok(self);
+        }
+
+        pipeline.optimize(&self.context, self.rendering_intent, input, output);
+
+        let formatters = if input.float() || output.float() {
+            find_unroll(&self.context, input)
+                .zip(find_pack(&self.context, output))
+                .map(|(unroll, pack)| Formatters::Float { unroll, pack })
+        } else {
+            find_unroll(&self.context, input)
+                .zip(find_pack(&self.context, output))
+                .map(|(unroll, pack)| Formatters::Words { unroll, pack })
+        };
+        let formatters = formatters
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Unsupported raster format"))?;
+
+        self.formats = Some(Formats {
+            input,
+            output,
+            formatters,
+            pipeline,
+        });
+        Ok(self)
+    }
+
+    /// Transform taking color indices of a named color profile. Indices map to the PCS values of the colors if
+    /// `to_pcs` is set, with Lab in the v4 encoding, or to their device values otherwise. Unknown indices give
+    /// zeros.
+    pub fn new_named_color(profile: &Profile, to_pcs: bool) -> Result<Self> {
+        if profile.device_class != profile_class::NAMED_COLOR {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Profile is not a named color profile",
+            ));
+        }
+
+        Ok(Self {
+            pipeline: profile.read_named_color_pipeline(to_pcs)?,
+            entry_color_space: crate::signatures::color_space::COLOR1,
+            exit_color_space: if to_pcs {
+                profile.pcs
+            } else {
+                profile.color_space
+            },
+            rendering_intent: profile.rendering_intent,
+            sequence: vec![profile.sequence_desc()],
+            context: profile.context().clone(),
+            formats: None,
+        })
+    }
+
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    pub fn input_channels(&self) -> usize {
+        self.pipeline.input_channels()
+    }
+
+    pub fn output_channels(&self) -> usize {
+        self.pipeline.output_channels()
+    }
+
+    pub fn entry_color_space(&self) -> Signature {
+        self.entry_color_space
+    }
+
+    pub fn exit_color_space(&self) -> Signature {
+        self.exit_color_space
+    }
+
+    pub fn rendering_intent(&self) -> u32 {
+        self.rendering_intent
+    }
+
+    /// Context whose plugins the transform uses
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Descriptions of the profiles the transform was built from
+    pub fn sequence(&self) -> &[ProfileSequenceDesc] {
+        &self.sequence
+    }
+
+    /// Builds a device link profile of the given version doing the same as the transform. The profiles the
+    /// transform was built from are recorded in its profile sequence tags.
+    pub fn to_device_link(&self, version: f64) -> Result<Profile> {
+        let mut result = Profile::new_with_context(&self.context);
+        result.set_version(version);
+        result.device_class = profile_class::LINK;
+        result.color_space = self.entry_color_space;
+        result.pcs = self.exit_color_space;
+        result.rendering_intent = self.rendering_intent;
+
+        result.write_tag(
+            tag::PROFILE_DESCRIPTION,
+            TagData::Mlu(Mlu::from("devicelink")),
+        )?;
+        result.write_tag(
+            tag::COPYRIGHT,
+            TagData::Mlu(Mlu::from("No copyright, use freely")),
+        )?;
+
+        // Pipelines the lookup table types can't hold are sampled into a CLUT
+        match result.write_pipeline(tag::A_TO_B0, &self.pipeline) {
+            Err(e) if e.kind() == ErrorKind::InvalidInput => {
+                result.write_pipeline(tag::A_TO_B0, &self.resampled()?)?
+            }
+            other => other?,
+        }
+
+        result.write_profile_sequence(&self.sequence)?;
+        Ok(result)
+    }
+
+    /// The pipeline sampled into a 16 bit CLUT between identity curves
+    fn resampled(&self) -> Result<Pipeline> {
+        let input_channels = self.input_channels();
+        let output_channels = self.output_channels();
+        let grid_points = match input_channels {
+            n if n > 4 => 7,
+            4 => 17,
+            _ => 33,
+        };
+
+        let unsuitable = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Transform can't be stored as a device link",
+            )
+        };
+        let mut clut = Stage::new_clut16(grid_points, input_channels, output_channels, None)
+            .ok_or_else(unsuitable)?;
+        clut.sample_clut16(|input, output| self.pipeline.eval_16(input, output));
+
+        let mut result = Pipeline::new(input_channels, output_channels).ok_or_else(unsuitable)?;
+        let stages = vec![
+            Stage::new_identity_curves(input_channels),
+            Some(clut),
+            Stage::new_identity_curves(output_channels),
+        ];
+        for stage in stages {
+            if !result.insert_stage(StageLoc::AtEnd, stage.ok_or_else(unsuitable)?) {
+                return Err(unsuitable());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Transforms 16 bit pixels. `output` needs room for as many pixels as `input` holds.
+    pub fn transform_16(&self, input: &[u16], output: &mut [u16]) {
+        let pixels = input.chunks_exact(self.input_channels());
+        for (pixel_in, pixel_out) in pixels.zip(output.chunks_exact_mut(self.output_channels())) {
+            self.pipeline.eval_16(pixel_in, pixel_out);
+        }
+    }
+
+    /// Transforms float pixels. `output` needs room for as many pixels as `input` holds.
+    pub fn transform_float(&self, input: &[f32], output: &mut [f32]) {
+        let pixels = input.chunks_exact(self.input_channels());
+        for (pixel_in, pixel_out) in pixels.zip(output.chunks_exact_mut(self.output_channels())) {
+            self.pipeline.eval_float(pixel_in, pixel_out);
+        }
+    }
+
+    /// Layout of the input buffers, if set by `with_formats`
+    pub fn input_format(&self) -> Option<PixelType> {
+        self.formats.as_ref().map(|f| f.input)
+    }
+
+    /// Layout of the output buffers, if set by `with_formats`
+    pub fn output_format(&self) -> Option<PixelType> {
+        self.formats.as_ref().map(|f| f.output)
+    }
+
+    /// Transforms `pixel_count` pixels laid out as set by `with_formats`. Planes of planar buffers follow each
+    /// other. Buffers too small for the pixels are rejected.
+    pub fn transform_buffer(
+        &self,
+        input: &[u8],
+        output: &mut [u8],
+        pixel_count: usize,
+    ) -> Result<()> {
+        let formats = self.formats.as_ref().ok_or_else(no_formats)?;
+        let stride = Stride {
+            bytes_per_line_in: 0,
+            bytes_per_line_out: 0,
+            bytes_per_plane_in: (pixel_count * formats.input.bytes()) as u32,
+            bytes_per_plane_out: (pixel_count * formats.output.bytes()) as u32,
+        };
+        self.transform_lines(input, output, pixel_count, 1, &stride)
+    }
+
+    /// Transforms `line_count` lines of `pixels_per_line` pixels laid out as set by `with_formats`, lines and
+    /// planes being `stride` apart. Buffers too small for the pixels, lines and stride are rejected. Formats
+    /// flagged as optimized are packed the way their plugin decides, so only their lines are checked.
+    pub fn transform_lines(
+        &self,
+        input: &[u8],
+        output: &mut [u8],
+        pixels_per_line: usize,
+        line_count: usize,
+        stride: &Stride,
+    ) -> Result<()> {
+        let formats = self.formats.as_ref().ok_or_else(no_formats)?;
+        let size_in = buffer_size(
+            formats.input,
+            pixels_per_line,
+            line_count,
+            stride.bytes_per_line_in as usize,
+            stride.bytes_per_plane_in as usize,
+        );
+        let size_out = buffer_size(
+            formats.output,
+            pixels_per_line,
+            line_count,
+            stride.bytes_per_line_out as usize,
+            stride.bytes_per_plane_out as usize,
+        );
+        if size_in.is_none_or(|size| size > input.len())
+            || size_out.is_none_or(|size| size > output.len())
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Buffer too small for the pixels",
+            ));
+        }
+
+        let lines = Lines {
+            formats,
+            pixels_per_line,
+            line_count,
+            stride,
+        };
+
+        match &formats.formatters {
+            Formatters::Words { unroll, pack } => {
+                lines.run(*unroll, *pack, input, output, |i, o| {
+                    formats.pipeline.eval_16(i, o)
+                })
+            }
+            Formatters::Float { unroll, pack } => {
+                lines.run(*unroll, *pack, input, output, |i, o| {
+                    formats.pipeline.eval_float(i, o)
+                })
+            }
+            Formatters::Worker(worker) => worker.transform(
+                &formats.pipeline,
+                input,
+                output,
+                pixels_per_line,
+                line_count,
+                stride,
+            ),
+        }
+        Ok(())
+    }
+}
+
+fn no_formats() -> Error {
+    Error::new(ErrorKind::InvalidInput, "Transform has no pixel formats")
+}
+
+/// Bytes the lines of a buffer span, if they fit in memory
+fn buffer_size(
+    format: PixelType,
+    pixels_per_line: usize,
+    line_count: usize,
+    bytes_per_line: usize,
+    bytes_per_plane: usize,
+) -> Option<usize> {
+    if pixels_per_line == 0 || line_count == 0 {
+        return Some(0);
+    }
+
+    let samples = (format.channels() + format.extra()) as usize;
+    let line = if format.optimized() {
+        1
+    } else if format.planar() {
+        let last_plane = samples.saturating_sub(1).checked_mul(bytes_per_plane)?;
+        last_plane.checked_add(pixels_per_line.checked_mul(format.bytes())?)?
+    } else {
+        pixels_per_line.checked_mul(samples * format.bytes())?
+    };
+
+    (line_count - 1)
+        .checked_mul(bytes_per_line)?
+        .checked_add(line)
+}
+
+/// Walks the pixels of the buffers given to `transform_lines`
+struct Lines<'a> {
+    formats: &'a Formats,
+    pixels_per_line: usize,
+    line_count: usize,
+    stride: &'a Stride,
+}
+
+impl Lines<'_> {
+    fn run<T: FormatSample>(
+        &self,
+        unroll: UnrollFn<T>,
+        pack: PackFn<T>,
+        input: &[u8],
+        output: &mut [u8],
+        eval: impl Fn(&[T], &mut [T]),
+    ) {
+        let n_in = self.formats.input.channels() as usize;
+        let n_out = self.formats.output.channels() as usize;
+        let mut values_in = [T::default(); MAX_STAGE_CHANNELS];
+        let mut values_out = [T::default(); MAX_STAGE_CHANNELS];
+
+        for line in 0..self.line_count {
+            let mut pos_in = line * self.stride.bytes_per_line_in as usize;
+            let mut pos_out = line * self.stride.bytes_per_line_out as usize;
+
+            for _ in 0..self.pixels_per_line {
+                pos_in += unroll(
+                    self.formats.input,
+                    &mut values_in,
+                    &input[pos_in..],
+                    self.stride.bytes_per_plane_in as usize,
+                );
+                eval(&values_in[..n_in], &mut values_out[..n_out]);
+                pos_out += pack(
+                    self.formats.output,
+                    &values_out,
+                    &mut output[pos_out..],
+                    self.stride.bytes_per_plane_out as usize,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use crate::plugin::{Mat3, Vec3};
+use crate::{d50, illuminant_type, CIEXYZTriple, CIExyY, CIExyYTripple, ToneCurve, CIEXYZ};
+
+/// Bradford cone response matrix
+pub const BRADFORD: Mat3 = Mat3 {
+    vx: Vec3::new(0.8951, 0.2664, -0.1614),
+    vy: Vec3::new(-0.7502, 1.7135, 0.0367),
+    vz: Vec3::new(0.0389, -0.0685, 1.0296),
+};
+
+fn xyz_to_vec3(value: CIEXYZ) -> Vec3 {
+    Vec3::new(value.X, value.Y, value.Z)
+}
+
+/// Computes a chromatic adaptation matrix using `cone_matrix` as the cone response domain
+fn compute_chromatic_adaptation(
+    source_white_point: CIEXYZ,
+    dest_white_point: CIEXYZ,
+    cone_matrix: Mat3,
+) -> Option<Mat3> {
+    let cone_inv = cone_matrix.inverse()?;
+
+    let cone_source_rgb = cone_matrix.eval(xyz_to_vec3(source_white_point));
+    let cone_dest_rgb = cone_matrix.eval(xyz_to_vec3(dest_white_point));
+
+    // Von Kries scaling in the cone domain
+    let cone = Mat3 {
+        vx: Vec3::new(cone_dest_rgb.x / cone_source_rgb.x, 0.0, 0.0),
+        vy: Vec3::new(0.0, cone_dest_rgb.y / cone_source_rgb.y, 0.0),
+        vz: Vec3::new(0.0, 0.0, cone_dest_rgb.z / cone_source_rgb.z),
+    };
+
+    Some(cone_inv.per(cone.per(cone_matrix)))
+}
+
+/// Returns the matrix adapting colors from `from_illuminant` to `to_illuminant`. Uses Bradford when no cone
+/// matrix is given.
+pub fn adaptation_matrix(
+    cone_matrix: Option<Mat3>,
+    from_illuminant: CIEXYZ,
+    to_illuminant: CIEXYZ,
+) -> Option<Mat3> {
+    compute_chromatic_adaptation(
+        from_illuminant,
+        to_illuminant,
+        cone_matrix.unwrap_or(BRADFORD),
+    )
+}
+
+/// Bradford adapts `matrix` from a white point given in xyY to D50
+pub fn adapt_matrix_to_d50(matrix: Mat3, source_white_point: CIExyY) -> Option<Mat3> {
+    let bradford = adaptation_matrix(None, CIEXYZ::from(source_white_point), d50::XYZ)?;
+
+    Some(bradford.per(matrix))
+}
+
+/// Adapts `value`, whose white point is `source_white_point`, to the given illuminant
+pub fn adapt_to_illuminant(
+    source_white_point: CIEXYZ,
+    illuminant: CIEXYZ,
+    value: CIEXYZ,
+) -> Option<CIEXYZ> {
+    let bradford = adaptation_matrix(None, source_white_point, illuminant)?;
+    let result = bradford.eval(xyz_to_vec3(value));
+
+    Some(CIEXYZ {
+        X: result.x,
+        Y: result.y,
+        Z: result.z,
+    })
+}
+
+/// RGB to XYZ matrix of a set of primaries, relative to their own white point
+fn primaries_to_xyz_matrix(white_point: CIExyY, primaries: &CIExyYTripple) -> Option<Mat3> {
+    let (xn, yn) = (white_point.x, white_point.y);
+    let (xr, yr) = (primaries.red.x, primaries.red.y);
+    let (xg, yg) = (primaries.green.x, primaries.green.y);
+    let (xb, yb) = (primaries.blue.x, primaries.blue.y);
+
+    let primaries = Mat3 {
+        vx: Vec3::new(xr, xg, xb),
+        vy: Vec3::new(yr, yg, yb),
+        vz: Vec3::new(1.0 - xr - yr, 1.0 - xg - yg, 1.0 - xb - yb),
+    };
+    let result = primaries.inverse()?;
+
+    // Scale each primary so that all of them add up to the white point
+    let white = Vec3::new(xn / yn, 1.0, (1.0 - xn - yn) / yn);
+    let coef = result.eval(white);
+
+    Some(Mat3 {
+        vx: Vec3::new(coef.x * xr, coef.y * xg, coef.z * xb),
+        vy: Vec3::new(coef.x * yr, coef.y * yg, coef.z * yb),
+        vz: Vec3::new(
+            coef.x * (1.0 - xr - yr),
+            coef.y * (1.0 - xg - yg),
+            coef.z * (1.0 - xb - yb),
+        ),
+    })
+}
+
+/// Builds the RGB to XYZ matrix of a set of primaries, adapted to D50 as ICC requires.
+///
+/// Columns of the result are the XYZ values of the red, green and blue colorants. Returns `None` if the
+/// primaries are degenerate.
+pub fn build_rgb_to_xyz_transfer_matrix(
+    white_point: CIExyY,
+    primaries: &CIExyYTripple,
+) -> Option<Mat3> {
+    let matrix = primaries_to_xyz_matrix(white_point, primaries)?;
+    adapt_matrix_to_d50(matrix, white_point)
+}
+
+/// Builds the red, green and blue `vcgt` curves making a display with the given primaries and native white
+/// point show `target_white_point`, following `gamma`.
+///
+/// Channels are scaled down so that full drive gives the target white, the brightest channel staying at 1.0.
+/// Returns `None` if the primaries are degenerate or the target can't be reached by the display.
+pub fn build_vcgt(
+    primaries: &CIExyYTripple,
+    native_white_point: CIExyY,
+    target_white_point: CIExyY,
+    gamma: f64,
+) -> Option<[ToneCurve; 3]> {
+    if gamma <= 0.0 || target_white_point.y <= 0.0 {
+        return None;
+    }
+
+    let matrix = primaries_to_xyz_matrix(native_white_point, primaries)?;
+    let target = CIEXYZ::from(CIExyY {
+        Y: 1.0,
+        ..target_white_point
+    });
+    let rgb = matrix.inverse()?.eval(xyz_to_vec3(target));
+
+    let max = rgb.x.max(rgb.y).max(rgb.z);
+    let gains = [rgb.x / max, rgb.y / max, rgb.z / max];
+    if gains.iter().any(|g| g.is_nan() || *g <= 0.0) {
+        return None;
+    }
+
+    // Y = gain * X ^ gamma, the formula form of the vcgt type
+    let curve = |gain: f64| {
+        ToneCurve::build_parametric(5, &[gamma, gain.powf(1.0 / gamma), 0.0, 0.0, 0.0, 0.0, 0.0])
+    };
+    Some([curve(gains[0])?, curve(gains[1])?, curve(gains[2])?])
+}
+
+/// Computes the D50 adapted `RED_COLORANT`, `GREEN_COLORANT` and `BLUE_COLORANT` values of a set of
+/// primaries. The white point luminance is ignored and taken as 1.0.
+pub fn build_colorants(white_point: CIExyY, primaries: &CIExyYTripple) -> Option<CIEXYZTriple> {
+    let max_white = CIExyY {
+        Y: 1.0,
+        ..white_point
+    };
+    let m = build_rgb_to_xyz_transfer_matrix(max_white, primaries)?;
+
+    Some(CIEXYZTriple {
+        red: CIEXYZ {
+            X: m.vx.x,
+            Y: m.vy.x,
+            Z: m.vz.x,
+        },
+        green: CIEXYZ {
+            X: m.vx.y,
+            Y: m.vy.y,
+            Z: m.vz.y,
+        },
+        blue: CIEXYZ {
+            X: m.vx.z,
+            Y: m.vy.z,
+            Z: m.vz.z,
+        },
+    })
+}
+
+/// Obtains the chromaticity of a point on the CIE daylight locus. Valid from 4000K to 25000K.
+pub fn white_point_from_temp(temp_k: f64) -> Option<CIExyY> {
+    let t = temp_k;
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    let x = if (4000.0..=7000.0).contains(&t) {
+        -4.6070 * (1e9 / t3) + 2.9678 * (1e6 / t2) + 0.09911 * (1e3 / t) + 0.244063
+    } else if t > 7000.0 && t <= 25000.0 {
+        -2.0064 * (1e9 / t3) + 1.9018 * (1e6 / t2) + 0.24748 * (1e3 / t) + 0.237040
+    } else {
+        return None;
+    };
+
+    let y = -3.000 * (x * x) + 2.870 * x - 0.275;
+
+    Some(CIExyY { x, y, Y: 1.0 })
+}
+
+/// Obtains the chromaticity of a black body radiator, using the Kim et al. cubic spline approximation of the
+/// Planckian locus. Valid from 1667K to 25000K.
+pub fn planckian_white_point_from_temp(temp_k: f64) -> Option<CIExyY> {
+    let t = temp_k;
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    let x = if (1667.0..=4000.0).contains(&t) {
+        -0.2661239 * (1e9 / t3) - 0.2343589 * (1e6 / t2) + 0.8776956 * (1e3 / t) + 0.179910
+    } else if t > 4000.0 && t <= 25000.0 {
+        -3.0258469 * (1e9 / t3) + 2.1070379 * (1e6 / t2) + 0.2226347 * (1e3 / t) + 0.240390
+    } else {
+        return None;
+    };
+
+    let x2 = x * x;
+    let x3 = x2 * x;
+
+    let y = if t <= 2222.0 {
+        -1.1063814 * x3 - 1.34811020 * x2 + 2.18555832 * x - 0.20219683
+    } else if t <= 4000.0 {
+        -0.9549476 * x3 - 1.37418593 * x2 + 2.09137015 * x - 0.16748867
+    } else {
+        3.0817580 * x3 - 5.87338670 * x2 + 3.75112997 * x - 0.37001483
+    };
+
+    Some(CIExyY { x, y, Y: 1.0 })
+}
+
+struct IsoTemperature {
+    /// Temperature in microreciprocal kelvin
+    mirek: f64,
+    /// u coord of intersection with the blackbody locus
+    ut: f64,
+    /// v coord of intersection with the blackbody locus
+    vt: f64,
+    /// Slope of the isotemperature line
+    tt: f64,
+}
+
+macro_rules! iso {
+    ($mirek:expr, $ut:expr, $vt:expr, $tt:expr) => {
+        IsoTemperature {
+            mirek: $mirek,
+            ut: $ut,
+            vt: $vt,
+            tt: $tt,
+        }
+    };
+}
+
+const ISOTEMP_DATA: [IsoTemperature; 31] = [
+    iso!(0.0, 0.18006, 0.26352, -0.24341),
+    iso!(10.0, 0.18066, 0.26589, -0.25479),
+    iso!(20.0, 0.18133, 0.26846, -0.26876),
+    iso!(30.0, 0.18208, 0.27119, -0.28539),
+    iso!(40.0, 0.18293, 0.27407, -0.30470),
+    iso!(50.0, 0.18388, 0.27709, -0.32675),
+    iso!(60.0, 0.18494, 0.28021, -0.35156),
+    iso!(70.0, 0.18611, 0.28342, -0.37915),
+    iso!(80.0, 0.18740, 0.28668, -0.40955),
+    iso!(90.0, 0.18880, 0.28997, -0.44278),
+    iso!(100.0, 0.19032, 0.29326, -0.47888),
+    iso!(125.0, 0.19462, 0.30141, -0.58204),
+    iso!(150.0, 0.19962, 0.30921, -0.70471),
+    iso!(175.0, 0.20525, 0.31647, -0.84901),
+    iso!(200.0, 0.21142, 0.32312, -1.0182),
+    iso!(225.0, 0.21807, 0.32909, -1.2168),
+    iso!(250.0, 0.22511, 0.33439, -1.4512),
+    iso!(275.0, 0.23247, 0.33904, -1.7298),
+    iso!(300.0, 0.24010, 0.34308, -2.0637),
+    iso!(325.0, 0.24792, 0.34655, -2.4681),
+    iso!(350.0, 0.25591, 0.34951, -2.9641),
+    iso!(375.0, 0.26400, 0.35200, -3.5814),
+    iso!(400.0, 0.27218, 0.35407, -4.3633),
+    iso!(425.0, 0.28039, 0.35577, -5.3762),
+    iso!(450.0, 0.28863, 0.35714, -6.7262),
+    iso!(475.0, 0.29685, 0.35823, -8.5955),
+    iso!(500.0, 0.30505, 0.35907, -11.324),
+    iso!(525.0, 0.31320, 0.35968, -15.628),
+    iso!(550.0, 0.32129, 0.36011, -23.325),
+    iso!(575.0, 0.32931, 0.36038, -40.770),
+    iso!(600.0, 0.33724, 0.36051, -116.45),
+];
+
+/// Estimates the correlated color temperature of a white point using Robertson's method. Returns `None` if
+/// the white point falls outside the isotemperature table.
+pub fn temp_from_white_point(white_point: CIExyY) -> Option<f64> {
+    let (xs, ys) = (white_point.x, white_point.y);
+
+    // Convert (x, y) to CIE 1960 (u, v)
+    let us = (2.0 * xs) / (-xs + 6.0 * ys + 1.5);
+    let vs = (3.0 * ys) / (-xs + 6.0 * ys + 1.5);
+
+    let mut di = 0.0;
+    let mut mi = 0.0;
+
+    for (j, iso) in ISOTEMP_DATA.iter().enumerate() {
+        let dj = ((vs - iso.vt) - iso.tt * (us - iso.ut)) / (1.0 + iso.tt * iso.tt).sqrt();
+
+        if j != 0 && di / dj < 0.0 {
+            return Some(1000000.0 / (mi + (di / (di - dj)) * (iso.mirek - mi)));
+        }
+
+        di = dj;
+        mi = iso.mirek;
+    }
+
+    None
+}
+
+/// Chromaticity of the CIE 1931 2° standard illuminants listed in `illuminant_type`
+pub fn illuminant_white_point(illuminant: u32) -> Option<CIExyY> {
+    let (x, y) = match illuminant {
+        illuminant_type::D50 => (0.34567, 0.35850),
+        illuminant_type::D65 => (0.31271, 0.32902),
+        illuminant_type::D93 => (0.28315, 0.29711),
+        illuminant_type::F2 => (0.37208, 0.37529),
+        illuminant_type::D55 => (0.33242, 0.34743),
+        illuminant_type::A => (0.44757, 0.40745),
+        illuminant_type::E => (1.0 / 3.0, 1.0 / 3.0),
+        illuminant_type::F8 => (0.34588, 0.35875),
+        _ => return None,
+    };
+
+    Some(CIExyY { x, y, Y: 1.0 })
+}